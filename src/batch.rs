@@ -0,0 +1,449 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::changes::ChangeList;
+use super::config::Config;
+use super::file::{File, NoProgress, SaveMode};
+use super::hex;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Batch edit command.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Set bytes at the offset: `set OFFSET HEXBYTES`.
+    Set(u64, Vec<u8>),
+    /// Fill the range with a pattern: `fill RANGE PATTERN`.
+    Fill(Range<u64>, Vec<u8>),
+    /// Insert bytes filled with a pattern: `insert OFFSET LENGTH [PATTERN]`.
+    Insert(u64, u64, Vec<u8>),
+    /// Cut out the range: `cut RANGE`.
+    Cut(Range<u64>),
+    /// Replace all entries of the sequence: `replace NEEDLE WITH`.
+    Replace(Vec<u8>, Vec<u8>),
+}
+
+impl Command {
+    /// Parse command from the text line.
+    ///
+    /// Offsets and lengths are decimal or hexadecimal (`0x` prefix or hex digits),
+    /// ranges are `START..END` (end excluded) or `START+LENGTH`, byte
+    /// sequences are hex strings (`deadbeef`).
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - command text
+    ///
+    /// # Return value
+    ///
+    /// Command or error description.
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let args: Vec<&str> = text.split_whitespace().collect();
+        let (name, args) = args.split_first().ok_or("Empty command")?;
+        let expect = |count: Range<usize>| {
+            if count.contains(&args.len()) {
+                Ok(())
+            } else {
+                Err(format!("Invalid number of arguments for {}", name))
+            }
+        };
+        match *name {
+            "set" => {
+                expect(2..3)?;
+                Ok(Command::Set(parse_number(args[0])?, parse_bytes(args[1])?))
+            }
+            "fill" => {
+                expect(2..3)?;
+                Ok(Command::Fill(parse_range(args[0])?, parse_bytes(args[1])?))
+            }
+            "insert" => {
+                expect(2..4)?;
+                let pattern = match args.get(2) {
+                    Some(pattern) => parse_bytes(pattern)?,
+                    None => vec![0],
+                };
+                let length = parse_number(args[1])?;
+                if length == 0 {
+                    return Err("Nothing to insert".to_string());
+                }
+                Ok(Command::Insert(parse_number(args[0])?, length, pattern))
+            }
+            "cut" => {
+                expect(1..2)?;
+                Ok(Command::Cut(parse_range(args[0])?))
+            }
+            "replace" => {
+                expect(2..3)?;
+                Ok(Command::Replace(
+                    parse_bytes(args[0])?,
+                    parse_bytes(args[1])?,
+                ))
+            }
+            _ => Err(format!("Unknown command: {}", name)),
+        }
+    }
+}
+
+/// Parse numeric value.
+fn parse_number(text: &str) -> std::result::Result<u64, String> {
+    hex::parse_number(text).ok_or_else(|| format!("Invalid number: {}", text))
+}
+
+/// Parse range: `START..END` or `START+LENGTH`.
+fn parse_range(text: &str) -> std::result::Result<Range<u64>, String> {
+    let range = if let Some((start, end)) = text.split_once("..") {
+        parse_number(start)?..parse_number(end)?
+    } else if let Some((start, length)) = text.split_once('+') {
+        let start = parse_number(start)?;
+        let end = start
+            .checked_add(parse_number(length)?)
+            .ok_or_else(|| format!("Invalid range: {}", text))?;
+        start..end
+    } else {
+        return Err(format!("Invalid range: {}", text));
+    };
+    if range.is_empty() {
+        return Err(format!("Empty range: {}", text));
+    }
+    Ok(range)
+}
+
+/// Parse sequence of bytes from hex string.
+fn parse_bytes(text: &str) -> std::result::Result<Vec<u8>, String> {
+    hex::parse_bytes(text).ok_or_else(|| format!("Invalid hex sequence: {}", text))
+}
+
+/// Headless editor: applies commands to the file.
+/// Byte modifications are accumulated in the change list. Insert and cut
+/// operations change the file size, so they are applied to a working copy of
+/// the file which replaces the original one on save. The original file stays
+/// intact until the whole batch succeeds.
+pub struct Batch {
+    /// Original file, locked until the batch is closed.
+    origin: File,
+    /// Working copy of the file, created on the first resize operation.
+    copy: Option<File>,
+    /// Change list.
+    changes: ChangeList,
+}
+
+impl Batch {
+    /// Open file for batch editing.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the file
+    /// * `config` - app configuration
+    ///
+    /// # Return value
+    ///
+    /// Batch editor instance.
+    pub fn open(path: &Path, config: &Config) -> Result<Self> {
+        let mut origin = File::open(path)?;
+        origin.save_mode = config.save_mode;
+        origin.backup = config.backup;
+        if let Some(holder) = origin.lock() {
+            return Err(Error::new(
+                ErrorKind::WouldBlock,
+                format!("File is locked by {}", holder),
+            ));
+        }
+        Ok(Self {
+            origin,
+            copy: None,
+            changes: ChangeList::default(),
+        })
+    }
+
+    /// Execute the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - command to execute
+    pub fn execute(&mut self, command: &Command) -> Result<()> {
+        match command {
+            Command::Set(offset, data) => {
+                self.check_range(&(*offset..offset.saturating_add(data.len() as u64)))?;
+                for (index, &value) in data.iter().enumerate() {
+                    self.change(offset + index as u64, value)?;
+                }
+            }
+            Command::Fill(range, pattern) => {
+                self.check_range(range)?;
+                for (offset, &value) in range.clone().zip(pattern.iter().cycle()) {
+                    self.change(offset, value)?;
+                }
+            }
+            Command::Insert(offset, length, pattern) => {
+                if *offset > self.file().size {
+                    return Err(Batch::out_of_range());
+                }
+                self.resizable()?
+                    .insert(*offset, *length, pattern, &mut NoProgress)?;
+            }
+            Command::Cut(range) => {
+                self.check_range(range)?;
+                self.resizable()?.cut(range, &mut NoProgress)?;
+            }
+            Command::Replace(needle, with) => self.replace(needle, with)?,
+        }
+        self.file().changes = self.changes.get();
+        Ok(())
+    }
+
+    /// Write the result to the original file and close the batch.
+    pub fn save(mut self) -> Result<()> {
+        self.file().changes = self.changes.get();
+        if let Some(mut copy) = self.copy.take() {
+            let status = Batch::commit(&self.origin, &mut copy);
+            std::fs::remove_file(&copy.path).ok();
+            status
+        } else if self.origin.is_modified() {
+            self.origin.save(&mut NoProgress)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Replace the original file with the working copy.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - original file
+    /// * `copy` - working copy
+    fn commit(origin: &File, copy: &mut File) -> Result<()> {
+        if copy.is_modified() {
+            copy.save(&mut NoProgress)?;
+        }
+        let mode = origin.effective_save_mode();
+        origin.make_backup(mode)?;
+        match mode {
+            SaveMode::InPlace => std::fs::copy(&copy.path, &origin.path).map(|_| ()),
+            SaveMode::Atomic => {
+                origin.copy_attributes(Path::new(&copy.path))?;
+                std::fs::rename(&copy.path, &origin.path)
+            }
+        }
+    }
+
+    /// Get the currently edited file: the working copy if it exists.
+    fn file(&mut self) -> &mut File {
+        self.copy.as_mut().unwrap_or(&mut self.origin)
+    }
+
+    /// Prepare the working copy for an operation that changes the file size,
+    /// pending changes are written to it.
+    ///
+    /// # Return value
+    ///
+    /// Working copy of the file.
+    fn resizable(&mut self) -> Result<&mut File> {
+        self.file().changes = self.changes.get();
+        if let Some(copy) = &mut self.copy {
+            if copy.is_modified() {
+                copy.save(&mut NoProgress)?;
+            }
+        } else {
+            self.origin.check_raw()?;
+            // the copy must be in the same directory to be renamed atomically
+            let path = PathBuf::from(&self.origin.path);
+            let name = path.file_name().unwrap().to_string_lossy();
+            let tmp = path.with_file_name(format!(".{}.xvi-batch-{}", name, std::process::id()));
+            let range = 0..self.origin.size;
+            let status = self
+                .origin
+                .export(&range, &tmp, false, &mut NoProgress)
+                .and_then(|()| {
+                    let permissions = std::fs::metadata(&path)?.permissions();
+                    std::fs::set_permissions(&tmp, permissions)
+                })
+                .and_then(|()| File::open(&tmp));
+            match status {
+                Ok(copy) => self.copy = Some(copy),
+                Err(err) => {
+                    std::fs::remove_file(&tmp).ok();
+                    return Err(err);
+                }
+            }
+        }
+        self.changes.reset();
+        Ok(self.copy.as_mut().unwrap())
+    }
+
+    /// Replace all entries of the sequence.
+    ///
+    /// # Arguments
+    ///
+    /// * `needle` - sequence to find
+    /// * `with` - replacement
+    fn replace(&mut self, needle: &[u8], with: &[u8]) -> Result<()> {
+        let mut offset = 0;
+        while offset + needle.len() as u64 <= self.file().size {
+            // find the next entry, the search wraps around the end of file
+            let head = self.file().read(offset, needle.len())?;
+            let found = if head == needle {
+                offset
+            } else {
+                match self.file().find(offset, needle, false, &mut NoProgress) {
+                    Ok(found) if found > offset => found,
+                    Ok(_) => break,
+                    Err(err) if err.kind() == ErrorKind::NotFound => break,
+                    Err(err) => return Err(err),
+                }
+            };
+
+            // overwrite the common part
+            let common = needle.len().min(with.len());
+            for (index, &value) in with[..common].iter().enumerate() {
+                self.change(found + index as u64, value)?;
+            }
+            self.file().changes = self.changes.get();
+
+            // resize the entry
+            let tail = found + common as u64;
+            if with.len() > needle.len() {
+                let length = (with.len() - needle.len()) as u64;
+                self.resizable()?
+                    .insert(tail, length, &with[common..], &mut NoProgress)?;
+            } else if with.len() < needle.len() {
+                let length = (needle.len() - with.len()) as u64;
+                self.resizable()?
+                    .cut(&(tail..tail + length), &mut NoProgress)?;
+            }
+
+            offset = found + with.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Change the byte value.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - address of the byte
+    /// * `value` - new value
+    fn change(&mut self, offset: u64, value: u8) -> Result<()> {
+        let old = if let Some(val) = self.changes.last(offset) {
+            val
+        } else {
+            let data = self.file().read(offset, 1)?;
+            *data.first().ok_or_else(Batch::out_of_range)?
+        };
+        if old != value {
+            self.changes.set(offset, old, value);
+        }
+        Ok(())
+    }
+
+    /// Check if the range is inside the file.
+    fn check_range(&mut self, range: &Range<u64>) -> Result<()> {
+        if range.end > self.file().size {
+            Err(Batch::out_of_range())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Create "out of range" error.
+    fn out_of_range() -> Error {
+        Error::new(ErrorKind::InvalidInput, "Out of file range")
+    }
+}
+
+impl Drop for Batch {
+    fn drop(&mut self) {
+        // the working copy is not needed if the batch was not saved
+        if let Some(copy) = &self.copy {
+            std::fs::remove_file(&copy.path).ok();
+        }
+    }
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        Command::parse("set 0x10 deadbeef"),
+        Ok(Command::Set(0x10, vec![0xde, 0xad, 0xbe, 0xef]))
+    );
+    assert_eq!(
+        Command::parse("fill 2..4 ff"),
+        Ok(Command::Fill(2..4, vec![0xff]))
+    );
+    assert_eq!(
+        Command::parse(" fill  0x10+16   0102 "),
+        Ok(Command::Fill(0x10..0x20, vec![1, 2]))
+    );
+    assert_eq!(
+        Command::parse("insert 0 4"),
+        Ok(Command::Insert(0, 4, vec![0]))
+    );
+    assert_eq!(Command::parse("cut 1+1"), Ok(Command::Cut(1..2)));
+    assert_eq!(
+        Command::parse("replace 0a0d 0a"),
+        Ok(Command::Replace(vec![0x0a, 0x0d], vec![0x0a]))
+    );
+
+    assert!(Command::parse("").is_err());
+    assert!(Command::parse("move 1 2").is_err());
+    assert!(Command::parse("set 1").is_err());
+    assert!(Command::parse("set 1 abc").is_err());
+    assert!(Command::parse("set x 00").is_err());
+    assert!(Command::parse("fill 4..2 00").is_err());
+    assert!(Command::parse("cut 4").is_err());
+    assert!(Command::parse("insert 0 0").is_err());
+}
+
+#[test]
+fn test_execute() {
+    let path = std::env::temp_dir().join("xvi_test_batch");
+    std::fs::write(&path, b"0123456789").unwrap();
+
+    let config = Config::default();
+    let mut batch = Batch::open(&path, &config).unwrap();
+    let commands = [
+        "set 0 4142",
+        "fill 2+2 2e",
+        "insert 4 3 78",
+        "cut 0x0b..12",
+        "replace 3739 5a5a5a",
+        "replace 2e2e 2d",
+    ];
+    for command in commands {
+        batch.execute(&Command::parse(command).unwrap()).unwrap();
+    }
+    // the original file is untouched and locked until the batch is saved
+    assert_eq!(std::fs::read(&path).unwrap(), b"0123456789");
+    assert!(batch.origin.is_locked());
+    batch.save().unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"AB-xxx456ZZZ");
+
+    // failed batch leaves the file intact
+    let mut batch = Batch::open(&path, &config).unwrap();
+    batch.execute(&Command::parse("cut 0+2").unwrap()).unwrap();
+    let err = batch
+        .execute(&Command::parse("set 14 00").unwrap())
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(batch.execute(&Command::parse("cut 10+5").unwrap()).is_err());
+    drop(batch);
+    assert_eq!(std::fs::read(&path).unwrap(), b"AB-xxx456ZZZ");
+    let name = path.file_name().unwrap().to_string_lossy().to_string();
+    let copy = path.with_file_name(format!(".{}.xvi-batch-{}", name, std::process::id()));
+    assert!(!copy.exists());
+
+    // atomic save
+    let config = Config {
+        save_mode: SaveMode::Atomic,
+        ..Config::default()
+    };
+    let mut batch = Batch::open(&path, &config).unwrap();
+    batch
+        .execute(&Command::parse("insert 0 1 31").unwrap())
+        .unwrap();
+    batch.save().unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"1AB-xxx456ZZZ");
+    assert!(!copy.exists());
+
+    std::fs::remove_file(path).unwrap();
+}
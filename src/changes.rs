@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
 
 /// Modification of single byte.
 #[derive(Copy, Clone)]
@@ -9,6 +10,8 @@ pub struct ByteChange {
     pub offset: u64,
     pub old: u8,
     pub new: u8,
+    /// Group of changes that are undone as a single operation.
+    group: Option<usize>,
 }
 
 /// List of bytes modifications.
@@ -17,6 +20,13 @@ pub struct ChangeList {
     changes: Vec<ByteChange>,
     /// Current position in the queue.
     index: usize,
+    /// Currently open group.
+    group: Option<usize>,
+    /// Number of created groups.
+    groups: usize,
+    /// Position in the queue and the redo tail at the start of the open
+    /// group, restored if the group is discarded.
+    redo: Option<(usize, Vec<ByteChange>)>,
 }
 
 impl ChangeList {
@@ -30,7 +40,7 @@ impl ChangeList {
     pub fn set(&mut self, offset: u64, old: u8, new: u8) {
         // try to update the last changed value if it in the same offset
         if let Some(last) = self.changes.last_mut() {
-            if last.offset == offset {
+            if last.offset == offset && last.group == self.group {
                 last.new = new;
                 return;
             }
@@ -38,29 +48,116 @@ impl ChangeList {
 
         // reset forward changes by removing the tail
         if self.index != 0 {
-            self.changes.truncate(self.index);
+            self.truncate_redo();
         }
 
-        self.changes.push(ByteChange { offset, old, new });
+        self.changes.push(ByteChange {
+            offset,
+            old,
+            new,
+            group: self.group,
+        });
         self.index = self.changes.len();
     }
 
+    /// Make changes of the bytes block: set new values for the bytes that
+    /// differ from the origin ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - address of the first byte of the block
+    /// * `old` - origin values of the bytes
+    /// * `new` - new values of the bytes
+    ///
+    /// # Return value
+    ///
+    /// Number of changed bytes.
+    pub fn set_block(&mut self, offset: u64, old: &[u8], new: &[u8]) -> usize {
+        debug_assert_eq!(old.len(), new.len());
+
+        // reset forward changes by removing the tail
+        self.truncate_redo();
+
+        let group = self.group;
+        self.changes.extend(
+            old.iter()
+                .zip(new.iter())
+                .enumerate()
+                .filter(|(_, (old, new))| old != new)
+                .map(|(index, (&old, &new))| ByteChange {
+                    offset: offset + index as u64,
+                    old,
+                    new,
+                    group,
+                }),
+        );
+        let changed = self.changes.len() - self.index;
+        self.index = self.changes.len();
+        changed
+    }
+
+    /// Start group of changes: all changes made until `end_group` call are
+    /// undone and redone as a single operation.
+    pub fn begin_group(&mut self) {
+        self.groups += 1;
+        self.group = Some(self.groups);
+        self.redo = Some((self.index, Vec::new()));
+    }
+
+    /// Finish the current group of changes.
+    pub fn end_group(&mut self) {
+        self.group = None;
+        self.redo = None;
+    }
+
+    /// Discard all changes made in the currently open group, the redo queue
+    /// is restored.
+    pub fn discard_group(&mut self) {
+        if let (Some(group), Some((index, redo))) = (self.group, self.redo.take()) {
+            let len = self
+                .changes
+                .iter()
+                .rposition(|ch| ch.group != Some(group))
+                .map_or(0, |index| index + 1);
+            self.changes.truncate(len);
+            self.changes.extend(redo);
+            self.index = index;
+        }
+    }
+
+    /// Remove forward changes, the removed tail is kept until the end of
+    /// the open group.
+    fn truncate_redo(&mut self) {
+        let tail = self.changes.split_off(self.index);
+        if let Some((_, redo)) = &mut self.redo {
+            if redo.is_empty() {
+                *redo = tail;
+            }
+        }
+    }
+
     /// Get the map of real changes.
     ///
     /// Returns map with chages: offset -> value.
     pub fn get(&self) -> BTreeMap<u64, u8> {
+        self.diff()
+            .into_iter()
+            .map(|(offset, (_, new))| (offset, new))
+            .collect()
+    }
+
+    /// Get the map of real changes with origin values.
+    ///
+    /// Returns map with chages: offset -> (origin value, new value).
+    pub fn diff(&self) -> BTreeMap<u64, (u8, u8)> {
         let mut real = BTreeMap::new();
-        let mut origins = BTreeMap::new();
         for change in self.changes[0..self.index].iter() {
-            origins.entry(change.offset).or_insert(change.old);
-            real.insert(change.offset, change.new);
+            real.entry(change.offset)
+                .or_insert((change.old, change.new))
+                .1 = change.new;
         }
         // remove changes that restore origin values
-        for (offset, origin) in &origins {
-            if origin == real.get(offset).unwrap() {
-                real.remove(offset);
-            }
-        }
+        real.retain(|_, (old, new)| old != new);
         real
     }
 
@@ -82,27 +179,40 @@ impl ChangeList {
         None
     }
 
-    /// Undo the last change.
+    /// Undo the last change or group of changes.
     ///
-    /// Returns description of the undone change.
+    /// Returns description of the undone change (the first one in group).
     pub fn undo(&mut self) -> Option<ByteChange> {
         if self.changes.is_empty() || self.index == 0 {
             None
         } else {
             self.index -= 1;
+            if let Some(group) = self.changes[self.index].group {
+                while self.index > 0 && self.changes[self.index - 1].group == Some(group) {
+                    self.index -= 1;
+                }
+            }
             Some(self.changes[self.index])
         }
     }
 
-    /// Redo the next change.
+    /// Redo the next change or group of changes.
     ///
-    /// Returns description of the applied change.
+    /// Returns description of the applied change (the first one in group).
     pub fn redo(&mut self) -> Option<ByteChange> {
         if self.changes.is_empty() || self.index == self.changes.len() {
             None
         } else {
+            let change = self.changes[self.index];
             self.index += 1;
-            Some(self.changes[self.index - 1])
+            if let Some(group) = change.group {
+                while self.index < self.changes.len()
+                    && self.changes[self.index].group == Some(group)
+                {
+                    self.index += 1;
+                }
+            }
+            Some(change)
         }
     }
 
@@ -110,6 +220,7 @@ impl ChangeList {
     pub fn reset(&mut self) {
         self.changes.clear();
         self.index = 0;
+        self.redo = None;
     }
 }
 
@@ -118,8 +229,41 @@ impl Default for ChangeList {
         Self {
             changes: Vec::with_capacity(64),
             index: 0,
+            group: None,
+            groups: 0,
+            redo: None,
+        }
+    }
+}
+
+/// Get ranges of the data that differ between two sets of changes.
+///
+/// # Arguments
+///
+/// * `old` - previous changes: offset -> value
+/// * `new` - current changes: offset -> value
+///
+/// # Return value
+///
+/// Sorted list of the changed ranges.
+pub fn changed_ranges(old: &BTreeMap<u64, u8>, new: &BTreeMap<u64, u8>) -> Vec<Range<u64>> {
+    let offsets: BTreeSet<u64> = old
+        .iter()
+        .filter(|(offset, value)| new.get(offset) != Some(value))
+        .chain(
+            new.iter()
+                .filter(|(offset, value)| old.get(offset) != Some(value)),
+        )
+        .map(|(&offset, _)| offset)
+        .collect();
+    let mut ranges: Vec<Range<u64>> = Vec::new();
+    for offset in offsets {
+        match ranges.last_mut() {
+            Some(last) if last.end == offset => last.end += 1,
+            _ => ranges.push(offset..offset + 1),
         }
     }
+    ranges
 }
 
 #[test]
@@ -135,6 +279,10 @@ fn test_changesqueue() {
     assert_eq!(*real.get(&0x1234).unwrap(), 2);
     assert_eq!(*real.get(&0x1235).unwrap(), 6);
 
+    let diff = ch.diff();
+    assert_eq!(diff.get(&0x1234), Some(&(1, 2)));
+    assert_eq!(diff.get(&0x1235), Some(&(3, 6)));
+
     ch.set(0x1234, 2, 1); // restore origin
     let real = ch.get();
     assert_eq!(real.len(), 1);
@@ -154,3 +302,117 @@ fn test_changesqueue() {
     ch.reset();
     assert_eq!(ch.get().len(), 0);
 }
+
+#[test]
+fn test_group() {
+    let mut ch = ChangeList::default();
+
+    ch.set(0, 1, 2);
+    ch.begin_group();
+    ch.set(0, 2, 3);
+    ch.set(1, 1, 3);
+    ch.set(2, 1, 3);
+    ch.end_group();
+    ch.set(2, 3, 4);
+    assert_eq!(ch.get().len(), 3);
+
+    assert_eq!(ch.undo().unwrap().offset, 2);
+    assert_eq!(*ch.get().get(&2).unwrap(), 3);
+    let change = ch.undo().unwrap();
+    assert_eq!((change.offset, change.old), (0, 2));
+    assert_eq!(ch.get().len(), 1);
+    assert_eq!(*ch.get().get(&0).unwrap(), 2);
+
+    assert_eq!(ch.redo().unwrap().offset, 0);
+    assert_eq!(ch.get().len(), 3);
+    assert_eq!(*ch.get().get(&0).unwrap(), 3);
+
+    // discarded group doesn't affect the redo queue and previous changes
+    ch.begin_group();
+    ch.discard_group();
+    ch.end_group();
+    assert_eq!(ch.redo().unwrap().offset, 2);
+    ch.begin_group();
+    ch.set(5, 0, 1);
+    ch.set(6, 0, 1);
+    ch.discard_group();
+    ch.end_group();
+    assert_eq!(ch.get().len(), 3);
+    assert_eq!(ch.undo().unwrap().offset, 2);
+}
+
+#[test]
+fn test_block() {
+    let mut ch = ChangeList::default();
+
+    ch.set(0, 1, 2);
+    ch.set(1, 1, 2);
+    ch.undo();
+    ch.begin_group();
+    assert_eq!(ch.set_block(2, &[1, 2, 3, 4], &[1, 5, 3, 6]), 2);
+    assert_eq!(ch.set_block(6, &[1, 2], &[1, 2]), 0);
+    ch.end_group();
+    assert_eq!(
+        ch.diff().into_iter().collect::<Vec<_>>(),
+        [(0, (1, 2)), (3, (2, 5)), (5, (4, 6))]
+    );
+
+    // block is undone as a single operation, the redo tail is reset
+    assert_eq!(ch.undo().unwrap().offset, 3);
+    assert_eq!(ch.get().len(), 1);
+    assert_eq!(ch.undo().unwrap().offset, 0);
+    assert!(ch.undo().is_none());
+    assert_eq!(ch.redo().unwrap().offset, 0);
+    assert_eq!(ch.redo().unwrap().offset, 3);
+    assert!(ch.redo().is_none());
+}
+
+#[test]
+fn test_discard() {
+    let mut ch = ChangeList::default();
+
+    // failed operation after undo keeps the redo queue
+    ch.set(0, 1, 2);
+    ch.set(1, 1, 2);
+    ch.undo();
+    ch.begin_group();
+    assert_eq!(ch.set_block(4, &[0, 0], &[1, 1]), 2);
+    ch.set(7, 0, 1);
+    ch.discard_group();
+    ch.end_group();
+    assert_eq!(ch.get().into_iter().collect::<Vec<_>>(), [(0, 2)]);
+    assert_eq!(ch.redo().unwrap().offset, 1);
+    assert_eq!(ch.get().len(), 2);
+    assert!(ch.redo().is_none());
+
+    // all changes are undone
+    ch.undo();
+    ch.undo();
+    ch.begin_group();
+    ch.set(5, 0, 1);
+    ch.discard_group();
+    ch.end_group();
+    assert!(ch.get().is_empty());
+    assert_eq!(ch.redo().unwrap().offset, 0);
+    assert_eq!(ch.redo().unwrap().offset, 1);
+    assert!(ch.redo().is_none());
+
+    // the tail is reset by the committed group
+    ch.undo();
+    ch.begin_group();
+    ch.set_block(4, &[0], &[1]);
+    ch.end_group();
+    assert!(ch.redo().is_none());
+    assert_eq!(ch.get().len(), 2);
+}
+
+#[test]
+fn test_changed_ranges() {
+    let old: BTreeMap<u64, u8> = [(1, 1), (2, 2), (3, 3), (10, 0)].iter().copied().collect();
+    let new: BTreeMap<u64, u8> = [(1, 1), (2, 5), (3, 3), (4, 4), (5, 5), (7, 0)]
+        .iter()
+        .copied()
+        .collect();
+    assert_eq!(changed_ranges(&old, &new), vec![2..3, 4..6, 7..8, 10..11]);
+    assert!(changed_ranges(&new, &new).is_empty());
+}
@@ -0,0 +1,788 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::file::{File, ProgressHandler};
+use std::io::Result;
+use std::ops::Range;
+
+/// Checksum or hash algorithm.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Algorithm {
+    /// CRC-8 (polynomial 0x07).
+    Crc8,
+    /// CRC-16/ARC (polynomial 0x8005, reflected).
+    Crc16,
+    /// CRC-16/CCITT-FALSE (polynomial 0x1021, init 0xffff).
+    Crc16Ccitt,
+    /// CRC-16/MODBUS (polynomial 0x8005, reflected, init 0xffff).
+    Crc16Modbus,
+    /// CRC-32 (IEEE 802.3).
+    Crc32,
+    /// CRC-32C (Castagnoli).
+    Crc32c,
+    /// Adler-32.
+    Adler32,
+    /// Fletcher-16.
+    Fletcher16,
+    /// Fletcher-32 (little endian 16-bit words).
+    Fletcher32,
+    /// 8-bit sum of bytes.
+    Sum8,
+    /// 16-bit sum of bytes.
+    Sum16,
+    /// 32-bit sum of bytes.
+    Sum32,
+    /// XOR of bytes.
+    Xor8,
+    /// MD5 hash.
+    Md5,
+    /// SHA-1 hash.
+    Sha1,
+    /// SHA-256 hash.
+    Sha256,
+}
+
+impl Algorithm {
+    /// All available algorithms.
+    pub const ALL: &'static [Algorithm] = &[
+        Algorithm::Crc8,
+        Algorithm::Crc16,
+        Algorithm::Crc16Ccitt,
+        Algorithm::Crc16Modbus,
+        Algorithm::Crc32,
+        Algorithm::Crc32c,
+        Algorithm::Adler32,
+        Algorithm::Fletcher16,
+        Algorithm::Fletcher32,
+        Algorithm::Sum8,
+        Algorithm::Sum16,
+        Algorithm::Sum32,
+        Algorithm::Xor8,
+        Algorithm::Md5,
+        Algorithm::Sha1,
+        Algorithm::Sha256,
+    ];
+
+    /// Get human readable algorithm name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Crc8 => "CRC-8",
+            Algorithm::Crc16 => "CRC-16/ARC",
+            Algorithm::Crc16Ccitt => "CRC-16/CCITT",
+            Algorithm::Crc16Modbus => "CRC-16/MODBUS",
+            Algorithm::Crc32 => "CRC-32",
+            Algorithm::Crc32c => "CRC-32C",
+            Algorithm::Adler32 => "Adler-32",
+            Algorithm::Fletcher16 => "Fletcher-16",
+            Algorithm::Fletcher32 => "Fletcher-32",
+            Algorithm::Sum8 => "Sum-8",
+            Algorithm::Sum16 => "Sum-16",
+            Algorithm::Sum32 => "Sum-32",
+            Algorithm::Xor8 => "XOR-8",
+            Algorithm::Md5 => "MD5",
+            Algorithm::Sha1 => "SHA-1",
+            Algorithm::Sha256 => "SHA-256",
+        }
+    }
+
+    /// Check if the algorithm is a hash function: its value is a sequence of
+    /// bytes, not a number, so the byte order can not be changed.
+    pub fn is_hash(self) -> bool {
+        matches!(self, Algorithm::Md5 | Algorithm::Sha1 | Algorithm::Sha256)
+    }
+
+    /// Create calculator state.
+    fn digest(self) -> Box<dyn Digest> {
+        match self {
+            Algorithm::Crc8 => Box::new(Crc::new(8, 0x07, 0, false, 0)),
+            Algorithm::Crc16 => Box::new(Crc::new(16, 0x8005, 0, true, 0)),
+            Algorithm::Crc16Ccitt => Box::new(Crc::new(16, 0x1021, 0xffff, false, 0)),
+            Algorithm::Crc16Modbus => Box::new(Crc::new(16, 0x8005, 0xffff, true, 0)),
+            Algorithm::Crc32 => Box::new(Crc::crc32()),
+            Algorithm::Crc32c => Box::new(Crc::new(32, 0x1edc_6f41, !0, true, !0)),
+            Algorithm::Adler32 => Box::new(Adler32 { a: 1, b: 0 }),
+            Algorithm::Fletcher16 => Box::new(Fletcher16 { a: 0, b: 0 }),
+            Algorithm::Fletcher32 => Box::new(Fletcher32 {
+                a: 0,
+                b: 0,
+                odd: None,
+            }),
+            Algorithm::Sum8 => Box::new(Sum { width: 1, value: 0 }),
+            Algorithm::Sum16 => Box::new(Sum { width: 2, value: 0 }),
+            Algorithm::Sum32 => Box::new(Sum { width: 4, value: 0 }),
+            Algorithm::Xor8 => Box::new(Xor8 { value: 0 }),
+            Algorithm::Md5 => Box::new(Md5::default()),
+            Algorithm::Sha1 => Box::new(Sha1::default()),
+            Algorithm::Sha256 => Box::new(Sha256::default()),
+        }
+    }
+
+    /// Calculate checksum of the file range.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - source file
+    /// * `range` - range to process
+    /// * `progress` - long time operation handler
+    ///
+    /// # Return value
+    ///
+    /// Checksum value, numbers are in big endian byte order.
+    pub fn calculate(
+        self,
+        file: &mut File,
+        range: &Range<u64>,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<Vec<u8>> {
+        const BLOCK_SIZE: usize = 0x10000;
+        let mut digest = self.digest();
+        file.read_blocks(range, BLOCK_SIZE, progress, &mut |_, data| {
+            digest.update(data);
+            Ok(true)
+        })?;
+        Ok(digest.finish())
+    }
+}
+
+/// Checksum calculator state.
+trait Digest {
+    /// Process the next portion of data.
+    fn update(&mut self, data: &[u8]);
+    /// Get the final value, numbers are in big endian byte order.
+    fn finish(&mut self) -> Vec<u8>;
+}
+
+/// Generic CRC calculator (up to 32 bits).
+struct Crc {
+    /// Width of the CRC in bits.
+    width: u32,
+    /// Reflected (LSB first) input and output.
+    reflected: bool,
+    /// Value to XOR the final CRC with.
+    xorout: u32,
+    /// Current value.
+    value: u32,
+    /// Lookup table.
+    table: [u32; 256],
+}
+
+impl Crc {
+    /// Create CRC calculator.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - width of the CRC in bits
+    /// * `poly` - polynomial (normal form)
+    /// * `init` - initial value
+    /// * `reflected` - reflected input and output
+    /// * `xorout` - value to XOR the final CRC with
+    fn new(width: u32, poly: u32, init: u32, reflected: bool, xorout: u32) -> Self {
+        debug_assert!((8..=32).contains(&width));
+        let mask = u32::MAX >> (32 - width);
+        let top = 1 << (width - 1);
+        let rpoly = poly.reverse_bits() >> (32 - width);
+        let mut table = [0; 256];
+        for (index, entry) in table.iter_mut().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let mut crc = if reflected {
+                index as u32
+            } else {
+                (index as u32) << (width - 8)
+            };
+            for _ in 0..8 {
+                crc = if reflected {
+                    if crc & 1 != 0 {
+                        (crc >> 1) ^ rpoly
+                    } else {
+                        crc >> 1
+                    }
+                } else if crc & top != 0 {
+                    (crc << 1) ^ poly
+                } else {
+                    crc << 1
+                };
+            }
+            *entry = crc & mask;
+        }
+        Self {
+            width,
+            reflected,
+            xorout: xorout & mask,
+            value: init & mask,
+            table,
+        }
+    }
+
+    /// Create CRC-32 (IEEE 802.3) calculator.
+    fn crc32() -> Self {
+        Crc::new(32, 0x04c1_1db7, !0, true, !0)
+    }
+}
+
+impl Digest for Crc {
+    fn update(&mut self, data: &[u8]) {
+        let mask = u32::MAX >> (32 - self.width);
+        for &byte in data {
+            self.value = if self.reflected {
+                self.table[((self.value ^ u32::from(byte)) & 0xff) as usize] ^ (self.value >> 8)
+            } else {
+                let index = ((self.value >> (self.width - 8)) ^ u32::from(byte)) & 0xff;
+                (self.table[index as usize] ^ (self.value << 8)) & mask
+            };
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let bytes = (self.width / 8) as usize;
+        (self.value ^ self.xorout).to_be_bytes()[4 - bytes..].to_vec()
+    }
+}
+
+/// Adler-32 calculator.
+struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Digest for Adler32 {
+    fn update(&mut self, data: &[u8]) {
+        const MOD: u32 = 65521;
+        for &byte in data {
+            self.a = (self.a + u32::from(byte)) % MOD;
+            self.b = (self.b + self.a) % MOD;
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        ((self.b << 16) | self.a).to_be_bytes().to_vec()
+    }
+}
+
+/// Fletcher-16 calculator.
+struct Fletcher16 {
+    a: u16,
+    b: u16,
+}
+
+impl Digest for Fletcher16 {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.a = (self.a + u16::from(byte)) % 255;
+            self.b = (self.b + self.a) % 255;
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        ((self.b << 8) | self.a).to_be_bytes().to_vec()
+    }
+}
+
+/// Fletcher-32 calculator.
+struct Fletcher32 {
+    a: u32,
+    b: u32,
+    /// The first byte of incomplete word.
+    odd: Option<u8>,
+}
+
+impl Fletcher32 {
+    /// Process a single 16-bit word.
+    fn word(&mut self, word: u16) {
+        self.a = (self.a + u32::from(word)) % 65535;
+        self.b = (self.b + self.a) % 65535;
+    }
+}
+
+impl Digest for Fletcher32 {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            if let Some(low) = self.odd.take() {
+                self.word(u16::from_le_bytes([low, byte]));
+            } else {
+                self.odd = Some(byte);
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        if let Some(low) = self.odd.take() {
+            self.word(u16::from(low));
+        }
+        ((self.b << 16) | self.a).to_be_bytes().to_vec()
+    }
+}
+
+/// Sum of bytes.
+struct Sum {
+    /// Width of the sum in bytes.
+    width: usize,
+    value: u32,
+}
+
+impl Digest for Sum {
+    fn update(&mut self, data: &[u8]) {
+        self.value = data
+            .iter()
+            .fold(self.value, |sum, &byte| sum.wrapping_add(u32::from(byte)));
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        self.value.to_be_bytes()[4 - self.width..].to_vec()
+    }
+}
+
+/// XOR of bytes.
+struct Xor8 {
+    value: u8,
+}
+
+impl Digest for Xor8 {
+    fn update(&mut self, data: &[u8]) {
+        self.value = data.iter().fold(self.value, |xor, &byte| xor ^ byte);
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        vec![self.value]
+    }
+}
+
+/// Splitter of the data stream to 64-byte blocks for hash functions.
+#[derive(Default)]
+struct Blocks {
+    /// Incomplete block.
+    buffer: Vec<u8>,
+    /// Total length of the data.
+    length: u64,
+}
+
+impl Blocks {
+    /// Size of the block.
+    const SIZE: usize = 64;
+
+    /// Split data to blocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - data to process
+    /// * `process` - block handler
+    fn update(&mut self, mut data: &[u8], process: &mut dyn FnMut(&[u8])) {
+        self.length += data.len() as u64;
+        if !self.buffer.is_empty() {
+            let size = (Blocks::SIZE - self.buffer.len()).min(data.len());
+            self.buffer.extend_from_slice(&data[..size]);
+            data = &data[size..];
+            if self.buffer.len() == Blocks::SIZE {
+                process(&self.buffer);
+                self.buffer.clear();
+            }
+        }
+        let mut chunks = data.chunks_exact(Blocks::SIZE);
+        for block in &mut chunks {
+            process(block);
+        }
+        self.buffer.extend_from_slice(chunks.remainder());
+    }
+
+    /// Add the final padding with the data length in bits.
+    ///
+    /// # Arguments
+    ///
+    /// * `big_endian` - byte order of the length
+    /// * `process` - block handler
+    fn finish(&mut self, big_endian: bool, process: &mut dyn FnMut(&[u8])) {
+        let bits = self.length.wrapping_mul(8);
+        let mut padding = vec![0x80];
+        let tail = (self.buffer.len() + 1) % Blocks::SIZE;
+        let zeros = (Blocks::SIZE + Blocks::SIZE - 8 - tail) % Blocks::SIZE;
+        padding.resize(1 + zeros, 0);
+        if big_endian {
+            padding.extend_from_slice(&bits.to_be_bytes());
+        } else {
+            padding.extend_from_slice(&bits.to_le_bytes());
+        }
+        self.update(&padding, process);
+        debug_assert!(self.buffer.is_empty());
+    }
+}
+
+/// MD5 calculator.
+struct Md5 {
+    state: [u32; 4],
+    blocks: Blocks,
+}
+
+impl Default for Md5 {
+    fn default() -> Self {
+        Self {
+            state: [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476],
+            blocks: Blocks::default(),
+        }
+    }
+}
+
+impl Md5 {
+    /// Process single block.
+    fn block(state: &mut [u32; 4], block: &[u8]) {
+        const SHIFT: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+        let mut words = [0; 16];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        let [mut a, mut b, mut c, mut d] = *state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let k = (((i + 1) as f64).sin().abs() * 4_294_967_296.0) as u32;
+            let f = f.wrapping_add(a).wrapping_add(k).wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(SHIFT[(i / 16) * 4 + i % 4]));
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+}
+
+impl Digest for Md5 {
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.blocks
+            .update(data, &mut |block| Md5::block(state, block));
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks
+            .finish(false, &mut |block| Md5::block(state, block));
+        self.state.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+}
+
+/// SHA-1 calculator.
+struct Sha1 {
+    state: [u32; 5],
+    blocks: Blocks,
+}
+
+impl Default for Sha1 {
+    fn default() -> Self {
+        Self {
+            state: [
+                0x6745_2301,
+                0xefcd_ab89,
+                0x98ba_dcfe,
+                0x1032_5476,
+                0xc3d2_e1f0,
+            ],
+            blocks: Blocks::default(),
+        }
+    }
+}
+
+impl Sha1 {
+    /// Process single block.
+    fn block(state: &mut [u32; 5], block: &[u8]) {
+        let mut words = [0; 80];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = *state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5a82_7999),
+                1 => (b ^ c ^ d, 0x6ed9_eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1b_bcdc),
+                _ => (b ^ c ^ d, 0xca62_c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+}
+
+impl Digest for Sha1 {
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.blocks
+            .update(data, &mut |block| Sha1::block(state, block));
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks
+            .finish(true, &mut |block| Sha1::block(state, block));
+        self.state.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+}
+
+/// SHA-256 calculator.
+struct Sha256 {
+    state: [u32; 8],
+    blocks: Blocks,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self {
+            state: [
+                0x6a09_e667,
+                0xbb67_ae85,
+                0x3c6e_f372,
+                0xa54f_f53a,
+                0x510e_527f,
+                0x9b05_688c,
+                0x1f83_d9ab,
+                0x5be0_cd19,
+            ],
+            blocks: Blocks::default(),
+        }
+    }
+}
+
+impl Sha256 {
+    /// Round constants.
+    const K: [u32; 64] = [
+        0x428a_2f98,
+        0x7137_4491,
+        0xb5c0_fbcf,
+        0xe9b5_dba5,
+        0x3956_c25b,
+        0x59f1_11f1,
+        0x923f_82a4,
+        0xab1c_5ed5,
+        0xd807_aa98,
+        0x1283_5b01,
+        0x2431_85be,
+        0x550c_7dc3,
+        0x72be_5d74,
+        0x80de_b1fe,
+        0x9bdc_06a7,
+        0xc19b_f174,
+        0xe49b_69c1,
+        0xefbe_4786,
+        0x0fc1_9dc6,
+        0x240c_a1cc,
+        0x2de9_2c6f,
+        0x4a74_84aa,
+        0x5cb0_a9dc,
+        0x76f9_88da,
+        0x983e_5152,
+        0xa831_c66d,
+        0xb003_27c8,
+        0xbf59_7fc7,
+        0xc6e0_0bf3,
+        0xd5a7_9147,
+        0x06ca_6351,
+        0x1429_2967,
+        0x27b7_0a85,
+        0x2e1b_2138,
+        0x4d2c_6dfc,
+        0x5338_0d13,
+        0x650a_7354,
+        0x766a_0abb,
+        0x81c2_c92e,
+        0x9272_2c85,
+        0xa2bf_e8a1,
+        0xa81a_664b,
+        0xc24b_8b70,
+        0xc76c_51a3,
+        0xd192_e819,
+        0xd699_0624,
+        0xf40e_3585,
+        0x106a_a070,
+        0x19a4_c116,
+        0x1e37_6c08,
+        0x2748_774c,
+        0x34b0_bcb5,
+        0x391c_0cb3,
+        0x4ed8_aa4a,
+        0x5b9c_ca4f,
+        0x682e_6ff3,
+        0x748f_82ee,
+        0x78a5_636f,
+        0x84c8_7814,
+        0x8cc7_0208,
+        0x90be_fffa,
+        0xa450_6ceb,
+        0xbef9_a3f7,
+        0xc671_78f2,
+    ];
+
+    /// Process single block.
+    fn block(state: &mut [u32; 8], block: &[u8]) {
+        let mut words = [0; 64];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        for i in 16..64 {
+            let s0 = words[i - 15].rotate_right(7)
+                ^ words[i - 15].rotate_right(18)
+                ^ (words[i - 15] >> 3);
+            let s1 = words[i - 2].rotate_right(17)
+                ^ words[i - 2].rotate_right(19)
+                ^ (words[i - 2] >> 10);
+            words[i] = words[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(words[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+        for (word, k) in words.iter().zip(Sha256::K.iter()) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(*k)
+                .wrapping_add(*word);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+        for (value, add) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *value = value.wrapping_add(add);
+        }
+    }
+}
+
+impl Digest for Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        let state = &mut self.state;
+        self.blocks
+            .update(data, &mut |block| Sha256::block(state, block));
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let state = &mut self.state;
+        self.blocks
+            .finish(true, &mut |block| Sha256::block(state, block));
+        self.state.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+}
+
+/// Calculate CRC-32 (IEEE 802.3) checksum.
+///
+/// # Arguments
+///
+/// * `data` - data to process
+///
+/// # Return value
+///
+/// Checksum value.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::crc32();
+    crc.update(data);
+    crc.value ^ crc.xorout
+}
+
+#[test]
+fn test_crc32() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(
+        crc32(b"The quick brown fox jumps over the lazy dog"),
+        0x414f_a339
+    );
+}
+
+#[test]
+fn test_algorithms() {
+    let hex = |data: Vec<u8>| -> String { data.iter().map(|b| format!("{:02x}", b)).collect() };
+    let compute = |algorithm: Algorithm, data: &[u8]| -> Vec<u8> {
+        let mut digest = algorithm.digest();
+        digest.update(data);
+        digest.finish()
+    };
+    let check = b"123456789";
+    let expect = [
+        (Algorithm::Crc8, "f4"),
+        (Algorithm::Crc16, "bb3d"),
+        (Algorithm::Crc16Ccitt, "29b1"),
+        (Algorithm::Crc16Modbus, "4b37"),
+        (Algorithm::Crc32, "cbf43926"),
+        (Algorithm::Crc32c, "e3069283"),
+        (Algorithm::Sum8, "dd"),
+        (Algorithm::Sum16, "01dd"),
+        (Algorithm::Sum32, "000001dd"),
+        (Algorithm::Xor8, "31"),
+    ];
+    for (algorithm, value) in expect {
+        assert_eq!(
+            hex(compute(algorithm, check)),
+            value,
+            "{}",
+            algorithm.name()
+        );
+    }
+
+    assert_eq!(hex(compute(Algorithm::Adler32, b"Wikipedia")), "11e60398");
+    assert_eq!(hex(compute(Algorithm::Fletcher16, b"abcde")), "c8f0");
+    assert_eq!(hex(compute(Algorithm::Fletcher32, b"abcde")), "f04fc729");
+    assert_eq!(hex(compute(Algorithm::Fletcher32, b"abcdef")), "56502d2a");
+
+    assert_eq!(
+        hex(compute(Algorithm::Md5, b"")),
+        "d41d8cd98f00b204e9800998ecf8427e"
+    );
+    assert_eq!(
+        hex(compute(Algorithm::Md5, b"abc")),
+        "900150983cd24fb0d6963f7d28e17f72"
+    );
+    assert_eq!(
+        hex(compute(Algorithm::Sha1, b"abc")),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    assert_eq!(
+        hex(compute(Algorithm::Sha256, b"abc")),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+
+    // multiblock data split to unaligned portions
+    let data = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    let mut digest = Algorithm::Sha256.digest();
+    for part in data.chunks(7) {
+        digest.update(part);
+    }
+    assert_eq!(
+        hex(digest.finish()),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+    let mut digest = Algorithm::Sha1.digest();
+    for part in data.chunks(13) {
+        digest.update(part);
+    }
+    assert_eq!(
+        hex(digest.finish()),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::hex;
+use std::io::{Error, ErrorKind, Result};
+use std::process::{Command, Stdio};
+
+/// Commands used to read the system clipboard, in order of preference.
+const PASTE_COMMANDS: &[&[&str]] = &[
+    &["wl-paste", "--no-newline"],
+    &["xclip", "-selection", "clipboard", "-out"],
+    &["xsel", "--clipboard", "--output"],
+    &["pbpaste"],
+];
+
+/// Read contents of the system clipboard with one of the external tools
+/// (`wl-paste`, `xclip`, `xsel` or `pbpaste`). Text that consists of hex
+/// bytes (`de ad be ef`) is converted to binary, any other text is used as
+/// is.
+///
+/// # Return value
+///
+/// Clipboard data.
+pub fn paste() -> Result<Vec<u8>> {
+    for command in PASTE_COMMANDS {
+        let output = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                return Ok(decode(output.stdout));
+            }
+            _ => {}
+        }
+    }
+    Err(Error::new(
+        ErrorKind::NotFound,
+        "Clipboard is not available",
+    ))
+}
+
+/// Convert hex text to binary data.
+///
+/// # Arguments
+///
+/// * `data` - clipboard data
+///
+/// # Return value
+///
+/// Decoded bytes or origin data if it is not a hex text.
+fn decode(data: Vec<u8>) -> Vec<u8> {
+    let bytes = std::str::from_utf8(&data).ok().and_then(hex::parse_bytes);
+    bytes.unwrap_or(data)
+}
+
+#[test]
+fn test_decode() {
+    assert_eq!(decode(b"de ad\nBE ef\n".to_vec()), [0xde, 0xad, 0xbe, 0xef]);
+    assert_eq!(decode(b"hello".to_vec()), b"hello");
+    assert_eq!(decode(b"abc".to_vec()), b"abc");
+    assert_eq!(decode(b" \n".to_vec()), b" \n");
+}
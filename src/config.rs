@@ -3,7 +3,9 @@
 
 use super::ascii::Table;
 use super::curses::Color;
+use super::file::{Backup, SaveMode};
 use super::inifile::IniFile;
+use super::minimap::MapMode;
 use std::env;
 use std::path::PathBuf;
 
@@ -13,26 +15,28 @@ pub struct Config {
     pub fixed_width: bool,
     /// ASCII table identifier.
     pub ascii_table: Option<&'static Table>,
+    /// Minimap mode (None hides the minimap).
+    pub minimap: Option<MapMode>,
+    /// Color bytes in the hex field by their class.
+    pub byte_colors: bool,
+    /// File save strategy.
+    pub save_mode: SaveMode,
+    /// Backup policy used on save.
+    pub backup: Backup,
     /// Color scheme.
     pub colors: Vec<(Color, i16, i16)>,
 }
 
 impl Config {
     const VIEW: &'static str = "View";
+    const FILE: &'static str = "File";
     const COLORS: &'static str = "Colors";
 
     /// Load configuration from the default rc file.
     pub fn load() -> Self {
         let mut instance = Config::default();
 
-        let dir = match env::var("XDG_CONFIG_HOME") {
-            Ok(val) => PathBuf::from(val),
-            Err(_) => match env::var("HOME") {
-                Ok(val) => PathBuf::from(val).join(".config"),
-                Err(_) => PathBuf::new(),
-            },
-        };
-        let file = dir.join("xvi").join("config");
+        let file = Config::dir().join("config");
 
         if let Ok(ini) = IniFile::load(&file) {
             if let Some(val) = ini.get_boolval(Config::VIEW, "FixedWidth") {
@@ -45,6 +49,22 @@ impl Config {
                     instance.ascii_table = Table::from_id(&val);
                 }
             }
+            if let Some(val) = ini.get_boolval(Config::VIEW, "ByteColors") {
+                instance.byte_colors = val;
+            }
+            if let Some(val) = ini.get_strval(Config::VIEW, "Minimap") {
+                instance.minimap = MapMode::from_id(&val);
+            }
+            if let Some(val) = ini.get_strval(Config::FILE, "Save") {
+                if let Some(mode) = SaveMode::from_id(&val) {
+                    instance.save_mode = mode;
+                }
+            }
+            if let Some(val) = ini.get_strval(Config::FILE, "Backup") {
+                if let Some(backup) = Backup::from_id(&val) {
+                    instance.backup = backup;
+                }
+            }
             let mut palette = Palette::DARK.clone();
             if let Some(val) = ini.get_strval(Config::COLORS, "Theme") {
                 if val.to_lowercase().as_str() == "light" {
@@ -59,6 +79,18 @@ impl Config {
 
         instance
     }
+
+    /// Get path to the directory with configuration files.
+    pub fn dir() -> PathBuf {
+        let dir = match env::var("XDG_CONFIG_HOME") {
+            Ok(val) => PathBuf::from(val),
+            Err(_) => match env::var("HOME") {
+                Ok(val) => PathBuf::from(val).join(".config"),
+                Err(_) => PathBuf::new(),
+            },
+        };
+        dir.join("xvi")
+    }
 }
 
 impl Default for Config {
@@ -66,6 +98,10 @@ impl Default for Config {
         Self {
             fixed_width: false,
             ascii_table: Some(Table::default()),
+            minimap: None,
+            byte_colors: false,
+            save_mode: SaveMode::InPlace,
+            backup: Backup::None,
             colors: Palette::DARK.colors(),
         }
     }
@@ -80,6 +116,7 @@ struct Palette {
     ascii: (i16, i16),
     modified: (i16, i16),
     diff: (i16, i16),
+    appended: (i16, i16),
     bar: (i16, i16),
     dialog: (i16, i16),
     error: (i16, i16),
@@ -87,6 +124,20 @@ struct Palette {
     focused: (i16, i16),
     input: (i16, i16),
     select: (i16, i16),
+    field: (i16, i16),
+    field_alt: (i16, i16),
+    boundary: (i16, i16),
+    minimap: (i16, i16),
+    minimap_zero: (i16, i16),
+    minimap_text: (i16, i16),
+    minimap_high: (i16, i16),
+    minimap_random: (i16, i16),
+    byte_zero: (i16, i16),
+    byte_print: (i16, i16),
+    byte_space: (i16, i16),
+    byte_control: (i16, i16),
+    byte_ff: (i16, i16),
+    byte_high: (i16, i16),
 }
 
 impl Palette {
@@ -98,6 +149,7 @@ impl Palette {
         ascii: (241, -1),
         modified: (220, -1),
         diff: (124, -1),
+        appended: (34, -1),
         bar: (242, 236),
         dialog: (235, 245),
         error: (250, 88),
@@ -105,6 +157,20 @@ impl Palette {
         focused: (250, 238),
         input: (235, 243),
         select: (250, 235),
+        field: (-1, 23),
+        field_alt: (-1, 53),
+        boundary: (235, 172),
+        minimap: (250, 238),
+        minimap_zero: (250, 232),
+        minimap_text: (250, 28),
+        minimap_high: (250, 94),
+        minimap_random: (250, 88),
+        byte_zero: (240, -1),
+        byte_print: (75, -1),
+        byte_space: (44, -1),
+        byte_control: (168, -1),
+        byte_ff: (141, -1),
+        byte_high: (173, -1),
     };
 
     /// Default color palette for the light theme.
@@ -115,6 +181,7 @@ impl Palette {
         ascii: (7, 4),
         modified: (11, 4),
         diff: (1, 4),
+        appended: (10, 4),
         bar: (0, 6),
         dialog: (0, 7),
         error: (15, 1),
@@ -122,6 +189,20 @@ impl Palette {
         focused: (0, 6),
         input: (0, 6),
         select: (15, 0),
+        field: (0, 6),
+        field_alt: (0, 5),
+        boundary: (0, 3),
+        minimap: (0, 7),
+        minimap_zero: (15, 0),
+        minimap_text: (0, 2),
+        minimap_high: (0, 3),
+        minimap_random: (15, 1),
+        byte_zero: (8, 4),
+        byte_print: (15, 4),
+        byte_space: (14, 4),
+        byte_control: (9, 4),
+        byte_ff: (13, 4),
+        byte_high: (3, 4),
     };
 
     /// Parse ini section with palette setup.
@@ -155,6 +236,9 @@ impl Palette {
                                 "diff" => {
                                     self.diff = (fg, bg);
                                 }
+                                "appended" => {
+                                    self.appended = (fg, bg);
+                                }
                                 "bar" => {
                                     self.bar = (fg, bg);
                                 }
@@ -176,6 +260,48 @@ impl Palette {
                                 "select" => {
                                     self.select = (fg, bg);
                                 }
+                                "field" => {
+                                    self.field = (fg, bg);
+                                }
+                                "fieldalt" => {
+                                    self.field_alt = (fg, bg);
+                                }
+                                "boundary" => {
+                                    self.boundary = (fg, bg);
+                                }
+                                "minimap" => {
+                                    self.minimap = (fg, bg);
+                                }
+                                "minimapzero" => {
+                                    self.minimap_zero = (fg, bg);
+                                }
+                                "minimaptext" => {
+                                    self.minimap_text = (fg, bg);
+                                }
+                                "minimaphigh" => {
+                                    self.minimap_high = (fg, bg);
+                                }
+                                "minimaprandom" => {
+                                    self.minimap_random = (fg, bg);
+                                }
+                                "bytezero" => {
+                                    self.byte_zero = (fg, bg);
+                                }
+                                "byteprint" => {
+                                    self.byte_print = (fg, bg);
+                                }
+                                "bytespace" => {
+                                    self.byte_space = (fg, bg);
+                                }
+                                "bytecontrol" => {
+                                    self.byte_control = (fg, bg);
+                                }
+                                "byteff" => {
+                                    self.byte_ff = (fg, bg);
+                                }
+                                "bytehigh" => {
+                                    self.byte_high = (fg, bg);
+                                }
                                 _ => {}
                             }
                         }
@@ -204,6 +330,10 @@ impl Palette {
             (Color::AsciiNormHi, self.highlight.0, self.highlight.1),
             (Color::AsciiModHi, self.modified.0, self.highlight.1),
             (Color::AsciiDiffHi, self.diff.0, self.highlight.1),
+            (Color::HexNew, self.appended.0, self.appended.1),
+            (Color::HexNewHi, self.appended.0, self.highlight.1),
+            (Color::AsciiNew, self.appended.0, self.appended.1),
+            (Color::AsciiNewHi, self.appended.0, self.highlight.1),
             (Color::Offset, self.offset.0, self.offset.1),
             (Color::OffsetHi, self.highlight.0, self.highlight.1),
             (Color::Bar, self.bar.0, self.bar.1),
@@ -213,6 +343,24 @@ impl Palette {
             (Color::Focused, self.focused.0, self.focused.1),
             (Color::Input, self.input.0, self.input.1),
             (Color::Select, self.select.0, self.select.1),
+            (Color::Field, self.field.0, self.field.1),
+            (Color::FieldAlt, self.field_alt.0, self.field_alt.1),
+            (Color::Boundary, self.boundary.0, self.boundary.1),
+            (Color::Minimap, self.minimap.0, self.minimap.1),
+            (Color::MinimapZero, self.minimap_zero.0, self.minimap_zero.1),
+            (Color::MinimapText, self.minimap_text.0, self.minimap_text.1),
+            (Color::MinimapHigh, self.minimap_high.0, self.minimap_high.1),
+            (
+                Color::MinimapRandom,
+                self.minimap_random.0,
+                self.minimap_random.1,
+            ),
+            (Color::ByteZero, self.byte_zero.0, self.byte_zero.1),
+            (Color::BytePrint, self.byte_print.0, self.byte_print.1),
+            (Color::ByteSpace, self.byte_space.0, self.byte_space.1),
+            (Color::ByteControl, self.byte_control.0, self.byte_control.1),
+            (Color::ByteFF, self.byte_ff.0, self.byte_ff.1),
+            (Color::ByteHigh, self.byte_high.0, self.byte_high.1),
         ]
     }
 }
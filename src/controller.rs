@@ -5,20 +5,39 @@ use super::config::Config;
 use super::curses::{Color, Curses, Event, Key, KeyPress, Window};
 use super::cursor::{Direction, HalfByte, Place};
 use super::editor::{Editor, Focus};
+use super::exec::{Executable, Node};
+use super::generator::Generator;
+use super::hexdump;
 use super::history::History;
+use super::magic::Hit;
+use super::minimap::MapMode;
+use super::strings::{Options, Text};
+use super::template::{Field, Template};
+use super::ui::changes::ChangesDialog;
+use super::ui::checksum::ChecksumDialog;
 use super::ui::cut::CutDialog;
 use super::ui::dialog::{Dialog, DialogType};
+use super::ui::export::ExportDialog;
 use super::ui::fill::FillDialog;
 use super::ui::goto::GotoDialog;
+use super::ui::import::ImportDialog;
 use super::ui::insert::InsertDialog;
 use super::ui::messagebox::MessageBox;
+use super::ui::patch::PatchDialog;
 use super::ui::progress::ProgressDialog;
 use super::ui::saveas::SaveAsDialog;
 use super::ui::search::SearchDialog;
 use super::ui::setup::SetupDialog;
+use super::ui::stats::StatsDialog;
+use super::ui::strings::{StringListDialog, StringsDialog};
+use super::ui::template::{FieldDialog, TemplateDialog};
+use super::ui::transform::TransformDialog;
+use super::ui::tree::{TreeAction, TreeButton, TreeDialog};
 use super::ui::widget::StandardButton;
-use std::io::{ErrorKind, Result};
+use std::io::{BufReader, Cursor, ErrorKind, Read, Result};
+use std::ops::Range;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 /// Controller: accepts input and converts it to commands for editor.
 pub struct Controller {
@@ -30,31 +49,34 @@ pub struct Controller {
     config: Config,
     /// Keybar window.
     keybar: Window,
+    /// Time of the last check for external modifications.
+    last_check: Instant,
 }
 
 impl Controller {
+    /// Interval of checking files for external modifications (milliseconds).
+    const CHECK_INTERVAL: i32 = 1000;
+    /// Interval of checking files for appended data in follow mode (milliseconds).
+    const FOLLOW_INTERVAL: i32 = 250;
+    /// Number of minimap cells skipped by PgUp/PgDown.
+    const MAP_PAGE: usize = 8;
+
     /// Run controller.
     ///
     /// # Arguments
     ///
     /// * `files` - files to open
     /// * `offset` - desirable initial offset
+    /// * `follow` - enable follow mode
     /// * `config` - configuration
-    pub fn run(files: &[String], offset: Option<u64>, config: Config) -> Result<()> {
+    pub fn run(files: &[String], offset: Option<u64>, follow: bool, config: Config) -> Result<()> {
         let history = History::default();
 
         // find initial offset
-        let initial_offset = if let Some(offset) = offset {
+        let initial_offset = if offset.is_some() {
             offset
         } else {
-            let mut offset = 0;
-            for file in files {
-                if let Some(val) = history.get_filepos(file) {
-                    offset = val;
-                    break;
-                }
-            }
-            offset
+            files.iter().find_map(|file| history.get_filepos(file))
         };
 
         // create controller instance
@@ -63,16 +85,22 @@ impl Controller {
             keybar: Window::new(0, 0, 0, 0, Color::Bar),
             history,
             config,
+            last_check: Instant::now(),
         };
 
         if !instance.resize() {
-            return Err(std::io::Error::new(
-                ErrorKind::Other,
-                "Not enough screen space to display",
-            ));
+            return Err(std::io::Error::other("Not enough screen space to display"));
         }
 
-        if initial_offset != 0 {
+        if follow {
+            instance.editor.set_follow(true);
+        }
+        // start of data by default (memory images)
+        let initial_offset =
+            initial_offset.unwrap_or_else(|| instance.editor.current().file.start());
+        if follow && offset.is_none() {
+            instance.editor.move_cursor(&Direction::FileEnd);
+        } else if initial_offset != 0 {
             instance
                 .editor
                 .move_cursor(&Direction::Absolute(initial_offset, 0));
@@ -89,11 +117,30 @@ impl Controller {
             self.draw();
 
             // handle next event
-            match Curses::wait_event() {
-                Event::TerminalResize => {
+            let interval = if self.editor.current().follow {
+                Controller::FOLLOW_INTERVAL
+            } else {
+                Controller::CHECK_INTERVAL
+            };
+            let mapping = self.editor.is_mapping();
+            match Curses::wait_event_for(if mapping { 0 } else { interval }) {
+                None => {
+                    if mapping {
+                        self.editor.update_minimaps();
+                    }
+                    // minimaps are built on every idle tick, files are
+                    // checked with the usual interval
+                    #[allow(clippy::cast_sign_loss)]
+                    let interval = Duration::from_millis(interval as u64);
+                    if !mapping || self.last_check.elapsed() >= interval {
+                        self.last_check = Instant::now();
+                        self.check_disk();
+                    }
+                }
+                Some(Event::TerminalResize) => {
                     self.resize();
                 }
-                Event::KeyPress(key) => match key.key {
+                Some(Event::KeyPress(key)) => match key.key {
                     Key::Esc | Key::F(10) => {
                         if self.exit() {
                             return;
@@ -107,6 +154,7 @@ impl Controller {
                                 self.key_input_ascii(&key);
                             }
                         }
+                        self.check_lock();
                     }
                 },
             }
@@ -126,7 +174,13 @@ impl Controller {
     fn key_input_common(&mut self, key: &KeyPress) -> bool {
         match key.key {
             Key::F(1) => {
-                Controller::help();
+                if key.modifier == KeyPress::SHIFT {
+                    self.identify();
+                } else if key.modifier == KeyPress::CTRL {
+                    self.statistics();
+                } else {
+                    Controller::help();
+                }
                 true
             }
             Key::F(2) => {
@@ -134,11 +188,31 @@ impl Controller {
                     self.save();
                 } else if key.modifier == KeyPress::SHIFT {
                     self.save_as();
+                } else if key.modifier == KeyPress::ALT {
+                    self.export();
                 }
                 true
             }
             Key::F(3) => {
-                self.goto();
+                if key.modifier == KeyPress::SHIFT {
+                    self.apply_template();
+                } else if key.modifier == KeyPress::ALT {
+                    self.template_fields();
+                } else if key.modifier == KeyPress::CTRL {
+                    self.structure();
+                } else {
+                    self.goto();
+                }
+                true
+            }
+            Key::F(4) => {
+                if key.modifier == KeyPress::SHIFT {
+                    self.apply_patch();
+                } else if key.modifier == KeyPress::CTRL {
+                    self.review_changes();
+                } else {
+                    self.create_patch();
+                }
                 true
             }
             Key::F(5) => {
@@ -146,17 +220,31 @@ impl Controller {
                     self.find_closest(self.history.search_backward);
                 } else if key.modifier == KeyPress::ALT {
                     self.find_closest(!self.history.search_backward);
+                } else if key.modifier == KeyPress::CTRL {
+                    self.scan();
                 } else {
                     self.find();
                 }
                 true
             }
             Key::F(6) => {
-                self.fill();
+                if key.modifier == KeyPress::SHIFT {
+                    self.checksum();
+                } else if key.modifier == KeyPress::ALT {
+                    self.transform();
+                } else if key.modifier == KeyPress::CTRL {
+                    self.strings();
+                } else {
+                    self.fill();
+                }
                 true
             }
             Key::F(7) => {
-                self.insert();
+                if key.modifier == KeyPress::SHIFT {
+                    self.import();
+                } else {
+                    self.insert();
+                }
                 true
             }
             Key::F(8) => {
@@ -164,7 +252,11 @@ impl Controller {
                 true
             }
             Key::F(9) => {
-                if SetupDialog::show(&mut self.config) {
+                if key.modifier == KeyPress::SHIFT {
+                    self.minimap_jump();
+                } else if key.modifier == KeyPress::CTRL {
+                    self.switch_minimap();
+                } else if SetupDialog::show(&mut self.config) {
                     self.editor.config_changed(&self.config);
                 }
                 true
@@ -250,21 +342,21 @@ impl Controller {
                 self.editor.move_cursor(&Direction::PageDown);
                 true
             }
-            Key::Char('z') => {
-                if key.modifier == KeyPress::CTRL {
-                    self.editor.undo();
-                    true
-                } else {
-                    false
-                }
+            Key::Char('z') if key.modifier == KeyPress::CTRL => {
+                self.editor.undo();
+                true
             }
-            Key::Char('r' | 'y') => {
-                if key.modifier == KeyPress::CTRL {
-                    self.editor.redo();
-                    true
-                } else {
-                    false
+            Key::Char('r' | 'y') if key.modifier == KeyPress::CTRL => {
+                self.editor.redo();
+                true
+            }
+            Key::Char('f') if key.modifier == KeyPress::CTRL => {
+                let follow = !self.editor.current().follow;
+                self.editor.set_follow(follow);
+                if follow {
+                    self.editor.move_cursor(&Direction::FileEnd);
                 }
+                true
             }
             _ => false,
         }
@@ -310,26 +402,24 @@ impl Controller {
             Key::Char('j') => {
                 self.editor.move_cursor(&Direction::LineDown);
             }
-            Key::Char('a'..='f' | 'A'..='F' | '0'..='9') => {
-                if key.modifier == KeyPress::NONE {
-                    if let Key::Char(chr) = key.key {
-                        let half = match chr {
-                            'a'..='f' => chr as u8 - b'a' + 10,
-                            'A'..='F' => chr as u8 - b'A' + 10,
-                            '0'..='9' => chr as u8 - b'0',
-                            _ => unreachable!(),
-                        };
-                        let cursor = &self.editor.current().cursor;
-                        let offset = cursor.offset;
-                        let (value, mask) = if cursor.half == HalfByte::Left {
-                            (half << 4, 0xf0)
-                        } else {
-                            (half, 0x0f)
-                        };
-                        self.editor.change(offset, value, mask);
-                        self.editor.move_cursor(&Direction::NextHalf);
-                    }
-                }
+            Key::Char(chr @ ('a'..='f' | 'A'..='F' | '0'..='9'))
+                if key.modifier == KeyPress::NONE =>
+            {
+                let half = match chr {
+                    'a'..='f' => chr as u8 - b'a' + 10,
+                    'A'..='F' => chr as u8 - b'A' + 10,
+                    '0'..='9' => chr as u8 - b'0',
+                    _ => unreachable!(),
+                };
+                let cursor = &self.editor.current().cursor;
+                let offset = cursor.offset;
+                let (value, mask) = if cursor.half == HalfByte::Left {
+                    (half << 4, 0xf0)
+                } else {
+                    (half, 0x0f)
+                };
+                self.editor.change(offset, value, mask);
+                self.editor.move_cursor(&Direction::NextHalf);
             }
             Key::Char('u') => {
                 self.editor.undo();
@@ -369,7 +459,7 @@ impl Controller {
             "Help",   // F1
             "Save",   // F2
             "Goto",   // F3
-            "",       // F4
+            "Patch",  // F4
             "Find",   // F5
             "Fill",   // F6
             "Insert", // F7
@@ -419,10 +509,11 @@ impl Controller {
 
     /// Show mini help.
     fn help() {
-        let mut dlg = Dialog::new(44, 8, DialogType::Normal, "XVI");
+        let mut dlg = Dialog::new(44, 9, DialogType::Normal, "XVI");
         dlg.add_center("Use arrows, PgUp, PgDown to move cursor.".to_string());
         dlg.add_center("Use Ctrl-z or u for undo,".to_string());
         dlg.add_center("Ctrl-r or Ctrl-y for redo.".to_string());
+        dlg.add_center("Use Ctrl-f to follow growing files.".to_string());
         dlg.add_center("Use Tab to switch between fields and files.".to_string());
         dlg.add_center("F1-F10 are described in the screen bottom.".to_string());
         dlg.add_separator();
@@ -432,17 +523,37 @@ impl Controller {
         dlg.show_unmanaged();
     }
 
+    /// Identify data type at the cursor position.
+    fn identify(&mut self) {
+        let offset = self.editor.current().cursor.offset;
+        let file_type = self.editor.identify(offset);
+        MessageBox::show(
+            DialogType::Normal,
+            "Identify",
+            &[
+                &format!("Data at offset 0x{:x}:", offset),
+                file_type.unwrap_or("Unknown data type"),
+            ],
+            &[(StandardButton::OK, true)],
+        );
+    }
+
     /// Save current file, returns false if operation failed.
     fn save(&mut self) -> bool {
         if !self.editor.current().file.is_modified() {
             return true;
         }
+        if self.editor.is_changed_on_disk() && !self.resolve_disk_change() {
+            return false;
+        }
         loop {
-            match self.editor.save() {
+            let mut progress = ProgressDialog::new("Saving...", true);
+            match self.editor.save(&mut progress) {
                 Ok(()) => {
                     return true;
                 }
                 Err(err) => {
+                    progress.hide();
                     if err.kind() == ErrorKind::Interrupted
                         || !MessageBox::retry_write(&self.editor.current().file.path, &err)
                     {
@@ -456,10 +567,14 @@ impl Controller {
     /// Save current file with new name.
     fn save_as(&mut self) {
         let name = self.editor.current().file.path.to_string();
-        if let Some(name) = SaveAsDialog::show(name) {
+        let format = self.editor.current().file.format;
+        if let Some((name, format, fill)) = SaveAsDialog::show(name, format) {
             loop {
                 let mut progress = ProgressDialog::new("Save as...", true);
-                match self.editor.save_as(Path::new(&name), &mut progress) {
+                match self
+                    .editor
+                    .save_as(Path::new(&name), format, fill, &mut progress)
+                {
                     Ok(()) => {
                         break;
                     }
@@ -476,16 +591,585 @@ impl Controller {
         }
     }
 
+    /// Write range of the current file to another file.
+    fn export(&mut self) {
+        let offset = self.editor.current().cursor.offset;
+        self.export_range(offset..offset + 1);
+    }
+
+    /// Write range of the current file to another file.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - default range to export
+    ///
+    /// # Return value
+    ///
+    /// Name of the written file.
+    fn export_range(&mut self, range: Range<u64>) -> Option<String> {
+        let current = self.editor.current();
+        let max = current.file.size;
+        let name = format!("{}.{:x}", current.file.path, range.start);
+        if let Some((range, name, append, format)) = ExportDialog::show(range, max, name) {
+            loop {
+                let mut progress = ProgressDialog::new("Exporting range...", true);
+                match self
+                    .editor
+                    .export(&range, Path::new(&name), append, format, &mut progress)
+                {
+                    Ok(()) => {
+                        return Some(name);
+                    }
+                    Err(err) => {
+                        progress.hide();
+                        if err.kind() == ErrorKind::Interrupted
+                            || !MessageBox::retry_write(&name, &err)
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Create patch from the pending changes or differences between documents.
+    fn create_patch(&mut self) {
+        let default = self.editor.current().file.path.clone();
+        let others = self.editor.others();
+        if let Some((source, format, name)) = PatchDialog::create(&default, &others) {
+            let patch = match self.editor.create_patch(format, source) {
+                Ok(patch) => patch,
+                Err(err) => {
+                    MessageBox::show(
+                        DialogType::Error,
+                        "Create patch",
+                        &[&err.to_string()],
+                        &[(StandardButton::OK, true)],
+                    );
+                    return;
+                }
+            };
+            while let Err(err) = std::fs::write(&name, &patch) {
+                if !MessageBox::retry_write(&name, &err) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Apply patch file to the current document.
+    fn apply_patch(&mut self) {
+        if let Some(name) = PatchDialog::apply() {
+            let result = std::fs::read(&name).and_then(|patch| self.editor.apply_patch(&patch));
+            if let Err(err) = result {
+                MessageBox::error_read(&name, &err, &[(StandardButton::Cancel, true)]);
+            }
+        }
+    }
+
+    /// Show pending changes of the current document as a patch list.
+    fn review_changes(&mut self) {
+        let list = self.editor.patch_list();
+        if list.is_empty() {
+            MessageBox::show(
+                DialogType::Normal,
+                "Pending changes",
+                &["No pending changes"],
+                &[(StandardButton::OK, true)],
+            );
+            return;
+        }
+        let default = format!("{}.patch.txt", self.editor.current().file.path);
+        let mut lines = list.lines();
+        if let Some(name) = ChangesDialog::show(lines.clone(), default) {
+            lines.push(String::new());
+            while let Err(err) = std::fs::write(&name, lines.join("\n")) {
+                if !MessageBox::retry_write(&name, &err) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Lock the current file on the first modification, warn if it is
+    /// already locked by another process.
+    fn check_lock(&mut self) {
+        if let Some(holder) = self.editor.lock() {
+            let path = self.editor.current().file.path.clone();
+            let holder = format!("is locked by {},", holder);
+            self.draw();
+            MessageBox::show(
+                DialogType::Error,
+                "File locked",
+                &[&path, &holder, "changes may be overwritten on save."],
+                &[(StandardButton::OK, true)],
+            );
+        }
+    }
+
+    /// Check opened files for modifications by another process.
+    fn check_disk(&mut self) {
+        self.editor.follow();
+        if let Some(index) = self.editor.changed_on_disk() {
+            self.editor.switch_focus(&Focus::DocumentIndex(index));
+            self.draw();
+            self.resolve_disk_change();
+        }
+    }
+
+    /// Ask user how to handle external modification of the current file.
+    ///
+    /// # Return value
+    ///
+    /// `true` if user wants to keep the current data
+    fn resolve_disk_change(&mut self) -> bool {
+        let path = self.editor.current().file.path.clone();
+        let button = MessageBox::show(
+            DialogType::Error,
+            "File changed",
+            &[&path, "was modified by another process."],
+            &[
+                (StandardButton::Reload, true),
+                (StandardButton::Keep, false),
+                (StandardButton::Diff, false),
+            ],
+        );
+        match button {
+            Some(StandardButton::Reload) => {
+                if let Err(err) = self.editor.reload() {
+                    self.editor.keep();
+                    MessageBox::error_read(&path, &err, &[(StandardButton::Cancel, true)]);
+                }
+                false
+            }
+            Some(StandardButton::Diff) => {
+                self.editor.keep();
+                match self.editor.open_snapshot(&self.config) {
+                    Ok(snapshot) => self.fit_document(&snapshot),
+                    Err(err) => {
+                        MessageBox::error_read(&path, &err, &[(StandardButton::Cancel, true)]);
+                    }
+                }
+                false
+            }
+            Some(StandardButton::Keep) => {
+                self.editor.keep();
+                true
+            }
+            _ => {
+                self.editor.keep();
+                false
+            }
+        }
+    }
+
+    /// Open file as a new document.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the file to open
+    fn open_document(&mut self, path: &str) {
+        if let Err(err) = self.editor.open(Path::new(path), &self.config) {
+            MessageBox::error_read(path, &err, &[(StandardButton::Cancel, true)]);
+        } else {
+            self.fit_document(path);
+        }
+    }
+
+    /// Resize the views to show the last opened document, close it if there
+    /// is not enough screen space.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the opened file
+    fn fit_document(&mut self, path: &str) {
+        if !self.resize() {
+            self.editor.close_last();
+            self.resize();
+            MessageBox::show(
+                DialogType::Error,
+                "Open file",
+                &["Not enough screen space to display", path],
+                &[(StandardButton::OK, true)],
+            );
+        }
+    }
+
     /// Goto to specified address.
     fn goto(&mut self) {
-        if let Some(offset) =
-            GotoDialog::show(&self.history.goto, self.editor.current().cursor.offset)
-        {
+        let pe = match self.editor.executable() {
+            Ok(Executable::Pe(pe)) => Some(pe),
+            _ => None,
+        };
+        if let Some(offset) = GotoDialog::show(
+            &self.history.goto,
+            self.editor.current().cursor.offset,
+            pe.as_ref(),
+        ) {
             self.history.add_goto(offset);
             self.editor.move_cursor(&Direction::Absolute(offset, 0));
         }
     }
 
+    /// Apply structure template.
+    fn apply_template(&mut self) {
+        let files = Template::list();
+        let current = self.editor.current();
+        if files.is_empty() && current.overlay.is_none() {
+            let dir = Template::dir();
+            MessageBox::show(
+                DialogType::Error,
+                "Apply template",
+                &["No templates found in", &dir.to_string_lossy()],
+                &[(StandardButton::OK, true)],
+            );
+            return;
+        }
+
+        let names: Vec<String> = files
+            .iter()
+            .map(|file| {
+                file.file_stem()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            })
+            .collect();
+        let applied = current
+            .overlay
+            .as_ref()
+            .and_then(|overlay| names.iter().position(|n| *n == overlay.template.name));
+        let offset = current
+            .overlay
+            .as_ref()
+            .map_or(current.cursor.offset, |overlay| overlay.offset);
+
+        if let Some((index, offset)) = TemplateDialog::show(&names, applied, offset) {
+            if let Some(index) = index {
+                let path = &files[index];
+                match Template::load(path)
+                    .and_then(|template| self.editor.set_template(Some((template, offset))))
+                {
+                    Ok(()) => self.template_fields(),
+                    Err(err) => {
+                        MessageBox::error_read(
+                            &path.to_string_lossy(),
+                            &err,
+                            &[(StandardButton::Cancel, true)],
+                        );
+                    }
+                }
+            } else if let Err(err) = self.editor.set_template(None) {
+                MessageBox::error_read(
+                    &self.editor.current().file.path,
+                    &err,
+                    &[(StandardButton::Cancel, true)],
+                );
+            }
+        }
+    }
+
+    /// Show fields of the applied template.
+    fn template_fields(&mut self) {
+        let mut selected = None;
+        loop {
+            let current = self.editor.current();
+            let overlay = if let Some(overlay) = &current.overlay {
+                overlay
+            } else {
+                self.apply_template();
+                return;
+            };
+            if let Some(err) = &overlay.error {
+                MessageBox::show(
+                    DialogType::Error,
+                    &overlay.template.name,
+                    &["Unable to apply template", err],
+                    &[(StandardButton::OK, true)],
+                );
+                return;
+            }
+
+            // select the deepest field under cursor by default
+            let fields = &overlay.fields;
+            let cursor = current.cursor.offset;
+            let index = selected.unwrap_or_else(|| {
+                fields
+                    .iter()
+                    .rposition(|field| field.range.contains(&cursor))
+                    .unwrap_or(0)
+            });
+            let lines = fields.iter().map(Field::line).collect();
+            let edit = TreeButton {
+                button: StandardButton::Edit,
+                enabled: fields.iter().map(Field::is_editable).collect(),
+            };
+
+            match TreeDialog::show(&overlay.template.name, None, lines, index, vec![edit]) {
+                Some(TreeAction::Goto(index)) => {
+                    let offset = fields[index].range.start;
+                    self.editor.move_cursor(&Direction::Absolute(offset, 0));
+                    return;
+                }
+                Some(TreeAction::Action(_, index)) => {
+                    let field = fields[index].clone();
+                    if let Some(data) = FieldDialog::show(&field) {
+                        if let Err(err) = self.editor.write_data(field.range.start, &data) {
+                            MessageBox::error_write(
+                                &self.editor.current().file.path,
+                                &err,
+                                &[(StandardButton::Cancel, true)],
+                            );
+                        }
+                    }
+                    selected = Some(index);
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Show structure of the executable file (ELF or PE).
+    fn structure(&mut self) {
+        let (title, nodes) = match self.editor.structure() {
+            Ok(tree) => tree,
+            Err(err) => {
+                MessageBox::show(
+                    DialogType::Error,
+                    "Structure",
+                    &["Unable to parse executable headers", &err.to_string()],
+                    &[(StandardButton::OK, true)],
+                );
+                return;
+            }
+        };
+
+        // select the deepest node under cursor by default
+        let cursor = self.editor.current().cursor.offset;
+        let index = nodes
+            .iter()
+            .rposition(|node| node.range.contains(&cursor))
+            .unwrap_or(0);
+        let lines = nodes.iter().map(Node::line).collect();
+
+        if let Some(TreeAction::Goto(index)) =
+            TreeDialog::show(title, None, lines, index, Vec::new())
+        {
+            let offset = nodes[index].range.start;
+            self.editor.move_cursor(&Direction::Absolute(offset, 0));
+        }
+    }
+
+    /// Scan the current file for embedded signatures.
+    fn scan(&mut self) {
+        let mut progress = ProgressDialog::new("Scanning...", false);
+        let hits = match self.editor.scan(&mut progress) {
+            Ok(hits) => hits,
+            Err(err) => {
+                progress.hide();
+                if err.kind() != ErrorKind::Interrupted {
+                    MessageBox::error_read(
+                        &self.editor.current().file.path,
+                        &err,
+                        &[(StandardButton::Cancel, true)],
+                    );
+                }
+                return;
+            }
+        };
+        progress.hide();
+        if hits.is_empty() {
+            MessageBox::show(
+                DialogType::Normal,
+                "Scan",
+                &["No known signatures found"],
+                &[(StandardButton::OK, true)],
+            );
+            return;
+        }
+
+        // select the last hit before cursor by default
+        let cursor = self.editor.current().cursor.offset;
+        let mut index = hits
+            .iter()
+            .rposition(|hit| hit.offset <= cursor)
+            .unwrap_or(0);
+        let title = format!("Signatures found: {}", hits.len());
+        let header = format!("{:<8}  {:>10}  Type", "Offset", "Size");
+        loop {
+            let lines = hits.iter().map(Hit::line).collect();
+            let actions = [StandardButton::Extract, StandardButton::Open]
+                .iter()
+                .map(|&button| TreeButton {
+                    button,
+                    enabled: Vec::new(),
+                })
+                .collect();
+            match TreeDialog::show(&title, Some(header.clone()), lines, index, actions) {
+                Some(TreeAction::Goto(index)) => {
+                    self.editor
+                        .move_cursor(&Direction::Absolute(hits[index].offset, 0));
+                    return;
+                }
+                Some(TreeAction::Action(StandardButton::Extract, selected)) => {
+                    let hit = &hits[selected];
+                    self.export_range(hit.offset..hit.offset + hit.size);
+                    index = selected;
+                }
+                Some(TreeAction::Action(_, selected)) => {
+                    let hit = &hits[selected];
+                    if let Some(name) = self.export_range(hit.offset..hit.offset + hit.size) {
+                        self.open_document(&name);
+                        return;
+                    }
+                    index = selected;
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Extract printable strings and go to the selected one.
+    fn strings(&mut self) {
+        let max = self.editor.current().file.size;
+        if max == 0 {
+            return;
+        }
+        if let Some((range, options)) = StringsDialog::show(max, Options::default()) {
+            let mut progress = ProgressDialog::new("Extracting strings...", false);
+            let result = self.editor.strings(&range, options, &mut progress);
+            progress.hide();
+            let texts = match result {
+                Ok(texts) => texts,
+                Err(err) => {
+                    if err.kind() != ErrorKind::Interrupted {
+                        MessageBox::error_read(
+                            &self.editor.current().file.path,
+                            &err,
+                            &[(StandardButton::Cancel, true)],
+                        );
+                    }
+                    return;
+                }
+            };
+            if texts.is_empty() {
+                MessageBox::show(
+                    DialogType::Normal,
+                    "Strings",
+                    &["No strings found"],
+                    &[(StandardButton::OK, true)],
+                );
+                return;
+            }
+
+            // select the last string before cursor by default
+            let cursor = self.editor.current().cursor.offset;
+            let index = texts
+                .iter()
+                .rposition(|text| text.offset <= cursor)
+                .unwrap_or(0);
+            let lines = texts.iter().map(Text::line).collect();
+            if let Some(index) = StringListDialog::show(lines, index) {
+                self.editor
+                    .move_cursor(&Direction::Absolute(texts[index].offset, 0));
+            }
+        }
+    }
+
+    /// Show byte statistics of the range, export them as CSV.
+    fn statistics(&mut self) {
+        let max = self.editor.current().file.size;
+        if max == 0 {
+            return;
+        }
+        if let Some(range) = StatsDialog::range(max) {
+            let mut progress = ProgressDialog::new("Calculating statistics...", false);
+            let result = self.editor.histogram(&range, &mut progress);
+            progress.hide();
+            let hist = match result {
+                Ok(hist) => hist,
+                Err(err) => {
+                    if err.kind() != ErrorKind::Interrupted {
+                        MessageBox::error_read(
+                            &self.editor.current().file.path,
+                            &err,
+                            &[(StandardButton::Cancel, true)],
+                        );
+                    }
+                    return;
+                }
+            };
+            let default = format!("{}.stats.csv", self.editor.current().file.path);
+            if let Some(name) = StatsDialog::show(&range, &hist, default) {
+                let csv = hist.csv();
+                while let Err(err) = std::fs::write(&name, &csv) {
+                    if !MessageBox::retry_write(&name, &err) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Switch minimap mode: hidden, entropy, byte class.
+    fn switch_minimap(&mut self) {
+        self.config.minimap = match self.config.minimap {
+            None => Some(MapMode::Entropy),
+            Some(MapMode::Entropy) => Some(MapMode::Class),
+            Some(MapMode::Class) => None,
+        };
+        self.editor.config_changed(&self.config);
+    }
+
+    /// Select area of the file on the minimap and move cursor to it.
+    fn minimap_jump(&mut self) {
+        if self.config.minimap.is_none() {
+            self.switch_minimap();
+        }
+        let current = self.editor.current();
+        let mut cell = match &current.minimap {
+            Some(map) if map.cells != 0 => map.cell(current.cursor.offset),
+            _ => return,
+        };
+        loop {
+            self.editor.select_map_cell(Some(cell));
+            self.draw();
+            let timeout = if self.editor.is_mapping() { 0 } else { -1 };
+            let event = Curses::wait_event_for(timeout);
+            let map = match &self.editor.current().minimap {
+                Some(map) if map.cells != 0 => map,
+                _ => break,
+            };
+            let last = map.cells - 1;
+            match event {
+                None => {
+                    self.editor.update_minimaps();
+                }
+                Some(Event::TerminalResize) => {
+                    self.resize();
+                }
+                Some(Event::KeyPress(key)) => match key.key {
+                    Key::Up => cell = cell.saturating_sub(1),
+                    Key::Down => cell += 1,
+                    Key::PageUp => cell = cell.saturating_sub(Controller::MAP_PAGE),
+                    Key::PageDown => cell += Controller::MAP_PAGE,
+                    Key::Home => cell = 0,
+                    Key::End => cell = last,
+                    Key::Enter => {
+                        let offset = map.range(cell.min(last)).start;
+                        self.editor.move_cursor(&Direction::Absolute(offset, 0));
+                        break;
+                    }
+                    Key::Esc => break,
+                    _ => {}
+                },
+            }
+            cell = cell.min(last);
+        }
+        self.editor.select_map_cell(None);
+    }
+
     /// Find position of the sequence.
     fn find(&mut self) {
         if let Some((seq, bkg)) =
@@ -539,16 +1223,95 @@ impl Controller {
         }
     }
 
+    /// Calculate checksum of the range.
+    fn checksum(&mut self) {
+        let current = self.editor.current();
+        let offset = current.cursor.offset;
+        let max = current.file.size;
+        if max == 0 {
+            return;
+        }
+        if let Some((range, algorithm, target)) = ChecksumDialog::show(offset, max) {
+            let mut progress = ProgressDialog::new("Calculating...", false);
+            let result = self.editor.checksum(algorithm, &range, &mut progress);
+            progress.hide();
+            let mut value = match result {
+                Ok(value) => value,
+                Err(err) => {
+                    if err.kind() != ErrorKind::Interrupted {
+                        MessageBox::error_read(
+                            &self.editor.current().file.path,
+                            &err,
+                            &[(StandardButton::Cancel, true)],
+                        );
+                    }
+                    return;
+                }
+            };
+
+            let hex: String = value.iter().map(|b| format!("{:02x}", b)).collect();
+            let mut message = vec![format!("Range {:x}-{:x}:", range.start, range.end - 1), hex];
+            if let Some(target) = target {
+                if target.little_endian && !algorithm.is_hash() {
+                    value.reverse();
+                }
+                message.push(match self.editor.write_data(target.offset, &value) {
+                    Ok(()) => format!("Written at offset {:x}", target.offset),
+                    Err(err) => format!("Write failed: {}", err),
+                });
+            }
+            self.draw();
+            let message: Vec<&str> = message.iter().map(String::as_str).collect();
+            MessageBox::show(
+                DialogType::Normal,
+                algorithm.name(),
+                &message,
+                &[(StandardButton::OK, true)],
+            );
+        }
+    }
+
     /// Fill range.
     fn fill(&mut self) {
         let current = self.editor.current();
-        if let Some((range, pattern)) = FillDialog::show(
+        if let Some((range, generator)) = FillDialog::show(
             current.cursor.offset,
             current.file.size,
             &self.history.pattern,
         ) {
-            self.history.pattern = pattern;
-            self.editor.fill(&range, &self.history.pattern);
+            if let Generator::Pattern(pattern) = &generator {
+                self.history.pattern = pattern.clone();
+            }
+            let result = generator
+                .stream()
+                .and_then(|mut stream| self.editor.fill(&range, &mut stream));
+            if let Err(err) = result {
+                let source = match &generator {
+                    Generator::File(path) => path.as_str(),
+                    Generator::Clipboard => "Clipboard",
+                    _ => &self.editor.current().file.path,
+                };
+                MessageBox::error_read(source, &err, &[(StandardButton::Cancel, true)]);
+            }
+        }
+    }
+
+    /// Transform range.
+    fn transform(&mut self) {
+        let current = self.editor.current();
+        if current.file.size == 0 {
+            return;
+        }
+        if let Some((range, transform)) =
+            TransformDialog::show(current.cursor.offset, current.file.size)
+        {
+            if let Err(err) = self.editor.transform(&range, &transform) {
+                MessageBox::error_read(
+                    &self.editor.current().file.path,
+                    &err,
+                    &[(StandardButton::Cancel, true)],
+                );
+            }
         }
     }
 
@@ -592,6 +1355,84 @@ impl Controller {
         }
     }
 
+    /// Import file contents into the current document.
+    fn import(&mut self) {
+        let params = if let Some(params) = ImportDialog::show(self.editor.current().cursor.offset) {
+            params
+        } else {
+            return;
+        };
+
+        let file = &self.editor.current().file;
+        if params.insert && file.is_modified() {
+            MessageBox::show(
+                DialogType::Error,
+                "Import file",
+                &[
+                    &file.path,
+                    "was modified.",
+                    "Please save or undo your changes first.",
+                ],
+                &[(StandardButton::OK, true)],
+            );
+            return;
+        }
+        let offset = if params.insert {
+            params.offset.min(file.size)
+        } else {
+            params.offset.min(file.size - 1)
+        };
+
+        let (mut source, mut length) = match Controller::open_source(&params.path, params.dump) {
+            Ok(source) => source,
+            Err(err) => {
+                MessageBox::error_read(&params.path, &err, &[(StandardButton::Cancel, true)]);
+                return;
+            }
+        };
+        if params.limit != 0 {
+            length = length.min(params.limit);
+        }
+
+        let mut progress = ProgressDialog::new("Importing file...", true);
+        if let Err(err) =
+            self.editor
+                .import(offset, &mut source, length, params.insert, &mut progress)
+        {
+            if err.kind() != ErrorKind::Interrupted {
+                progress.hide();
+                MessageBox::error_write(
+                    &self.editor.current().file.path,
+                    &err,
+                    &[(StandardButton::Cancel, true)],
+                );
+            }
+        }
+    }
+
+    /// Open source file for import.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the file
+    /// * `dump` - file is a text dump, the data is parsed from its first
+    ///   offset, gaps are filled with zeros
+    ///
+    /// # Return value
+    ///
+    /// Data source and its size.
+    fn open_source(path: &str, dump: bool) -> Result<(Box<dyn Read>, u64)> {
+        let file = std::fs::File::open(path)?;
+        if dump {
+            let data = hexdump::parse(&mut BufReader::new(file))?.to_raw(0);
+            let length = data.len() as u64;
+            Ok((Box::new(Cursor::new(data)), length))
+        } else {
+            let length = file.metadata()?.len();
+            Ok((Box::new(file), length))
+        }
+    }
+
     /// Cut out range.
     fn cut(&mut self) {
         let file = &self.editor.current().file;
@@ -644,11 +1485,9 @@ impl Controller {
                 ],
             ) {
                 match button {
-                    StandardButton::Yes => {
-                        // save current document
-                        if !self.save() {
-                            return false;
-                        }
+                    // save current document
+                    StandardButton::Yes if !self.save() => {
+                        return false;
                     }
                     StandardButton::Cancel => {
                         return false;
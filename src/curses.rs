@@ -110,6 +110,22 @@ impl Curses {
         }
     }
 
+    /// Read next event with timeout.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - max time to wait in milliseconds
+    ///
+    /// # Return value
+    ///
+    /// Event or `None` if timeout expired.
+    pub fn wait_event_for(timeout: i32) -> Option<Event> {
+        nc::timeout(timeout);
+        let event = Curses::read_event();
+        nc::timeout(-1);
+        event
+    }
+
     /// Read next event (non blocking).
     ///
     /// # Return value
@@ -260,6 +276,10 @@ pub enum Color {
     AsciiNormHi,
     AsciiModHi,
     AsciiDiffHi,
+    HexNew,
+    HexNewHi,
+    AsciiNew,
+    AsciiNewHi,
     Offset,
     OffsetHi,
     Bar,
@@ -269,6 +289,20 @@ pub enum Color {
     Focused,
     Input,
     Select,
+    Field,
+    FieldAlt,
+    Boundary,
+    Minimap,
+    MinimapZero,
+    MinimapText,
+    MinimapHigh,
+    MinimapRandom,
+    ByteZero,
+    BytePrint,
+    ByteSpace,
+    ByteControl,
+    ByteFF,
+    ByteHigh,
 }
 
 /// External event.
@@ -503,14 +537,7 @@ impl Window {
         let mut color = 0;
         nc::wattr_get(self.window, &mut _attr, &mut color);
 
-        nc::mvwchgat(
-            self.window,
-            y as i32,
-            x as i32,
-            width as i32,
-            style,
-            color as i16,
-        );
+        nc::mvwchgat(self.window, y as i32, x as i32, width as i32, style, color);
     }
 
     /// Set default color for further print operations.
@@ -1,15 +1,29 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
-use super::changes::ChangeList;
+use super::changes::{self, ChangeList};
+use super::checksum::Algorithm;
 use super::config::Config;
 use super::cursor::{Cursor, Direction, HalfByte, Place};
+use super::exec::{Executable, Node};
 use super::file::{File, ProgressHandler};
+use super::hexdump::Dump;
+use super::histogram::Histogram;
+use super::image::Format;
+use super::lock::Holder;
+use super::magic::{self, Hit, Signature};
+use super::minimap::Minimap;
+use super::patch::PatchFormat;
+use super::patchlist::PatchList;
+use super::strings::{self, Options, Text};
+use super::template::{Kind, Overlay, Template};
+use super::transform::Transform;
 use super::view::View;
 use std::collections::BTreeSet;
 use std::io;
+use std::io::Read;
 use std::ops::Range;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Holder of editable documents, implements editor business logic.
 pub struct Editor {
@@ -17,6 +31,8 @@ pub struct Editor {
     documents: Vec<Document>,
     /// Index of currently selected document.
     current: usize,
+    /// Directories with snapshots of files opened for comparison.
+    snapshots: Vec<PathBuf>,
 }
 
 impl Editor {
@@ -42,6 +58,7 @@ impl Editor {
         Ok(Self {
             documents,
             current: 0,
+            snapshots: Vec::new(),
         })
     }
 
@@ -50,6 +67,26 @@ impl Editor {
         &self.documents[self.current]
     }
 
+    /// Open new document.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - file to open
+    /// * `config` - app configuration
+    pub fn open(&mut self, file: &Path, config: &Config) -> io::Result<()> {
+        self.documents.push(Document::new(file, config)?);
+        Ok(())
+    }
+
+    /// Close the last opened document.
+    pub fn close_last(&mut self) {
+        debug_assert!(self.documents.len() > 1);
+        self.documents.pop();
+        if self.current >= self.documents.len() {
+            self.current = self.documents.len() - 1;
+        }
+    }
+
     /// Get number of opened documents.
     ///
     /// # Return value
@@ -71,10 +108,22 @@ impl Editor {
             self.documents
                 .iter()
                 .map(|doc| doc.file.path.to_string())
+                .filter(|path| !self.is_snapshot(path))
                 .collect(),
         )
     }
 
+    /// Check if the file is a snapshot opened for comparison.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - absolute path to the file
+    fn is_snapshot(&self, path: &str) -> bool {
+        self.snapshots
+            .iter()
+            .any(|dir| Path::new(path).starts_with(dir))
+    }
+
     /// Resize the current workspace.
     ///
     /// # Arguments
@@ -289,24 +338,474 @@ impl Editor {
     }
 
     /// Save currently focused document.
-    pub fn save(&mut self) -> io::Result<()> {
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - long time operation handler
+    pub fn save(&mut self, progress: &mut dyn ProgressHandler) -> io::Result<()> {
+        let current = &mut self.documents[self.current];
+        current.file.save(progress)?;
+        current.changes.reset();
+        self.refresh();
+        Ok(())
+    }
+
+    /// Lock the modified file of the currently focused document.
+    ///
+    /// # Return value
+    ///
+    /// Owner of the lock, each holder is reported only once.
+    pub fn lock(&mut self) -> Option<Holder> {
+        let current = &mut self.documents[self.current];
+        if !current.file.is_modified() || current.file.is_locked() {
+            return None;
+        }
+        let holder = current.file.lock();
+        if holder == current.lock_holder {
+            return None;
+        }
+        current.lock_holder = holder;
+        holder
+    }
+
+    /// Find the document which file was modified by another process.
+    ///
+    /// # Return value
+    ///
+    /// Index of the document.
+    pub fn changed_on_disk(&mut self) -> Option<usize> {
+        self.documents
+            .iter_mut()
+            .position(|doc| doc.file.is_changed_on_disk())
+    }
+
+    /// Check if the file of the currently focused document was modified by
+    /// another process.
+    pub fn is_changed_on_disk(&mut self) -> bool {
+        self.documents[self.current].file.is_changed_on_disk()
+    }
+
+    /// Open the current state of the currently focused file on disk as a new
+    /// document: the file is copied to a temporary snapshot, so it can be
+    /// compared with the document data.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - app configuration
+    ///
+    /// # Return value
+    ///
+    /// Path to the snapshot file.
+    pub fn open_snapshot(&mut self, config: &Config) -> io::Result<String> {
+        let source = PathBuf::from(&self.documents[self.current].file.path);
+        // keep the file name to show it in the title and detect the format
+        let dir = std::env::temp_dir()
+            .join(format!("xvi-{}", std::process::id()))
+            .join(self.snapshots.len().to_string());
+        let path = dir.join(source.file_name().unwrap());
+        let result = std::fs::create_dir_all(&dir).and_then(|()| std::fs::copy(&source, &path));
+        // closing the copied file releases the process lock, take it again
+        let current = &mut self.documents[self.current];
+        if current.file.is_locked() {
+            current.file.unlock();
+            current.file.lock();
+        }
+        let result = result.and_then(|_| self.open(&path, config));
+        // document paths are canonical
+        self.snapshots
+            .push(std::fs::canonicalize(&dir).unwrap_or(dir));
+        result?;
+        Ok(self.documents.last().unwrap().file.path.clone())
+    }
+
+    /// Reload currently focused document from disk, all changes will be lost.
+    pub fn reload(&mut self) -> io::Result<()> {
         let current = &mut self.documents[self.current];
-        current.file.save()?;
+        current.file.reload()?;
         current.changes.reset();
+        if let Some(overlay) = &mut current.overlay {
+            overlay.update(&mut current.file);
+        }
+        current.analyze();
+        // data on disk was changed, calculate the minimap from scratch
+        current.minimap = None;
+        current.view.max_offset = current.file.size;
+        current.view.reinit();
+
+        let cursor = current.cursor.offset;
+        let base = current.view.offset;
+        self.move_cursor(&Direction::Absolute(cursor, base));
         self.refresh();
+
         Ok(())
     }
 
+    /// Enable or disable follow mode for all documents.
+    ///
+    /// # Arguments
+    ///
+    /// * `enable` - new state of the follow mode
+    pub fn set_follow(&mut self, enable: bool) {
+        for doc in &mut self.documents {
+            doc.follow = enable;
+            doc.view.appended = 0..0;
+        }
+    }
+
+    /// Check files for appended data (follow mode).
+    /// Moves cursor to the end of file if it was at the end before.
+    pub fn follow(&mut self) {
+        let mut grown = false;
+        let mut scroll = false;
+        for (index, doc) in self.documents.iter_mut().enumerate() {
+            if !doc.follow {
+                continue;
+            }
+            let old_size = doc.file.size;
+            if let Ok(appended) = doc.file.grow() {
+                if appended != 0 {
+                    grown = true;
+                    doc.view.max_offset = doc.file.size;
+                    doc.view.appended = old_size..doc.file.size;
+                    doc.view.reinit();
+                    if index == self.current && doc.cursor.offset + 1 == old_size {
+                        scroll = true;
+                    }
+                }
+            }
+        }
+
+        if scroll {
+            self.move_cursor(&Direction::FileEnd);
+        }
+        if grown {
+            self.refresh();
+        }
+    }
+
+    /// Keep data of currently focused document, ignore changes on disk.
+    pub fn keep(&mut self) {
+        self.documents[self.current].file.keep();
+    }
+
     /// Save currently focused document with the new name.
     ///
     /// # Arguments
     ///
     /// * `file` - path to the new file
+    /// * `format` - format of the new file
+    /// * `fill` - value of the unused bytes for format conversion
     /// * `progress` - long time operation handler
-    pub fn save_as(&mut self, file: &Path, progress: &mut dyn ProgressHandler) -> io::Result<()> {
+    pub fn save_as(
+        &mut self,
+        file: &Path,
+        format: Format,
+        fill: u8,
+        progress: &mut dyn ProgressHandler,
+    ) -> io::Result<()> {
         let current = &mut self.documents[self.current];
-        current.file.save_as(file, progress)?;
+        current.file.save_as(file, format, fill, progress)?;
         current.changes.reset();
+        if current.view.max_offset != current.file.size {
+            current.view.max_offset = current.file.size;
+            current.view.reinit();
+            let cursor = current.cursor.offset;
+            let base = current.view.offset;
+            self.move_cursor(&Direction::Absolute(cursor, base));
+        }
+        self.refresh();
+        Ok(())
+    }
+
+    /// Write range of the currently focused document to another file.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to export
+    /// * `file` - path to the target file
+    /// * `append` - append data to the existing file
+    /// * `format` - text dump format, `None` to write raw binary data
+    /// * `progress` - long time operation handler
+    pub fn export(
+        &mut self,
+        range: &Range<u64>,
+        file: &Path,
+        append: bool,
+        format: Option<Dump>,
+        progress: &mut dyn ProgressHandler,
+    ) -> io::Result<()> {
+        let current = &mut self.documents[self.current];
+        if let Some(format) = format {
+            current.file.check_target(file)?;
+            let target = std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .append(append)
+                .truncate(!append)
+                .open(file)?;
+            let mut writer = io::BufWriter::new(target);
+            let columns = current.view.columns;
+            format.write(&mut current.file, range, columns, &mut writer, progress)?;
+            writer.into_inner()?.sync_all()
+        } else {
+            current.file.export(range, file, append, progress)
+        }
+    }
+
+    /// Create patch for the currently focused document.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - patch format
+    /// * `source` - index of the document used as the patch source, `None`
+    ///   to create patch from the pending changes
+    ///
+    /// # Return value
+    ///
+    /// Patch data.
+    pub fn create_patch(
+        &mut self,
+        format: PatchFormat,
+        source: Option<usize>,
+    ) -> io::Result<Vec<u8>> {
+        let (source, target) = if let Some(index) = source {
+            let source = self.documents[index].data()?;
+            (source, self.documents[self.current].data()?)
+        } else {
+            let file = &mut self.documents[self.current].file;
+            let changes = std::mem::take(&mut file.changes);
+            let source = file.read(0, file.size as usize);
+            file.changes = changes;
+            (source?, file.read(0, file.size as usize)?)
+        };
+        if source == target {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "No differences found",
+            ));
+        }
+        format.create(&source, &target)
+    }
+
+    /// Apply patch to the currently focused document as a set of changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `patch` - patch data: binary patch or text patch list
+    ///
+    /// # Return value
+    ///
+    /// Number of changed bytes.
+    pub fn apply_patch(&mut self, patch: &[u8]) -> io::Result<usize> {
+        if PatchFormat::detect(patch).is_none() {
+            let text = std::str::from_utf8(patch)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Unknown patch format"))?;
+            return self.apply_patch_list(&PatchList::parse(text)?);
+        }
+
+        let current = &mut self.documents[self.current];
+        let source = current.data()?;
+        let target = PatchFormat::apply(patch, &source)?;
+        if target.len() != source.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Patch changes the file size",
+            ));
+        }
+
+        const BLOCK_SIZE: usize = 0x10000;
+        let mut changed = 0;
+        let mut result = Ok(());
+        current.begin_write();
+        for (index, (old, new)) in source
+            .chunks(BLOCK_SIZE)
+            .zip(target.chunks(BLOCK_SIZE))
+            .enumerate()
+        {
+            if old != new {
+                match current.write((index * BLOCK_SIZE) as u64, new) {
+                    Ok(count) => changed += count,
+                    Err(err) => {
+                        result = Err(err);
+                        break;
+                    }
+                }
+            }
+        }
+        current.end_write(result.is_ok());
+        result?;
+        if let Some(offset) = source.iter().zip(target.iter()).position(|(l, r)| l != r) {
+            let view_offset = current.view.offset;
+            self.move_cursor(&Direction::Absolute(offset as u64, view_offset));
+        }
+        self.refresh();
+
+        Ok(changed)
+    }
+
+    /// Get pending changes of the currently focused document.
+    ///
+    /// # Return value
+    ///
+    /// Patch list with changes coalesced into runs.
+    pub fn patch_list(&self) -> PatchList {
+        PatchList::from_changes(&self.documents[self.current].changes.diff())
+    }
+
+    /// Apply patch list to the currently focused document as a set of
+    /// changes. Origin values of all hunks are verified before applying.
+    ///
+    /// # Arguments
+    ///
+    /// * `list` - patch list to apply
+    ///
+    /// # Return value
+    ///
+    /// Number of changed bytes.
+    pub fn apply_patch_list(&mut self, list: &PatchList) -> io::Result<usize> {
+        let current = &mut self.documents[self.current];
+
+        // check origin values
+        for hunk in &list.hunks {
+            if hunk.offset + hunk.old.len() as u64 > current.file.size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Offset 0x{:x} is out of file range", hunk.offset),
+                ));
+            }
+            let data = current.file.read(hunk.offset, hunk.old.len())?;
+            if let Some(pos) = data.iter().zip(hunk.old.iter()).position(|(l, r)| l != r) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Data mismatch at offset 0x{:x}: expected {:02x}, found {:02x}",
+                        hunk.offset + pos as u64,
+                        hunk.old[pos],
+                        data[pos]
+                    ),
+                ));
+            }
+        }
+
+        let mut changed = 0;
+        let mut result = Ok(());
+        current.begin_write();
+        for hunk in &list.hunks {
+            match current.write(hunk.offset, &hunk.new) {
+                Ok(count) => changed += count,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+        current.end_write(result.is_ok());
+        result?;
+        if let Some(hunk) = list.hunks.first() {
+            let view_offset = current.view.offset;
+            self.move_cursor(&Direction::Absolute(hunk.offset, view_offset));
+        }
+        self.refresh();
+
+        Ok(changed)
+    }
+
+    /// Get paths of the documents except the currently focused one.
+    ///
+    /// # Return value
+    ///
+    /// List of document indexes and file paths.
+    pub fn others(&self) -> Vec<(usize, String)> {
+        self.documents
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| *index != self.current)
+            .map(|(index, doc)| (index, doc.file.path.clone()))
+            .collect()
+    }
+
+    /// Calculate checksum of the range of the currently focused document.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - checksum algorithm
+    /// * `range` - range to process
+    /// * `progress` - long time operation handler
+    ///
+    /// # Return value
+    ///
+    /// Checksum value.
+    pub fn checksum(
+        &mut self,
+        algorithm: Algorithm,
+        range: &Range<u64>,
+        progress: &mut dyn ProgressHandler,
+    ) -> io::Result<Vec<u8>> {
+        let current = &mut self.documents[self.current];
+        algorithm.calculate(&mut current.file, range, progress)
+    }
+
+    /// Extract printable strings from the currently focused document.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to scan
+    /// * `options` - string options
+    /// * `progress` - long time operation handler
+    ///
+    /// # Return value
+    ///
+    /// List of strings sorted by offset.
+    pub fn strings(
+        &mut self,
+        range: &Range<u64>,
+        options: Options,
+        progress: &mut dyn ProgressHandler,
+    ) -> io::Result<Vec<Text>> {
+        let current = &mut self.documents[self.current];
+        strings::extract(&mut current.file, range, options, progress)
+    }
+
+    /// Calculate byte histogram of the currently focused document.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to process
+    /// * `progress` - long time operation handler
+    ///
+    /// # Return value
+    ///
+    /// Histogram of the range.
+    pub fn histogram(
+        &mut self,
+        range: &Range<u64>,
+        progress: &mut dyn ProgressHandler,
+    ) -> io::Result<Histogram> {
+        let current = &mut self.documents[self.current];
+        Histogram::calculate(&mut current.file, range, progress)
+    }
+
+    /// Write data to the currently focused document as a set of changes,
+    /// all changes are undone as a single operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start address
+    /// * `data` - data to write
+    pub fn write_data(&mut self, offset: u64, data: &[u8]) -> io::Result<()> {
+        let current = &mut self.documents[self.current];
+        if offset.saturating_add(data.len() as u64) > current.file.size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Out of file range",
+            ));
+        }
+        current.begin_write();
+        let result = current.write(offset, data);
+        current.end_write(result.is_ok());
+        result?;
+        let view_offset = current.view.offset;
+        self.move_cursor(&Direction::Absolute(offset, view_offset));
         self.refresh();
         Ok(())
     }
@@ -344,29 +843,135 @@ impl Editor {
         }
     }
 
-    /// Fill range in the currently focused document.
+    /// Fill range in the currently focused document, all changes are undone
+    /// as a single operation.
     ///
     /// # Arguments
     ///
     /// * `range` - range to fill
-    /// * `pattern` - pattern to use
-    pub fn fill(&mut self, range: &Range<u64>, pattern: &[u8]) {
+    /// * `data` - source of the data
+    ///
+    /// # Return value
+    ///
+    /// Operation status.
+    pub fn fill(&mut self, range: &Range<u64>, data: &mut dyn Read) -> io::Result<()> {
         debug_assert!(!range.is_empty());
-        debug_assert!(!pattern.is_empty());
 
         let current = &mut self.documents[self.current];
-        let mut pattern_pos = 0;
-        for offset in range.start..range.end {
-            current.change(offset, pattern[pattern_pos], 0xff);
-            pattern_pos += 1;
-            if pattern_pos == pattern.len() {
-                pattern_pos = 0;
-            }
-        }
+        current.begin_write();
+        let result = current.fill(range, data);
+        current.end_write(result.is_ok());
+        result?;
 
         let offset = current.view.offset;
         self.move_cursor(&Direction::Absolute(range.end, offset));
         self.refresh();
+        Ok(())
+    }
+
+    /// Transform range of the currently focused document, all changes are
+    /// undone as a single operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to transform
+    /// * `transform` - transform to apply
+    pub fn transform(&mut self, range: &Range<u64>, transform: &Transform) -> io::Result<()> {
+        debug_assert!(!range.is_empty());
+        transform
+            .validate(range.end - range.start)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        let current = &mut self.documents[self.current];
+        current.begin_write();
+        let result = current.transform(range, transform);
+        current.end_write(result.is_ok());
+        result?;
+
+        let offset = current.view.offset;
+        self.move_cursor(&Direction::Absolute(range.start, offset));
+        self.refresh();
+        Ok(())
+    }
+
+    /// Apply structure template to the currently focused document.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - template and start offset, `None` to remove the
+    ///   current template
+    ///
+    /// # Return value
+    ///
+    /// Operation status.
+    pub fn set_template(&mut self, template: Option<(Template, u64)>) -> io::Result<()> {
+        let current = &mut self.documents[self.current];
+        current.overlay = None;
+        if let Some((template, offset)) = template {
+            let mut overlay = Overlay::new(template, offset);
+            overlay.update(&mut current.file);
+            if let Some(err) = overlay.error {
+                self.refresh();
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+            }
+            current.overlay = Some(overlay);
+        }
+        self.refresh();
+        Ok(())
+    }
+
+    /// Identify data type at specified offset of the currently focused document.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start address of the data
+    ///
+    /// # Return value
+    ///
+    /// Name of the data type.
+    pub fn identify(&mut self, offset: u64) -> Option<&'static str> {
+        self.documents[self.current].identify(offset)
+    }
+
+    /// Scan the currently focused document for embedded signatures.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - long time operation handler
+    ///
+    /// # Return value
+    ///
+    /// List of hits sorted by offset.
+    pub fn scan(&mut self, progress: &mut dyn ProgressHandler) -> io::Result<Vec<Hit>> {
+        let file = &mut self.documents[self.current].file;
+        let size = file.size;
+        let mut read = |offset: u64, size: usize| file.read(offset, size);
+        magic::scan(size, &mut read, progress)
+    }
+
+    /// Parse executable headers of the currently focused document.
+    ///
+    /// # Return value
+    ///
+    /// Executable description.
+    pub fn executable(&mut self) -> io::Result<Executable> {
+        let current = &mut self.documents[self.current];
+        let size = current.file.size;
+        let mut read = |offset: u64, size: usize| current.file.read(offset, size);
+        Executable::parse(size, &mut read)
+    }
+
+    /// Get structure tree of the executable file.
+    ///
+    /// # Return value
+    ///
+    /// Name of the executable format and list of the tree nodes.
+    pub fn structure(&mut self) -> io::Result<(&'static str, Vec<Node>)> {
+        let exe = self.executable()?;
+        let current = &mut self.documents[self.current];
+        let size = current.file.size;
+        let mut read = |offset: u64, size: usize| current.file.read(offset, size);
+        Ok((exe.name(), exe.tree(size, &mut read)))
     }
 
     /// Insert bytes at specified offset.
@@ -403,6 +1008,81 @@ impl Editor {
         Ok(())
     }
 
+    /// Import data from another file at the specified offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start offset
+    /// * `source` - source of data
+    /// * `length` - number of bytes to import
+    /// * `insert` - insert data instead of overwriting the existing one
+    /// * `progress` - long time operation handler
+    ///
+    /// # Return value
+    ///
+    /// Number of imported bytes.
+    pub fn import(
+        &mut self,
+        offset: u64,
+        source: &mut dyn Read,
+        mut length: u64,
+        insert: bool,
+        progress: &mut dyn ProgressHandler,
+    ) -> io::Result<u64> {
+        let current = &mut self.documents[self.current];
+        debug_assert!(offset <= current.file.size);
+
+        if !insert {
+            length = length.min(current.file.size - offset);
+        }
+        if length == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "No data to import",
+            ));
+        }
+
+        if insert {
+            debug_assert!(!current.file.is_modified());
+            current.file.insert_data(offset, length, source, progress)?;
+            current.view.max_offset = current.file.size;
+        } else {
+            const BLOCK_SIZE: u64 = 0x10000;
+            let mut buffer = vec![0; BLOCK_SIZE as usize];
+            let mut handled = 0;
+            let mut result = Ok(());
+            current.begin_write();
+            while handled < length {
+                let percent = (100.0 / length as f64) * handled as f64;
+                if !progress.update(percent as u8) {
+                    result = Err(io::Error::new(
+                        io::ErrorKind::Interrupted,
+                        "Aborted by user",
+                    ));
+                    break;
+                }
+                #[allow(clippy::cast_possible_truncation)]
+                let size = (length - handled).min(BLOCK_SIZE) as usize;
+                result = source
+                    .read_exact(&mut buffer[..size])
+                    .and_then(|()| current.write(offset + handled, &buffer[..size]))
+                    .map(|_| ());
+                if result.is_err() {
+                    break;
+                }
+                handled += size as u64;
+            }
+            current.end_write(result.is_ok());
+            result?;
+        }
+
+        let view_offset = current.view.offset;
+        self.move_cursor(&Direction::Absolute(offset + length, view_offset));
+        self.refresh();
+
+        Ok(length)
+    }
+
     /// Cut out the specified range.
     ///
     /// # Arguments
@@ -433,6 +1113,8 @@ impl Editor {
         for doc in &mut self.documents {
             doc.view.fixed_width = config.fixed_width;
             doc.view.ascii_table = config.ascii_table;
+            doc.view.minimap = config.minimap;
+            doc.view.byte_colors = config.byte_colors;
             if doc.view.ascii_table.is_none() {
                 doc.cursor.set_place(Place::Hex);
             }
@@ -444,6 +1126,37 @@ impl Editor {
         self.refresh();
     }
 
+    /// Check if any minimap is being calculated.
+    pub fn is_mapping(&self) -> bool {
+        self.documents
+            .iter()
+            .any(|doc| doc.minimap.as_ref().is_some_and(|map| !map.is_complete()))
+    }
+
+    /// Calculate the next portion of minimaps.
+    pub fn update_minimaps(&mut self) {
+        for doc in &mut self.documents {
+            if let Some(map) = doc.minimap.as_mut().filter(|map| !map.is_complete()) {
+                let file = &mut doc.file;
+                let mut read = |offset: u64, size: usize| file.read(offset, size);
+                if map.update(&mut read).is_err() {
+                    // stop calculation until the next refresh
+                    *map = Minimap::new(0, 0);
+                }
+                break;
+            }
+        }
+    }
+
+    /// Select minimap cell of the currently focused document.
+    ///
+    /// # Arguments
+    ///
+    /// * `cell` - index of the cell, `None` to reset selection
+    pub fn select_map_cell(&mut self, cell: Option<usize>) {
+        self.documents[self.current].view.map_selected = cell;
+    }
+
     /// Refresh documents buffers: data cache, changed set, diff etc.
     fn refresh(&mut self) {
         // refresh buffer for all documents
@@ -484,6 +1197,18 @@ impl Editor {
     }
 }
 
+impl Drop for Editor {
+    fn drop(&mut self) {
+        // remove snapshots of the files opened for comparison
+        for dir in &self.snapshots {
+            std::fs::remove_dir_all(dir).ok();
+        }
+        if let Some(dir) = self.snapshots.first().and_then(|dir| dir.parent()) {
+            std::fs::remove_dir(dir).ok();
+        }
+    }
+}
+
 /// Editable document.
 pub struct Document {
     /// Editable file.
@@ -494,9 +1219,28 @@ pub struct Document {
     pub cursor: Cursor,
     /// View of the document.
     pub view: View,
+    /// Follow mode (tracking data appended to the file).
+    pub follow: bool,
+    /// Owner of the file lock (last reported).
+    pub lock_holder: Option<Holder>,
+    /// Applied structure template.
+    pub overlay: Option<Overlay>,
+    /// Type of the file detected by the signature.
+    pub file_type: Option<&'static str>,
+    /// Named regions of the file (sections of the executable), sorted by start offset.
+    pub regions: Vec<(Range<u64>, String)>,
+    /// File size at the last analysis.
+    analyzed_size: u64,
+    /// File ranges read by the last analysis: headers, tables etc.
+    analyzed: Vec<Range<u64>>,
+    /// Minimap of the file.
+    pub minimap: Option<Minimap>,
 }
 
 impl Document {
+    /// Size of the data block for bulk modifications.
+    const BLOCK_SIZE: usize = 0x10000;
+
     /// Create new document instance.
     ///
     /// # Arguments
@@ -508,15 +1252,117 @@ impl Document {
     ///
     /// Document instance.
     fn new(path: &Path, config: &Config) -> io::Result<Self> {
-        let file = File::open(path)?;
+        let mut file = File::open(path)?;
+        file.save_mode = config.save_mode;
+        file.backup = config.backup;
         let file_size = file.size;
 
-        Ok(Self {
+        let mut doc = Self {
             file,
             changes: ChangeList::default(),
             cursor: Cursor::default(),
             view: View::new(config, file_size),
-        })
+            follow: false,
+            lock_holder: None,
+            overlay: None,
+            file_type: None,
+            regions: Vec::new(),
+            analyzed_size: 0,
+            analyzed: Vec::new(),
+            minimap: None,
+        };
+        doc.analyze();
+
+        Ok(doc)
+    }
+
+    /// Get the innermost named region containing specified offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - address inside the region
+    ///
+    /// # Return value
+    ///
+    /// Range and name of the region.
+    pub fn region(&self, offset: u64) -> Option<&(Range<u64>, String)> {
+        self.regions
+            .iter()
+            .filter(|(range, _)| range.contains(&offset))
+            .min_by_key(|(range, _)| range.end - range.start)
+    }
+
+    /// Identify data type by the signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start address of the data
+    ///
+    /// # Return value
+    ///
+    /// Name of the data type.
+    fn identify(&mut self, offset: u64) -> Option<&'static str> {
+        if offset >= self.file.size {
+            return None;
+        }
+        let data = self.file.read(offset, Signature::WINDOW).ok()?;
+        Signature::identify(&data).map(|sig| sig.name)
+    }
+
+    /// Detect file type and named regions of the file.
+    fn analyze(&mut self) {
+        let size = self.file.size;
+        self.analyzed_size = size;
+        self.file_type = self.identify(0);
+        // the file type is detected by the head of the file
+        let mut analyzed = Vec::new();
+        analyzed.push(0..Signature::WINDOW as u64);
+        let file = &mut self.file;
+        let mut read = |offset: u64, size: usize| {
+            analyzed.push(offset..offset.saturating_add(size as u64));
+            file.read(offset, size)
+        };
+        self.regions = Executable::parse(size, &mut read)
+            .map(|exe| exe.regions(size))
+            .unwrap_or_default();
+        self.analyzed = analyzed;
+    }
+
+    /// Check if the analysis result may be outdated.
+    ///
+    /// # Arguments
+    ///
+    /// * `changed` - modified ranges of the data
+    fn is_analysis_outdated(&self, changed: &[Range<u64>]) -> bool {
+        self.analyzed_size != self.file.size
+            || changed.iter().any(|change| {
+                self.analyzed
+                    .iter()
+                    .any(|range| range.start < change.end && change.start < range.end)
+            })
+    }
+
+    /// Update minimap: restart calculation if the file or the view size was
+    /// changed, recalculate slices of the modified data.
+    ///
+    /// # Arguments
+    ///
+    /// * `changed` - modified ranges of the data
+    fn reset_minimap(&mut self, changed: &[Range<u64>]) {
+        if self.view.minimap.is_none() {
+            self.minimap = None;
+            return;
+        }
+        let size = self.file.size;
+        let cells = (self.view.lines as u64).min(size) as usize;
+        match &mut self.minimap {
+            Some(map) if map.file_size == size && map.cells == cells => {
+                for range in changed {
+                    map.invalidate(range);
+                }
+            }
+            _ => self.minimap = Some(Minimap::new(cells, size)),
+        }
     }
 
     /// Move cursor.
@@ -538,14 +1384,41 @@ impl Document {
         base_changed
     }
 
+    /// Get the entire document data with the current changes.
+    fn data(&mut self) -> io::Result<Vec<u8>> {
+        self.file.changes = self.changes.get();
+        #[allow(clippy::cast_possible_truncation)]
+        self.file.read(0, self.file.size as usize)
+    }
+
     /// Update currently displayed page.
     fn refresh(&mut self) {
-        self.file.changes = self.changes.get();
+        let changes = self.changes.get();
+        let changed = changes::changed_ranges(&self.file.changes, &changes);
+        let modified = !changed.is_empty();
+        self.file.changes = changes;
+        if let Some(overlay) = &mut self.overlay {
+            if modified || overlay.is_outdated(&self.file) {
+                overlay.update(&mut self.file);
+            }
+        }
+        if self.is_analysis_outdated(&changed) {
+            self.analyze();
+        }
+        self.reset_minimap(&changed);
 
         self.view.data = self
             .file
             .read(self.view.offset, self.view.lines * self.view.columns)
             .unwrap();
+        let page = self.view.offset..self.view.offset + self.view.data.len() as u64;
+        self.view.gaps = self
+            .file
+            .gaps(&page)
+            .into_iter()
+            .flatten()
+            .filter(|o| !self.file.changes.contains_key(o))
+            .collect();
         self.view.changes = self
             .file
             .changes
@@ -556,6 +1429,121 @@ impl Document {
             })
             .copied()
             .collect();
+
+        // template fields on the current page
+        let page_end = self.view.offset + self.view.data.len() as u64;
+        self.view.fields = self
+            .overlay
+            .iter()
+            .flat_map(|overlay| overlay.fields.iter())
+            .filter(|field| field.kind != Kind::Group)
+            .enumerate()
+            .filter(|(_, field)| field.range.start < page_end && field.range.end > self.view.offset)
+            .map(|(index, field)| (field.range.clone(), index % 2 != 0))
+            .collect();
+
+        // boundaries of the file regions on the current page
+        self.view.boundaries = self
+            .regions
+            .iter()
+            .map(|(range, _)| range.start)
+            .filter(|offset| (self.view.offset..page_end).contains(offset))
+            .collect();
+    }
+
+    /// Start bulk modification: all blocks written with `write` until
+    /// `end_write` are undone as a single operation.
+    fn begin_write(&mut self) {
+        self.file.changes = self.changes.get();
+        self.changes.begin_group();
+    }
+
+    /// Write block of data as a part of bulk modification, blocks written
+    /// within a single operation must not overlap.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start address of the block
+    /// * `data` - data to write
+    ///
+    /// # Return value
+    ///
+    /// Number of changed bytes.
+    fn write(&mut self, offset: u64, data: &[u8]) -> io::Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        let origin = self.file.read(offset, data.len())?;
+        Ok(self.changes.set_block(offset, &origin, data))
+    }
+
+    /// Fill range of the document as a part of bulk modification.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to fill
+    /// * `data` - source of the data
+    fn fill(&mut self, range: &Range<u64>, data: &mut dyn Read) -> io::Result<()> {
+        let mut block = vec![0; Document::BLOCK_SIZE];
+        let mut offset = range.start;
+        while offset < range.end {
+            #[allow(clippy::cast_possible_truncation)]
+            let size = (range.end - offset).min(Document::BLOCK_SIZE as u64) as usize;
+            data.read_exact(&mut block[..size])?;
+            self.write(offset, &block[..size])?;
+            offset += size as u64;
+        }
+        Ok(())
+    }
+
+    /// Transform range of the document as a part of bulk modification.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to transform
+    /// * `transform` - transform to apply
+    fn transform(&mut self, range: &Range<u64>, transform: &Transform) -> io::Result<()> {
+        if let Some(block_size) = transform.block_size(Document::BLOCK_SIZE) {
+            let mut offset = range.start;
+            while offset < range.end {
+                #[allow(clippy::cast_possible_truncation)]
+                let size = (range.end - offset).min(block_size as u64) as usize;
+                let mut data = self.file.read(offset, size)?;
+                transform.apply(&mut data);
+                self.write(offset, &data)?;
+                offset += size as u64;
+            }
+        } else {
+            // reverse: swap mirrored blocks from both ends of the range
+            let half = (range.end - range.start) / 2;
+            let mut pos = 0;
+            while pos < half {
+                #[allow(clippy::cast_possible_truncation)]
+                let size = (half - pos).min(Document::BLOCK_SIZE as u64) as usize;
+                let head = range.start + pos;
+                let tail = range.end - pos - size as u64;
+                let mut head_data = self.file.read(head, size)?;
+                let mut tail_data = self.file.read(tail, size)?;
+                head_data.reverse();
+                tail_data.reverse();
+                self.write(head, &tail_data)?;
+                self.write(tail, &head_data)?;
+                pos += size as u64;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finish bulk modification.
+    ///
+    /// # Arguments
+    ///
+    /// * `commit` - `false` to discard all changes of the operation
+    fn end_write(&mut self, commit: bool) {
+        if !commit {
+            self.changes.discard_group();
+        }
+        self.changes.end_group();
     }
 
     /// Change data in the document.
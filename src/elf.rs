@@ -0,0 +1,860 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::exec::{name, string, Node, Reader};
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+
+/// ELF file description.
+pub struct Elf {
+    /// 64-bit file class.
+    pub is64: bool,
+    /// Little endian byte order.
+    pub little_endian: bool,
+    /// Values of the file header fields (see `Elf::HEADER`).
+    header: Vec<u64>,
+    /// Program headers.
+    pub segments: Vec<Segment>,
+    /// Section headers.
+    pub sections: Vec<Section>,
+}
+
+/// Program header (segment).
+pub struct Segment {
+    /// Offset of the program header.
+    pub header: u64,
+    /// Segment type.
+    pub stype: u32,
+    /// Access flags.
+    pub flags: u32,
+    /// Offset of the segment data.
+    pub offset: u64,
+    /// Virtual address.
+    pub address: u64,
+    /// Size of the segment data in the file.
+    pub size: u64,
+}
+
+/// Section header.
+pub struct Section {
+    /// Offset of the section header.
+    pub header: u64,
+    /// Section name.
+    pub name: String,
+    /// Section type.
+    pub stype: u32,
+    /// Virtual address.
+    pub address: u64,
+    /// Offset of the section data.
+    pub offset: u64,
+    /// Size of the section data.
+    pub size: u64,
+    /// Index of the linked section (string table for symbols).
+    pub link: u32,
+    /// Size of the table entry.
+    pub entry_size: u64,
+}
+
+/// Symbol table entry.
+pub struct Symbol {
+    /// Index of the symbol table section.
+    pub table: usize,
+    /// Offset of the symbol table entry.
+    pub entry: u64,
+    /// Symbol name.
+    pub name: String,
+    /// Symbol value (address).
+    pub value: u64,
+    /// Size of the symbol data.
+    pub size: u64,
+    /// Symbol type and binding.
+    pub info: u8,
+    /// Offset of the symbol data inside the file.
+    pub offset: Option<u64>,
+}
+
+impl Elf {
+    /// File signature.
+    pub const SIGNATURE: &'static [u8] = b"\x7fELF";
+    /// Size of the identification bytes.
+    const IDENT_SIZE: usize = 16;
+    /// File header fields after the identification bytes: name and size,
+    /// zero size means the address size (4 or 8 bytes).
+    const HEADER: &'static [(&'static str, usize)] = &[
+        ("Type", 2),
+        ("Machine", 2),
+        ("Version", 4),
+        ("Entry point", 0),
+        ("Program headers", 0),
+        ("Section headers", 0),
+        ("Flags", 4),
+        ("Header size", 2),
+        ("Program header size", 2),
+        ("Program headers count", 2),
+        ("Section header size", 2),
+        ("Section headers count", 2),
+        ("Section names index", 2),
+    ];
+    /// Relocatable file type.
+    const ET_REL: u64 = 1;
+    /// Section without data in the file.
+    const SHT_NOBITS: u32 = 8;
+    /// Symbol table section types.
+    const SHT_SYMTAB: u32 = 2;
+    const SHT_DYNSYM: u32 = 11;
+    /// First reserved section index.
+    const SHN_LORESERVE: u64 = 0xff00;
+    /// Max size of the section names table.
+    const MAX_STRTAB: u64 = 1024 * 1024;
+    /// Max number of symbols to load.
+    const MAX_SYMBOLS: usize = 10_000;
+
+    /// Parse ELF headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    /// * `read` - data reader: offset and size of the block
+    ///
+    /// # Return value
+    ///
+    /// ELF description.
+    pub fn parse(size: u64, read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>) -> Result<Self> {
+        let mut reader = Reader::new(read, size, true);
+
+        let ident = reader
+            .bytes(0, Elf::IDENT_SIZE)
+            .map_err(|_| Elf::not_elf())?;
+        if ident[0..4] != *Elf::SIGNATURE
+            || !(1..=2).contains(&ident[4])
+            || !(1..=2).contains(&ident[5])
+        {
+            return Err(Elf::not_elf());
+        }
+        let is64 = ident[4] == 2;
+        let little_endian = ident[5] == 1;
+        reader.little_endian = little_endian;
+        let addr = if is64 { 8 } else { 4 };
+
+        let sizes: Vec<usize> = Elf::HEADER
+            .iter()
+            .map(|&(_, size)| if size == 0 { addr } else { size })
+            .collect();
+        let header = reader.numbers(Elf::IDENT_SIZE as u64, &sizes)?;
+
+        let mut elf = Self {
+            is64,
+            little_endian,
+            header,
+            segments: Vec::new(),
+            sections: Vec::new(),
+        };
+
+        // program headers
+        let (offset, entry_size, count) = elf.table(false);
+        let sizes = if is64 {
+            [4, 4, 8, 8, 8, 8, 8, 8]
+        } else {
+            [4, 4, 4, 4, 4, 4, 4, 4]
+        };
+        if count != 0 && entry_size < sizes.iter().sum::<usize>() as u64 {
+            return Err(Elf::invalid("Invalid program header size"));
+        }
+        for index in 0..count {
+            let header = offset.saturating_add(index * entry_size);
+            let val = reader.numbers(header, &sizes)?;
+            // 32-bit: type, offset, vaddr, paddr, filesz, memsz, flags, align
+            // 64-bit: type, flags, offset, vaddr, paddr, filesz, memsz, align
+            let (flags, offset, address, size) = if is64 {
+                (val[1], val[2], val[3], val[5])
+            } else {
+                (val[6], val[1], val[2], val[4])
+            };
+            #[allow(clippy::cast_possible_truncation)]
+            elf.segments.push(Segment {
+                header,
+                stype: val[0] as u32,
+                flags: flags as u32,
+                offset,
+                address,
+                size,
+            });
+        }
+
+        // section headers
+        let (offset, entry_size, count) = elf.table(true);
+        let sizes = if is64 {
+            [4, 4, 8, 8, 8, 8, 4, 4, 8, 8]
+        } else {
+            [4, 4, 4, 4, 4, 4, 4, 4, 4, 4]
+        };
+        if count != 0 && entry_size < sizes.iter().sum::<usize>() as u64 {
+            return Err(Elf::invalid("Invalid section header size"));
+        }
+        let mut names = Vec::with_capacity(count as usize);
+        for index in 0..count {
+            let header = offset.saturating_add(index * entry_size);
+            // name, type, flags, addr, offset, size, link, info, align, entsize
+            let val = reader.numbers(header, &sizes)?;
+            names.push(val[0]);
+            #[allow(clippy::cast_possible_truncation)]
+            elf.sections.push(Section {
+                header,
+                name: String::new(),
+                stype: val[1] as u32,
+                address: val[3],
+                offset: val[4],
+                size: val[5],
+                link: val[6] as u32,
+                entry_size: val[9],
+            });
+        }
+
+        // section names
+        #[allow(clippy::cast_possible_truncation)]
+        let names_index = elf.header[12] as usize;
+        if let Some(range) = elf.sections.get(names_index).and_then(Section::range) {
+            let size = (range.end - range.start).min(Elf::MAX_STRTAB);
+            #[allow(clippy::cast_possible_truncation)]
+            if let Ok(table) = reader.bytes(range.start, size as usize) {
+                for (section, name) in elf.sections.iter_mut().zip(names) {
+                    section.name = string(&table, name);
+                }
+            }
+        }
+
+        Ok(elf)
+    }
+
+    /// Load symbols from the static and dynamic symbol tables.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    /// * `read` - data reader: offset and size of the block
+    ///
+    /// # Return value
+    ///
+    /// List of symbols, broken tables are skipped.
+    pub fn symbols(
+        &self,
+        size: u64,
+        read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>,
+    ) -> Vec<Symbol> {
+        let mut reader = Reader::new(read, size, self.little_endian);
+        let sizes: &[usize] = if self.is64 {
+            // name, info, other, shndx, value, size
+            &[4, 1, 1, 2, 8, 8]
+        } else {
+            // name, value, size, info, other, shndx
+            &[4, 4, 4, 1, 1, 2]
+        };
+        let min_entry = sizes.iter().sum::<usize>() as u64;
+
+        let mut symbols = Vec::new();
+        for (table, section) in self.sections.iter().enumerate() {
+            if section.stype != Elf::SHT_SYMTAB && section.stype != Elf::SHT_DYNSYM {
+                continue;
+            }
+            let range = if let Some(range) = section.range() {
+                range
+            } else {
+                continue;
+            };
+            if section.entry_size < min_entry {
+                continue;
+            }
+            let strings = self
+                .sections
+                .get(section.link as usize)
+                .and_then(Section::range)
+                .and_then(|range| {
+                    let size = (range.end - range.start).min(Elf::MAX_STRTAB);
+                    #[allow(clippy::cast_possible_truncation)]
+                    reader.bytes(range.start, size as usize).ok()
+                })
+                .unwrap_or_default();
+
+            let count = (range.end - range.start) / section.entry_size;
+            // the first entry is always undefined
+            for index in 1..count {
+                if symbols.len() >= Elf::MAX_SYMBOLS {
+                    return symbols;
+                }
+                let entry = range.start + index * section.entry_size;
+                let val = if let Ok(val) = reader.numbers(entry, sizes) {
+                    val
+                } else {
+                    break; // truncated table
+                };
+                let (info, shndx, value, size) = if self.is64 {
+                    (val[1], val[3], val[4], val[5])
+                } else {
+                    (val[3], val[5], val[1], val[2])
+                };
+                #[allow(clippy::cast_possible_truncation)]
+                symbols.push(Symbol {
+                    table,
+                    entry,
+                    name: string(&strings, val[0]),
+                    value,
+                    size,
+                    info: info as u8,
+                    offset: self.symbol_offset(shndx, value),
+                });
+            }
+        }
+        symbols
+    }
+
+    /// Get named regions of the file: headers and sections data.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    ///
+    /// # Return value
+    ///
+    /// List of regions sorted by start offset.
+    pub fn regions(&self, size: u64) -> Vec<(Range<u64>, String)> {
+        let mut regions = vec![(0..self.header[7], "ELF header".to_string())];
+        let (offset, entry_size, count) = self.table(false);
+        if count != 0 {
+            regions.push((
+                offset..offset.saturating_add(entry_size * count),
+                "Program headers".to_string(),
+            ));
+        }
+        let (offset, entry_size, count) = self.table(true);
+        if count != 0 {
+            regions.push((
+                offset..offset.saturating_add(entry_size * count),
+                "Section headers".to_string(),
+            ));
+        }
+        for section in &self.sections {
+            if let Some(range) = section.range() {
+                regions.push((range, section.name.clone()));
+            }
+        }
+
+        let mut regions: Vec<(Range<u64>, String)> = regions
+            .into_iter()
+            .filter(|(range, _)| range.start < size && !range.is_empty())
+            .map(|(range, name)| (range.start..range.end.min(size), name))
+            .collect();
+        regions.sort_by_key(|(range, _)| range.start);
+        regions
+    }
+
+    /// Build the structure tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbols` - list of symbols
+    ///
+    /// # Return value
+    ///
+    /// List of nodes in the tree order (parent before its children).
+    pub fn tree(&self, symbols: &[Symbol]) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut push = |depth: usize, text: String, range: Range<u64>| {
+            nodes.push(Node { depth, text, range });
+        };
+
+        // file header
+        push(0, "ELF header".to_string(), 0..self.header[7]);
+        push(
+            1,
+            format!("Class: {}", if self.is64 { "ELF64" } else { "ELF32" }),
+            4..5,
+        );
+        push(
+            1,
+            format!(
+                "Data: {} endian",
+                if self.little_endian { "little" } else { "big" }
+            ),
+            5..6,
+        );
+        let mut offset = Elf::IDENT_SIZE as u64;
+        for (&(field, size), &value) in Elf::HEADER.iter().zip(self.header.iter()) {
+            let size = if size != 0 {
+                size
+            } else if self.is64 {
+                8
+            } else {
+                4
+            } as u64;
+            #[allow(clippy::cast_possible_truncation)]
+            let value = match field {
+                "Type" => name(Elf::TYPES, value as u32),
+                "Machine" => name(Elf::MACHINES, value as u32),
+                "Version"
+                | "Header size"
+                | "Program header size"
+                | "Program headers count"
+                | "Section header size"
+                | "Section headers count"
+                | "Section names index" => value.to_string(),
+                _ => format!("0x{:x}", value),
+            };
+            push(1, format!("{}: {}", field, value), offset..offset + size);
+            offset += size;
+        }
+
+        // program headers
+        let (offset, entry_size, count) = self.table(false);
+        if count != 0 {
+            push(
+                0,
+                format!("Program headers ({})", count),
+                offset..offset.saturating_add(entry_size * count),
+            );
+            for (index, segment) in self.segments.iter().enumerate() {
+                let flags: String = [(4, 'R'), (2, 'W'), (1, 'X')]
+                    .iter()
+                    .map(|&(bit, chr)| if segment.flags & bit != 0 { chr } else { '-' })
+                    .collect();
+                let text = format!(
+                    "[{}] {} {} size 0x{:x}, address 0x{:x}",
+                    index,
+                    name(Elf::SEGMENTS, segment.stype),
+                    flags,
+                    segment.size,
+                    segment.address
+                );
+                let range = if segment.size == 0 {
+                    segment.header..segment.header.saturating_add(entry_size)
+                } else {
+                    segment.offset..segment.offset.saturating_add(segment.size)
+                };
+                push(1, text, range);
+            }
+        }
+
+        // section headers
+        let (offset, entry_size, count) = self.table(true);
+        if count != 0 {
+            push(
+                0,
+                format!("Section headers ({})", count),
+                offset..offset.saturating_add(entry_size * count),
+            );
+            for (index, section) in self.sections.iter().enumerate() {
+                let text = format!(
+                    "[{}] {} {} size 0x{:x}, address 0x{:x}",
+                    index,
+                    if section.name.is_empty() {
+                        "-"
+                    } else {
+                        &section.name
+                    },
+                    name(Elf::SECTIONS, section.stype),
+                    section.size,
+                    section.address
+                );
+                let range = section
+                    .range()
+                    .unwrap_or(section.header..section.header.saturating_add(entry_size));
+                push(1, text, range);
+            }
+        }
+
+        // symbols grouped by tables
+        let mut table = None;
+        for symbol in symbols {
+            if table != Some(symbol.table) {
+                table = Some(symbol.table);
+                let section = &self.sections[symbol.table];
+                push(
+                    0,
+                    format!("Symbols ({})", section.name),
+                    section.range().unwrap_or(section.offset..section.offset),
+                );
+            }
+            let text = format!(
+                "{} {} {} value 0x{:x}, size 0x{:x}",
+                if symbol.name.is_empty() {
+                    "-"
+                } else {
+                    &symbol.name
+                },
+                name(Elf::SYMBOL_TYPES, u32::from(symbol.info & 0x0f)),
+                name(Elf::SYMBOL_BINDS, u32::from(symbol.info >> 4)),
+                symbol.value,
+                symbol.size
+            );
+            let range = if let Some(offset) = symbol.offset {
+                offset..offset.saturating_add(symbol.size.max(1))
+            } else {
+                symbol.entry..symbol.entry + 1
+            };
+            push(1, text, range);
+        }
+
+        nodes
+    }
+
+    /// Get location of the headers table.
+    ///
+    /// # Arguments
+    ///
+    /// * `sections` - section headers table if `true`, program headers otherwise
+    ///
+    /// # Return value
+    ///
+    /// Offset, entry size and number of entries.
+    fn table(&self, sections: bool) -> (u64, u64, u64) {
+        if sections {
+            (self.header[5], self.header[10], self.header[11])
+        } else {
+            (self.header[4], self.header[8], self.header[9])
+        }
+    }
+
+    /// Get file offset of the symbol data.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the section containing the symbol
+    /// * `value` - symbol value
+    ///
+    /// # Return value
+    ///
+    /// File offset or `None` if symbol has no data in the file.
+    fn symbol_offset(&self, index: u64, value: u64) -> Option<u64> {
+        if index == 0 || index >= Elf::SHN_LORESERVE {
+            return None;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let section = self.sections.get(index as usize)?;
+        let range = section.range()?;
+        let offset = if self.header[0] == Elf::ET_REL {
+            range.start.checked_add(value)?
+        } else {
+            range
+                .start
+                .checked_add(value.checked_sub(section.address)?)?
+        };
+        if range.contains(&offset) {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
+    /// Create "not an ELF" error.
+    fn not_elf() -> Error {
+        Elf::invalid("Not an ELF file")
+    }
+
+    /// Create "invalid data" error.
+    fn invalid(msg: &str) -> Error {
+        Error::new(ErrorKind::InvalidData, msg)
+    }
+
+    /// Object file types.
+    const TYPES: &'static [(u32, &'static str)] = &[
+        (0, "NONE"),
+        (1, "REL"),
+        (2, "EXEC"),
+        (3, "DYN"),
+        (4, "CORE"),
+    ];
+
+    /// Target architectures.
+    const MACHINES: &'static [(u32, &'static str)] = &[
+        (0, "None"),
+        (3, "x86"),
+        (8, "MIPS"),
+        (20, "PowerPC"),
+        (21, "PowerPC64"),
+        (40, "ARM"),
+        (42, "SuperH"),
+        (50, "IA-64"),
+        (62, "x86-64"),
+        (83, "AVR"),
+        (94, "Xtensa"),
+        (183, "AArch64"),
+        (243, "RISC-V"),
+        (258, "LoongArch"),
+    ];
+
+    /// Segment types.
+    const SEGMENTS: &'static [(u32, &'static str)] = &[
+        (0, "NULL"),
+        (1, "LOAD"),
+        (2, "DYNAMIC"),
+        (3, "INTERP"),
+        (4, "NOTE"),
+        (5, "SHLIB"),
+        (6, "PHDR"),
+        (7, "TLS"),
+        (0x6474_e550, "GNU_EH_FRAME"),
+        (0x6474_e551, "GNU_STACK"),
+        (0x6474_e552, "GNU_RELRO"),
+        (0x6474_e553, "GNU_PROPERTY"),
+    ];
+
+    /// Section types.
+    const SECTIONS: &'static [(u32, &'static str)] = &[
+        (0, "NULL"),
+        (1, "PROGBITS"),
+        (2, "SYMTAB"),
+        (3, "STRTAB"),
+        (4, "RELA"),
+        (5, "HASH"),
+        (6, "DYNAMIC"),
+        (7, "NOTE"),
+        (8, "NOBITS"),
+        (9, "REL"),
+        (10, "SHLIB"),
+        (11, "DYNSYM"),
+        (14, "INIT_ARRAY"),
+        (15, "FINI_ARRAY"),
+        (16, "PREINIT_ARRAY"),
+        (17, "GROUP"),
+        (18, "SYMTAB_SHNDX"),
+        (0x6fff_fff6, "GNU_HASH"),
+        (0x6fff_fffd, "VERDEF"),
+        (0x6fff_fffe, "VERNEED"),
+        (0x6fff_ffff, "VERSYM"),
+    ];
+
+    /// Symbol types.
+    const SYMBOL_TYPES: &'static [(u32, &'static str)] = &[
+        (0, "NOTYPE"),
+        (1, "OBJECT"),
+        (2, "FUNC"),
+        (3, "SECTION"),
+        (4, "FILE"),
+        (5, "COMMON"),
+        (6, "TLS"),
+    ];
+
+    /// Symbol bindings.
+    const SYMBOL_BINDS: &'static [(u32, &'static str)] =
+        &[(0, "LOCAL"), (1, "GLOBAL"), (2, "WEAK")];
+}
+
+impl Section {
+    /// Get file range of the section data.
+    ///
+    /// # Return value
+    ///
+    /// Range of the section data, `None` if section has no data in the file.
+    pub fn range(&self) -> Option<Range<u64>> {
+        if self.stype == 0 || self.stype == Elf::SHT_NOBITS || self.size == 0 {
+            return None;
+        }
+        Some(self.offset..self.offset.checked_add(self.size)?)
+    }
+}
+
+#[test]
+fn test_elf() {
+    // build a minimal 64-bit little endian relocatable file:
+    // header, .text, .shstrtab, .symtab, .strtab, section headers
+    fn put(data: &mut Vec<u8>, value: u64, size: usize) {
+        data.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+    let mut data = b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+    let shstrtab = b"\0.text\0.shstrtab\0.symtab\0.strtab\0";
+    let strtab = b"\0main\0";
+    let text_off = 0x40;
+    let shstrtab_off = text_off + 0x10;
+    let symtab_off = 0x80;
+    let strtab_off = symtab_off + 48;
+    let shoff = 0x100;
+    put(&mut data, 1, 2); // type
+    put(&mut data, 62, 2); // machine
+    put(&mut data, 1, 4); // version
+    put(&mut data, 0, 8); // entry
+    put(&mut data, 0, 8); // phoff
+    put(&mut data, shoff, 8); // shoff
+    put(&mut data, 0, 4); // flags
+    put(&mut data, 64, 2); // ehsize
+    put(&mut data, 56, 2); // phentsize
+    put(&mut data, 0, 2); // phnum
+    put(&mut data, 64, 2); // shentsize
+    put(&mut data, 5, 2); // shnum
+    put(&mut data, 2, 2); // shstrndx
+    data.resize(text_off as usize, 0);
+    data.extend_from_slice(&[0x90; 0x10]);
+    data.extend_from_slice(shstrtab);
+    data.resize(symtab_off as usize + 24, 0);
+    put(&mut data, 1, 4); // name
+    put(&mut data, 0x12, 1); // info: GLOBAL FUNC
+    put(&mut data, 0, 1); // other
+    put(&mut data, 1, 2); // shndx
+    put(&mut data, 4, 8); // value
+    put(&mut data, 8, 8); // size
+    data.extend_from_slice(strtab);
+    data.resize(shoff as usize + 64, 0);
+    let sections = [
+        (1, 1, text_off, 0x10, 0, 0),
+        (7, 3, shstrtab_off, shstrtab.len() as u64, 0, 0),
+        (17, 2, symtab_off, 48, 4, 24),
+        (25, 3, strtab_off, strtab.len() as u64, 0, 0),
+    ];
+    for &(name, stype, offset, size, link, entsize) in &sections {
+        put(&mut data, name, 4);
+        put(&mut data, stype, 4);
+        put(&mut data, 0, 8); // flags
+        put(&mut data, 0, 8); // addr
+        put(&mut data, offset, 8);
+        put(&mut data, size, 8);
+        put(&mut data, link, 4);
+        put(&mut data, 0, 4); // info
+        put(&mut data, 0, 8); // align
+        put(&mut data, entsize, 8);
+    }
+
+    let size = data.len() as u64;
+    let mut read = |offset: u64, size: usize| -> Result<Vec<u8>> {
+        Ok(data[offset as usize..offset as usize + size].to_vec())
+    };
+    let elf = Elf::parse(size, &mut read).unwrap();
+    assert!(elf.is64);
+    assert!(elf.little_endian);
+    assert!(elf.segments.is_empty());
+    let names: Vec<&str> = elf.sections.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["", ".text", ".shstrtab", ".symtab", ".strtab"]);
+
+    let regions = elf.regions(size);
+    assert_eq!(regions[0], (0..64, "ELF header".to_string()));
+    assert_eq!(regions[1], (0x40..0x50, ".text".to_string()));
+    assert_eq!(regions.last().unwrap().1, "Section headers");
+
+    let symbols = elf.symbols(size, &mut read);
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "main");
+    assert_eq!(symbols[0].table, 3);
+    assert_eq!(symbols[0].offset, Some(0x44));
+
+    let tree = elf.tree(&symbols);
+    assert_eq!(tree[0].line(), "00000000  ELF header");
+    assert_eq!(tree[3].line(), "00000010    Type: REL");
+    assert_eq!(tree[4].line(), "00000012    Machine: x86-64");
+    let last = tree.last().unwrap();
+    assert_eq!(last.text, "main FUNC GLOBAL value 0x4, size 0x8");
+    assert_eq!(last.range, 0x44..0x4c);
+
+    // truncated and foreign data
+    assert!(Elf::parse(0x20, &mut read).is_err());
+    let mut read = |_: u64, size: usize| -> Result<Vec<u8>> { Ok(vec![0; size]) };
+    assert_eq!(
+        Elf::parse(size, &mut read).err().unwrap().to_string(),
+        "Not an ELF file"
+    );
+}
+
+#[test]
+fn test_elf_malformed() {
+    fn put(data: &mut Vec<u8>, value: u64, size: usize) {
+        data.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+    // 64-bit little endian relocatable file with the section headers
+    // following the file header
+    fn build(shentsize: u64, sections: &[(u64, u64, u64, u64, u64)]) -> Vec<u8> {
+        let mut data = b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00".to_vec();
+        put(&mut data, 1, 2); // type
+        put(&mut data, 62, 2); // machine
+        put(&mut data, 1, 4); // version
+        put(&mut data, 0, 8); // entry
+        put(&mut data, 0, 8); // phoff
+        put(&mut data, 0x40, 8); // shoff
+        put(&mut data, 0, 4); // flags
+        put(&mut data, 64, 2); // ehsize
+        put(&mut data, 56, 2); // phentsize
+        put(&mut data, 0, 2); // phnum
+        put(&mut data, shentsize, 2); // shentsize
+        put(&mut data, sections.len() as u64 + 1, 2); // shnum
+        put(&mut data, 0, 2); // shstrndx
+        data.resize(0x80, 0); // null section
+        for &(stype, offset, size, link, entsize) in sections {
+            put(&mut data, 0, 4); // name
+            put(&mut data, stype, 4);
+            put(&mut data, 0, 8); // flags
+            put(&mut data, 0, 8); // addr
+            put(&mut data, offset, 8);
+            put(&mut data, size, 8);
+            put(&mut data, link, 4);
+            put(&mut data, 0, 4); // info
+            put(&mut data, 0, 8); // align
+            put(&mut data, entsize, 8);
+        }
+        data
+    }
+    fn parse(data: &[u8]) -> Result<(Elf, Vec<Symbol>)> {
+        let mut read = |offset: u64, size: usize| -> Result<Vec<u8>> {
+            Ok(data[offset as usize..offset as usize + size].to_vec())
+        };
+        let size = data.len() as u64;
+        let elf = Elf::parse(size, &mut read)?;
+        let symbols = elf.symbols(size, &mut read);
+        elf.regions(size);
+        elf.tree(&symbols);
+        Ok((elf, symbols))
+    }
+
+    // truncated headers
+    let data = build(64, &[(1, 0, 0, 0, 0)]);
+    assert!(parse(&data[..0x30]).is_err());
+    assert!(parse(&data[..0xa0]).is_err());
+    assert_eq!(
+        parse(&build(16, &[(1, 0, 0, 0, 0)]))
+            .err()
+            .unwrap()
+            .to_string(),
+        "Invalid section header size"
+    );
+
+    // section table at the end of address space
+    let mut data = build(64, &[]);
+    data[0x28..0x30].copy_from_slice(&(u64::MAX - 0x10).to_le_bytes());
+    data[0x3c] = 2;
+    assert!(parse(&data).is_err());
+
+    // section names out of the file
+    let mut data = build(64, &[(3, 0x1000, 0x10, 0, 0), (3, u64::MAX, 2, 0, 0)]);
+    data[0x3e] = 1;
+    let (elf, _) = parse(&data).unwrap();
+    assert!(elf.sections.iter().all(|section| section.name.is_empty()));
+    data[0x3e] = 2;
+    assert!(parse(&data).is_ok());
+
+    // symbol table without the entry size
+    let mut data = build(64, &[(2, 0x100, 24 * 4, 0, 0)]);
+    data.resize(0x100 + 24 * 4, 0xff);
+    assert!(parse(&data).unwrap().1.is_empty());
+
+    // truncated symbol table: entries beyond the file are skipped
+    let mut data = build(64, &[(2, 0x100, 24 * 100, 0, 24)]);
+    data.resize(0x100 + 24 * 3, 0);
+    let (_, symbols) = parse(&data).unwrap();
+    assert_eq!(symbols.len(), 2);
+    assert!(symbols
+        .iter()
+        .all(|s| s.name.is_empty() && s.offset.is_none()));
+
+    // symbol value points out of the section
+    let mut data = build(64, &[(1, u64::MAX - 4, 4, 0, 0), (2, 0x100, 48, 0, 24)]);
+    data.resize(0x100 + 24, 0);
+    put(&mut data, 0, 4); // name
+    put(&mut data, 0x12, 1); // info
+    put(&mut data, 0, 1); // other
+    put(&mut data, 1, 2); // shndx
+    put(&mut data, 0x10, 8); // value
+    put(&mut data, u64::MAX, 8); // size
+    let (_, symbols) = parse(&data).unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].offset, None);
+
+    // number of symbols is limited
+    let count = Elf::MAX_SYMBOLS as u64 + 10;
+    let mut data = build(64, &[(2, 0x100, 24 * count, 0, 24)]);
+    data.resize((0x100 + 24 * count) as usize, 0);
+    assert_eq!(parse(&data).unwrap().1.len(), Elf::MAX_SYMBOLS);
+}
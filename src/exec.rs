@@ -0,0 +1,245 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::elf::Elf;
+use super::pe::Pe;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+
+/// Executable file (ELF or PE).
+pub enum Executable {
+    Elf(Elf),
+    Pe(Pe),
+}
+
+impl Executable {
+    /// Parse executable headers, the format is detected by the signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    /// * `read` - data reader: offset and size of the block
+    ///
+    /// # Return value
+    ///
+    /// Executable description.
+    pub fn parse(size: u64, read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>) -> Result<Self> {
+        let signature = Reader::new(read, size, true)
+            .bytes(0, 4)
+            .unwrap_or_default();
+        if signature == Elf::SIGNATURE {
+            Ok(Executable::Elf(Elf::parse(size, read)?))
+        } else if signature.starts_with(Pe::SIGNATURE) {
+            Ok(Executable::Pe(Pe::parse(size, read)?))
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "Unknown executable format",
+            ))
+        }
+    }
+
+    /// Get name of the executable format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Executable::Elf(_) => "ELF",
+            Executable::Pe(_) => "PE",
+        }
+    }
+
+    /// Get named regions of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    ///
+    /// # Return value
+    ///
+    /// List of regions sorted by start offset.
+    pub fn regions(&self, size: u64) -> Vec<(Range<u64>, String)> {
+        match self {
+            Executable::Elf(elf) => elf.regions(size),
+            Executable::Pe(pe) => pe.regions(size),
+        }
+    }
+
+    /// Build the structure tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    /// * `read` - data reader: offset and size of the block
+    ///
+    /// # Return value
+    ///
+    /// List of nodes in the tree order (parent before its children).
+    pub fn tree(
+        &self,
+        size: u64,
+        read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>,
+    ) -> Vec<Node> {
+        match self {
+            Executable::Elf(elf) => {
+                let symbols = elf.symbols(size, read);
+                elf.tree(&symbols)
+            }
+            Executable::Pe(pe) => pe.tree(size, read),
+        }
+    }
+}
+
+/// Node of the executable structure tree.
+pub struct Node {
+    /// Nesting level.
+    pub depth: usize,
+    /// Description.
+    pub text: String,
+    /// File range described by the node.
+    pub range: Range<u64>,
+}
+
+impl Node {
+    /// Get text representation of the node.
+    pub fn line(&self) -> String {
+        format!(
+            "{:08x}  {:indent$}{}",
+            self.range.start,
+            "",
+            self.text,
+            indent = self.depth * 2
+        )
+    }
+}
+
+/// Bounds-checked file data reader.
+pub struct Reader<'a> {
+    /// Data reader: offset and size of the block.
+    read: &'a mut dyn FnMut(u64, usize) -> Result<Vec<u8>>,
+    /// Size of the file.
+    size: u64,
+    /// Byte order of the numbers.
+    pub little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    /// Create new reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `read` - data reader: offset and size of the block
+    /// * `size` - size of the file
+    /// * `little_endian` - byte order of the numbers
+    ///
+    /// # Return value
+    ///
+    /// Reader instance.
+    pub fn new(
+        read: &'a mut dyn FnMut(u64, usize) -> Result<Vec<u8>>,
+        size: u64,
+        little_endian: bool,
+    ) -> Self {
+        Self {
+            read,
+            size,
+            little_endian,
+        }
+    }
+
+    /// Read block of data.
+    pub fn bytes(&mut self, offset: u64, size: usize) -> Result<Vec<u8>> {
+        if offset
+            .checked_add(size as u64)
+            .filter(|&end| end <= self.size)
+            .is_none()
+        {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("Data at 0x{:x} is out of file range", offset),
+            ));
+        }
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        (self.read)(offset, size)
+    }
+
+    /// Read sequence of numbers.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start offset
+    /// * `sizes` - sizes of the numbers
+    ///
+    /// # Return value
+    ///
+    /// Values of the numbers.
+    pub fn numbers(&mut self, offset: u64, sizes: &[usize]) -> Result<Vec<u64>> {
+        let data = self.bytes(offset, sizes.iter().sum())?;
+        let fold = |value: u64, byte: &u8| (value << 8) | u64::from(*byte);
+        let mut pos = 0;
+        Ok(sizes
+            .iter()
+            .map(|&size| {
+                let num = &data[pos..pos + size];
+                pos += size;
+                if self.little_endian {
+                    num.iter().rev().fold(0, fold)
+                } else {
+                    num.iter().fold(0, fold)
+                }
+            })
+            .collect())
+    }
+
+    /// Read null-terminated string.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start offset
+    /// * `max` - max length of the string
+    ///
+    /// # Return value
+    ///
+    /// String, truncated at the end of file.
+    pub fn string(&mut self, offset: u64, max: usize) -> Result<String> {
+        #[allow(clippy::cast_possible_truncation)]
+        let max = (self.size.saturating_sub(offset) as usize).min(max);
+        let data = self.bytes(offset, max)?;
+        Ok(string(&data, 0))
+    }
+}
+
+/// Get null-terminated string from the string table.
+///
+/// # Arguments
+///
+/// * `table` - string table
+/// * `offset` - offset of the string inside the table
+///
+/// # Return value
+///
+/// String, empty if offset is out of the table.
+pub fn string(table: &[u8], offset: u64) -> String {
+    #[allow(clippy::cast_possible_truncation)]
+    let start = (offset as usize).min(table.len());
+    let data = &table[start..];
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).to_string()
+}
+
+/// Get name of the numeric constant.
+///
+/// # Arguments
+///
+/// * `names` - table of the constants names
+/// * `value` - constant value
+///
+/// # Return value
+///
+/// Name of the constant or its hex value if the name is unknown.
+pub fn name(names: &[(u32, &str)], value: u32) -> String {
+    names
+        .iter()
+        .find(|&&(id, _)| id == value)
+        .map_or_else(|| format!("0x{:x}", value), |(_, name)| name.to_string())
+}
@@ -1,11 +1,15 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
+use super::image::{Format, Image};
+use super::lock::{self, Holder};
 use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
 use std::ops::Range;
-use std::path::Path;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 /// Editable file.
 pub struct File {
@@ -17,6 +21,20 @@ pub struct File {
     pub size: u64,
     /// Cached map of real changes (offset -> new byte value).
     pub changes: BTreeMap<u64, u8>,
+    /// Save strategy.
+    pub save_mode: SaveMode,
+    /// Backup policy used on save.
+    pub backup: Backup,
+    /// Content format.
+    pub format: Format,
+    /// Memory image (Intel HEX and S-record formats).
+    image: Option<Image>,
+    /// Last known state of the file on disk.
+    stamp: Stamp,
+    /// Handle used to hold the advisory lock while the file is modified.
+    lock: Option<std::fs::File>,
+    /// Ignore data appended to the file on disk (the user kept the data).
+    keep_appends: bool,
     /// Data cache.
     cache: Cache,
 }
@@ -24,6 +42,8 @@ pub struct File {
 impl File {
     /// Size of the block for read/write operations.
     const BLOCK_SIZE: usize = Cache::SIZE;
+    /// Value of bytes in gaps of memory images.
+    const GAP_FILL: u8 = 0xff;
 
     /// Open file.
     ///
@@ -46,13 +66,59 @@ impl File {
         if meta.len() == 0 {
             return Err(Error::new(ErrorKind::UnexpectedEof, "File is empty"));
         }
-        Ok(Self {
+        let mut instance = Self {
             file,
-            path: path.into_os_string().into_string().unwrap(),
             size: meta.len(),
             changes: BTreeMap::new(),
+            save_mode: SaveMode::InPlace,
+            backup: Backup::None,
+            format: Format::from_path(&path),
+            image: None,
+            path: path.into_os_string().into_string().unwrap(),
+            stamp: Stamp::new(&meta),
+            lock: None,
+            keep_appends: false,
             cache: Cache::new(),
-        })
+        };
+        instance.load_image();
+        Ok(instance)
+    }
+
+    /// Load memory image from Intel HEX or S-record file.
+    /// Falls back to the raw format if the file can not be parsed.
+    fn load_image(&mut self) {
+        self.image = None;
+        if self.format == Format::Raw {
+            return;
+        }
+        let mut text = Vec::new();
+        let image = self
+            .file
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.file.read_to_end(&mut text))
+            .and_then(|_| Image::parse(&text, self.format));
+        if let Ok(image) = image {
+            self.size = image.end();
+            self.image = Some(image);
+        } else {
+            self.format = Format::Raw;
+        }
+    }
+
+    /// Get the lowest address with data.
+    pub fn start(&self) -> u64 {
+        self.image.as_ref().map_or(0, Image::start)
+    }
+
+    /// Get ranges without data (gaps of memory image).
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to check
+    pub fn gaps(&self, range: &Range<u64>) -> Vec<Range<u64>> {
+        self.image
+            .as_ref()
+            .map_or_else(Vec::new, |image| image.gaps(range))
     }
 
     /// Check if file is modofied.
@@ -60,6 +126,122 @@ impl File {
         !self.changes.is_empty()
     }
 
+    /// Check if file was modified by another process.
+    /// Data appended after keeping the current state is not reported.
+    ///
+    /// # Return value
+    ///
+    /// `true` if file on disk differs from the last known state.
+    pub fn is_changed_on_disk(&mut self) -> bool {
+        match std::fs::metadata(&self.path) {
+            Ok(meta) => {
+                let stamp = Stamp::new(&meta);
+                if stamp == self.stamp {
+                    false
+                } else if self.keep_appends
+                    && stamp.inode == self.stamp.inode
+                    && stamp.size > self.stamp.size
+                {
+                    self.stamp = stamp;
+                    false
+                } else {
+                    true
+                }
+            }
+            Err(_) => true,
+        }
+    }
+
+    /// Keep the current data, ignore the changes on disk and data appended
+    /// to the file later.
+    pub fn keep(&mut self) {
+        self.update_stamp();
+        self.keep_appends = true;
+    }
+
+    /// Accept the current state of the file on disk as known (keep our data).
+    pub fn update_stamp(&mut self) {
+        if let Ok(meta) = std::fs::metadata(&self.path) {
+            self.stamp = Stamp::new(&meta);
+        }
+    }
+
+    /// Check the file on disk for appended data (follow mode).
+    ///
+    /// # Return value
+    ///
+    /// Number of appended bytes.
+    pub fn grow(&mut self) -> Result<u64> {
+        if self.image.is_some() {
+            return Ok(0);
+        }
+        let meta = std::fs::metadata(&self.path)?;
+        let stamp = Stamp::new(&meta);
+        if stamp.inode != self.stamp.inode || stamp.size <= self.size {
+            return Ok(0);
+        }
+        let appended = stamp.size - self.size;
+        self.size = stamp.size;
+        self.stamp = stamp;
+        Ok(appended)
+    }
+
+    /// Take an advisory lock on the file to prevent concurrent editing by
+    /// other instances of the editor.
+    ///
+    /// # Return value
+    ///
+    /// Owner of the lock, `None` if the lock was taken or can not be set
+    /// (e.g. read only file). The lock is held until the file is saved,
+    /// reloaded or closed.
+    pub fn lock(&mut self) -> Option<Holder> {
+        if self.lock.is_some() {
+            return None;
+        }
+        let file = OpenOptions::new().write(true).open(&self.path).ok()?;
+        match lock::try_lock(&file) {
+            Ok(None) => {
+                self.lock = Some(file);
+                None
+            }
+            Ok(Some(holder)) => Some(holder),
+            Err(_) => None,
+        }
+    }
+
+    /// Release the advisory lock.
+    pub fn unlock(&mut self) {
+        // closing the handle releases the lock
+        self.lock = None;
+    }
+
+    /// Check if the file is locked by the current process.
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    /// Reload file from disk, all changes will be lost.
+    pub fn reload(&mut self) -> Result<()> {
+        let file = OpenOptions::new().read(true).open(&self.path)?;
+        let meta = file.metadata()?;
+        if meta.len() == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "File is empty"));
+        }
+
+        self.file = file;
+        self.size = meta.len();
+        self.stamp = Stamp::new(&meta);
+        self.load_image();
+
+        // reset
+        self.cache.data.clear();
+        self.changes.clear();
+        self.keep_appends = false;
+        self.unlock();
+
+        Ok(())
+    }
+
     /// Read up to `size` bytes from file.
     ///
     /// # Arguments
@@ -78,20 +260,24 @@ impl File {
         let max_size = (self.size - offset) as usize;
         let size = size.min(max_size);
 
-        // update cache if needed
-        if !self.cache.has(offset, size) {
-            let cache_size = Cache::SIZE.min(max_size).max(size);
-            self.cache.data.resize(cache_size, 0);
-            self.cache.start = offset;
-            self.file.seek(SeekFrom::Start(offset))?;
-            self.file.read_exact(&mut self.cache.data)?;
-        }
+        let mut data = if let Some(image) = &self.image {
+            image.read(offset, size, File::GAP_FILL)
+        } else {
+            // update cache if needed
+            if !self.cache.has(offset, size) {
+                let cache_size = Cache::SIZE.min(max_size).max(size);
+                self.cache.data.resize(cache_size, 0);
+                self.cache.start = offset;
+                self.file.seek(SeekFrom::Start(offset))?;
+                self.file.read_exact(&mut self.cache.data)?;
+            }
 
-        // get file data
-        #[allow(clippy::cast_possible_truncation)]
-        let start = (offset - self.cache.start) as usize;
-        let end = start + size;
-        let mut data = self.cache.data[start..end].to_vec();
+            // get file data
+            #[allow(clippy::cast_possible_truncation)]
+            let start = (offset - self.cache.start) as usize;
+            let end = start + size;
+            self.cache.data[start..end].to_vec()
+        };
 
         // apply changes
         for (&addr, &value) in self.changes.range(offset..offset + size as u64) {
@@ -104,19 +290,153 @@ impl File {
     }
 
     /// Write changes to the current file.
-    pub fn save(&mut self) -> Result<()> {
-        // reopen file with the write permission
-        let mut file = OpenOptions::new().write(true).open(self.path.clone())?;
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - long time operation handler
+    pub fn save(&mut self, progress: &mut dyn ProgressHandler) -> Result<()> {
+        let mode = self.effective_save_mode();
+        self.make_backup(mode)?;
 
-        // write changes
-        for (&offset, &value) in &self.changes {
-            file.seek(SeekFrom::Start(offset))?;
-            file.write_all(&[value])?;
+        if let Some(image) = &mut self.image {
+            for (&offset, &value) in &self.changes {
+                image.put(offset, &[value]);
+            }
         }
 
+        match mode {
+            SaveMode::InPlace if self.image.is_none() => self.save_inplace()?,
+            SaveMode::InPlace => self.save_rewrite(progress)?,
+            SaveMode::Atomic => self.save_atomic(progress)?,
+        }
+        self.update_stamp();
+
         // reset
         self.cache.data.clear();
         self.changes.clear();
+        self.keep_appends = false;
+
+        // the lock is taken again with the next modification
+        self.unlock();
+
+        Ok(())
+    }
+
+    /// Get the save strategy applicable to the file. Atomic replacement
+    /// creates a new inode, which breaks hard links and changes the owner if
+    /// the current user can't restore it, such files are saved in place.
+    pub fn effective_save_mode(&self) -> SaveMode {
+        if self.save_mode == SaveMode::Atomic {
+            if let Ok(meta) = self.file.metadata() {
+                if meta.nlink() > 1 || !can_chown(&meta) {
+                    return SaveMode::InPlace;
+                }
+            }
+        }
+        self.save_mode
+    }
+
+    /// Copy owner and permissions of the current file to the file that
+    /// replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - path to the new file
+    pub fn copy_attributes(&self, file: &Path) -> Result<()> {
+        let meta = self.file.metadata()?;
+        std::os::unix::fs::chown(file, Some(meta.uid()), Some(meta.gid()))?;
+        // set permissions after the owner, chown clears setuid/setgid bits
+        std::fs::set_permissions(file, meta.permissions())
+    }
+
+    /// Keep the previous version of the file according to the backup policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - save strategy to be used
+    pub fn make_backup(&self, mode: SaveMode) -> Result<()> {
+        if let Some(backup) = self.backup.path(&self.path) {
+            if mode == SaveMode::Atomic {
+                // original inode will be replaced, so a hard link is enough
+                std::fs::remove_file(&backup).ok();
+                if std::fs::hard_link(&self.path, &backup).is_err() {
+                    std::fs::copy(&self.path, &backup)?;
+                }
+            } else {
+                std::fs::copy(&self.path, &backup)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write changes directly to the current file.
+    fn save_inplace(&mut self) -> Result<()> {
+        // reopen file with the write permission
+        let mut file = OpenOptions::new().write(true).open(&self.path)?;
+
+        // coalesce changed bytes into contiguous runs
+        let mut runs: Vec<(u64, Vec<u8>)> = Vec::new();
+        for (&offset, &value) in &self.changes {
+            if let Some((start, data)) = runs.last_mut() {
+                if *start + data.len() as u64 == offset {
+                    data.push(value);
+                    continue;
+                }
+            }
+            runs.push((offset, vec![value]));
+        }
+
+        // write changes
+        for (offset, data) in &runs {
+            file.seek(SeekFrom::Start(*offset))?;
+            file.write_all(data)?;
+        }
+
+        file.sync_all()
+    }
+
+    /// Rewrite the entire file.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - long time operation handler
+    fn save_rewrite(&mut self, progress: &mut dyn ProgressHandler) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.write_content(&mut file, progress)?;
+        file.sync_all()
+    }
+
+    /// Write the file with changes to a temporary file and replace the
+    /// original one with it.
+    ///
+    /// # Arguments
+    ///
+    /// * `progress` - long time operation handler
+    fn save_atomic(&mut self, progress: &mut dyn ProgressHandler) -> Result<()> {
+        let path = PathBuf::from(&self.path);
+        let name = path.file_name().unwrap().to_string_lossy();
+        let tmp = path.with_file_name(format!(".{}.xvi-{}", name, std::process::id()));
+
+        // temporary file must be in the same directory to be renamed atomically
+        let mut tmp_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&tmp)?;
+        let status = self
+            .write_content(&mut tmp_file, progress)
+            .and_then(|_| tmp_file.sync_all())
+            .and_then(|_| self.copy_attributes(&tmp))
+            .and_then(|_| std::fs::rename(&tmp, &path));
+        if let Err(err) = status {
+            std::fs::remove_file(&tmp).ok();
+            return Err(err);
+        }
+
+        self.file = tmp_file;
 
         Ok(())
     }
@@ -126,45 +446,220 @@ impl File {
     /// # Arguments
     ///
     /// * `file` - path to the new file
+    /// * `format` - format of the new file
+    /// * `fill` - value of the unused bytes for format conversion
     /// * `progress` - long time operation handler
-    pub fn save_as(&mut self, file: &Path, progress: &mut dyn ProgressHandler) -> Result<()> {
+    pub fn save_as(
+        &mut self,
+        file: &Path,
+        format: Format,
+        fill: u8,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<()> {
         // create new file
         let mut new_file = OpenOptions::new()
             .read(true)
             .write(true)
             .create(true)
-            .open(&file)?;
-        new_file.set_len(0)?;
+            .truncate(true)
+            .open(file)?;
 
-        let mut offset = 0;
-        loop {
-            // update progress info
-            let percent = (100.0 / self.size as f64) * offset as f64;
-            if !progress.update(percent as u8) {
-                return Err(Error::new(ErrorKind::Interrupted, "Aborted by user"));
-            }
-
-            // read and write
-            let data = self.read(offset, File::BLOCK_SIZE)?;
-            new_file.write_all(&data)?;
-            offset += data.len() as u64;
-            if offset >= self.size {
-                break; //eof
-            }
+        if format == Format::Raw && self.image.is_none() {
+            self.copy_to(&mut new_file, progress)?;
+        } else {
+            new_file.write_all(&self.convert(format, fill)?)?;
         }
+        new_file.sync_all()?;
 
         self.file = new_file;
 
         let path = std::fs::canonicalize(file)?;
         self.path = path.into_os_string().into_string().unwrap();
 
-        // reset
-        self.cache.data.clear();
-        self.changes.clear();
+        if self.format == format && format == Format::Raw {
+            self.update_stamp();
+            // reset
+            self.cache.data.clear();
+            self.changes.clear();
+            self.keep_appends = false;
+            self.unlock();
+        } else {
+            self.format = format;
+            self.reload()?;
+        }
 
         Ok(())
     }
 
+    /// Convert the file content with the current changes to another format.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - target format
+    /// * `fill` - value of the unused bytes: fills gaps of memory image on
+    ///   conversion to raw binary, skipped on conversion from raw
+    ///
+    /// # Return value
+    ///
+    /// Converted data.
+    fn convert(&mut self, format: Format, fill: u8) -> Result<Vec<u8>> {
+        let mut image = if let Some(image) = &self.image {
+            let mut image = image.clone();
+            for (&offset, &value) in &self.changes {
+                image.put(offset, &[value]);
+            }
+            image
+        } else {
+            #[allow(clippy::cast_possible_truncation)]
+            let data = self.read(0, self.size as usize)?;
+            Image::from_raw(&data, fill)
+        };
+        if format == Format::Raw {
+            Ok(image.to_raw(fill))
+        } else {
+            if image.end() == 0 {
+                // at least one record is required
+                image.put(0, &[fill]);
+            }
+            Ok(image.write(format))
+        }
+    }
+
+    /// Write the file content with the current changes in the current format.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - destination file
+    /// * `progress` - long time operation handler
+    fn write_content(
+        &mut self,
+        file: &mut std::fs::File,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<()> {
+        if let Some(image) = &self.image {
+            file.write_all(&image.write(self.format))
+        } else {
+            self.copy_to(file, progress)
+        }
+    }
+
+    /// Write the range of the file with the current changes to another file
+    /// (export range).
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to write
+    /// * `file` - path to the target file
+    /// * `append` - append data to the existing file instead of rewriting it
+    /// * `progress` - long time operation handler
+    pub fn export(
+        &mut self,
+        range: &Range<u64>,
+        file: &Path,
+        append: bool,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<()> {
+        self.check_target(file)?;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(append)
+            .truncate(!append)
+            .open(file)?;
+        self.copy_range(&mut file, range, progress)?;
+        file.sync_all()
+    }
+
+    /// Check that the target file of an export operation is not the current
+    /// file itself (opening it for writing would destroy the source data).
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - path to the target file
+    pub fn check_target(&self, file: &Path) -> Result<()> {
+        if let (Ok(source), Ok(target)) = (self.file.metadata(), std::fs::metadata(file)) {
+            if source.dev() == target.dev() && source.ino() == target.ino() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Target file is the source file",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the entire file content with the current changes to another file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - destination file
+    /// * `progress` - long time operation handler
+    fn copy_to(
+        &mut self,
+        file: &mut std::fs::File,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<()> {
+        self.copy_range(file, &(0..self.size), progress)
+    }
+
+    /// Write the range of the file content with the current changes to
+    /// another file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - destination file
+    /// * `range` - range to write
+    /// * `progress` - long time operation handler
+    fn copy_range(
+        &mut self,
+        file: &mut std::fs::File,
+        range: &Range<u64>,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<()> {
+        debug_assert!(range.end <= self.size);
+        self.read_blocks(range, File::BLOCK_SIZE, progress, &mut |_, data| {
+            file.write_all(data)?;
+            Ok(true)
+        })
+    }
+
+    /// Read the range of the file with the current changes block by block.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to read
+    /// * `block_size` - max size of the block
+    /// * `progress` - long time operation handler
+    /// * `handler` - block handler: offset and data of the block, returns
+    ///   `false` to stop reading
+    pub fn read_blocks(
+        &mut self,
+        range: &Range<u64>,
+        block_size: usize,
+        progress: &mut dyn ProgressHandler,
+        handler: &mut dyn FnMut(u64, &[u8]) -> Result<bool>,
+    ) -> Result<()> {
+        debug_assert!(block_size > 0);
+        let length = range.end - range.start;
+        let mut offset = range.start;
+        while offset < range.end {
+            // update progress info
+            let percent = (100.0 / length as f64) * (offset - range.start) as f64;
+            if !progress.update(percent as u8) {
+                return Err(Error::new(ErrorKind::Interrupted, "Aborted by user"));
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            let size = (range.end - offset).min(block_size as u64) as usize;
+            let data = self.read(offset, size)?;
+            if !handler(offset, &data)? {
+                break;
+            }
+            offset += size as u64;
+        }
+        Ok(())
+    }
+
     /// Find sequence inside the current file from the specified position.
     ///
     /// # Arguments
@@ -225,25 +720,25 @@ impl File {
                 }
             } else {
                 // forward search
-                if offset as u64 >= self.size {
+                if offset >= self.size {
                     offset = 0;
                     round = true;
                 }
             }
 
-            let file_data = self.read(offset as u64, File::BLOCK_SIZE)?;
+            let file_data = self.read(offset, File::BLOCK_SIZE)?;
             let mut window = file_data.windows(sequence.len());
             if !backward {
                 if let Some(pos) = window.position(|wnd| wnd == sequence) {
-                    return Ok(offset as u64 + pos as u64);
+                    return Ok(offset + pos as u64);
                 }
             } else if let Some(pos) = window.rposition(|wnd| wnd == sequence) {
-                return Ok(offset as u64 + pos as u64);
+                return Ok(offset + pos as u64);
             }
 
             if !backward {
                 offset += File::BLOCK_SIZE as u64;
-                if round && offset as u64 >= start {
+                if round && offset >= start {
                     break;
                 }
             }
@@ -269,10 +764,60 @@ impl File {
         pattern: &[u8],
         progress: &mut dyn ProgressHandler,
     ) -> Result<()> {
+        debug_assert!(!pattern.is_empty());
+
+        let mut pattern_pos = 0;
+        self.insert_from(offset, length, progress, &mut |buffer| {
+            for byte in buffer.iter_mut() {
+                *byte = pattern[pattern_pos];
+                pattern_pos += 1;
+                if pattern_pos == pattern.len() {
+                    pattern_pos = 0;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Insert data from the stream at the specified position in the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start position of bytes to insert
+    /// * `length` - number of bytes to insert
+    /// * `source` - source of the data to insert
+    /// * `progress` - long time operation handler
+    pub fn insert_data(
+        &mut self,
+        offset: u64,
+        length: u64,
+        source: &mut dyn Read,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<()> {
+        self.insert_from(offset, length, progress, &mut |buffer| {
+            source.read_exact(buffer)
+        })
+    }
+
+    /// Insert bytes at the specified position in the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start position of bytes to insert
+    /// * `length` - number of bytes to insert
+    /// * `progress` - long time operation handler
+    /// * `fill` - function to fill the next block of the added range
+    fn insert_from(
+        &mut self,
+        offset: u64,
+        length: u64,
+        progress: &mut dyn ProgressHandler,
+        fill: &mut dyn FnMut(&mut [u8]) -> Result<()>,
+    ) -> Result<()> {
+        self.check_raw()?;
         debug_assert!(self.changes.is_empty());
         debug_assert!(offset <= self.size);
         debug_assert!(length > 0);
-        debug_assert!(!pattern.is_empty());
 
         // reopen file with the write permission
         let mut file = OpenOptions::new().read(true).write(true).open(&self.path)?;
@@ -314,10 +859,9 @@ impl File {
             handled += size as u64;
         }
 
-        // fill with pattern
+        // fill the added range
         let max_offset = offset + length;
         let mut fill_offset = offset;
-        let mut pattern_pos = 0;
         while fill_offset < max_offset {
             // update progress info
             let percent = (100.0 / (self.size - offset + length) as f64) * handled as f64;
@@ -332,14 +876,7 @@ impl File {
                 size = (max_offset - fill_offset) as usize;
             }
 
-            // apply pattern
-            for byte in buffer.iter_mut().take(size) {
-                *byte = pattern[pattern_pos];
-                pattern_pos += 1;
-                if pattern_pos == pattern.len() {
-                    pattern_pos = 0;
-                }
-            }
+            fill(&mut buffer[..size])?;
 
             // write data
             file.seek(SeekFrom::Start(fill_offset))?;
@@ -352,6 +889,7 @@ impl File {
         file.sync_all()?;
 
         self.size += length;
+        self.update_stamp();
 
         // reset cache
         self.cache.data.clear();
@@ -359,6 +897,17 @@ impl File {
         Ok(())
     }
 
+    /// Check that the file is a raw binary (operation can change the file size).
+    pub fn check_raw(&self) -> Result<()> {
+        if self.image.is_some() {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Not supported for {} files", self.format.name()),
+            ));
+        }
+        Ok(())
+    }
+
     /// Cut out the specified range from the file.
     ///
     /// # Arguments
@@ -366,6 +915,7 @@ impl File {
     /// * `range` - range to cut out
     /// * `progress` - long time operation handler
     pub fn cut(&mut self, range: &Range<u64>, progress: &mut dyn ProgressHandler) -> Result<()> {
+        self.check_raw()?;
         debug_assert!(self.changes.is_empty());
         debug_assert!(!range.is_empty());
         debug_assert!(range.end <= self.size);
@@ -403,6 +953,7 @@ impl File {
         // truncate the file
         file.set_len(self.size)?;
         file.sync_all()?;
+        self.update_stamp();
 
         // reset cache
         self.cache.data.clear();
@@ -411,6 +962,147 @@ impl File {
     }
 }
 
+/// File save strategy.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SaveMode {
+    /// Write changed bytes directly into the original file.
+    InPlace,
+    /// Write result to a temporary file and rename it over the original one.
+    Atomic,
+}
+
+impl SaveMode {
+    /// Get save mode by its ID.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id.to_lowercase().as_str() {
+            "inplace" => Some(SaveMode::InPlace),
+            "atomic" => Some(SaveMode::Atomic),
+            _ => None,
+        }
+    }
+}
+
+/// Backup policy: how to keep the previous version of the file on save.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Backup {
+    /// Don't create backup.
+    None,
+    /// Single backup file `file~`.
+    Simple,
+    /// Backup file with timestamp `file.YYYYMMDD-hhmmss~` (UTC).
+    Timestamp,
+}
+
+impl Backup {
+    /// Get backup policy by its ID.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id.to_lowercase().as_str() {
+            "none" => Some(Backup::None),
+            "simple" => Some(Backup::Simple),
+            "timestamp" => Some(Backup::Timestamp),
+            _ => None,
+        }
+    }
+
+    /// Get path to the backup file.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - path to the origin file
+    ///
+    /// # Return value
+    ///
+    /// Path to the backup file, `None` if backup is disabled.
+    fn path(self, file: &str) -> Option<String> {
+        match self {
+            Backup::None => None,
+            Backup::Simple => Some(format!("{}~", file)),
+            Backup::Timestamp => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                Some(format!("{}.{}~", file, Backup::timestamp(now)))
+            }
+        }
+    }
+
+    /// Format UNIX time as `YYYYMMDD-hhmmss` (UTC).
+    fn timestamp(time: u64) -> String {
+        let days = time / 86400;
+        let secs = time % 86400;
+
+        // convert number of days to the civil date
+        let z = days + 719_468;
+        let era = z / 146_097;
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + u64::from(month <= 2);
+
+        format!(
+            "{:04}{:02}{:02}-{:02}{:02}{:02}",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs % 3600 / 60,
+            secs % 60
+        )
+    }
+}
+
+/// State of the file on disk, used to detect external modifications.
+#[derive(PartialEq)]
+struct Stamp {
+    /// Last modification time.
+    mtime: Option<SystemTime>,
+    /// File size.
+    size: u64,
+    /// Inode number.
+    inode: u64,
+}
+
+impl Stamp {
+    /// Create stamp from file metadata.
+    fn new(meta: &std::fs::Metadata) -> Self {
+        Self {
+            mtime: meta.modified().ok(),
+            size: meta.len(),
+            inode: meta.ino(),
+        }
+    }
+}
+
+/// Check if the current user can give a file the owner and the group of the
+/// specified one.
+///
+/// # Arguments
+///
+/// * `meta` - metadata of the file with the owner to set
+fn can_chown(meta: &std::fs::Metadata) -> bool {
+    let euid = unsafe { libc::geteuid() };
+    if euid == 0 {
+        return true;
+    }
+    if meta.uid() != euid {
+        return false;
+    }
+    if meta.gid() == unsafe { libc::getegid() } {
+        return true;
+    }
+    // supplementary groups of the process
+    let count = unsafe { libc::getgroups(0, std::ptr::null_mut()) };
+    #[allow(clippy::cast_sign_loss)]
+    let mut groups = vec![0; count.max(0) as usize];
+    let count = unsafe { libc::getgroups(count, groups.as_mut_ptr()) };
+    #[allow(clippy::cast_sign_loss)]
+    groups.truncate(count.max(0) as usize);
+    groups.contains(&meta.gid())
+}
+
 /// Data cache.
 struct Cache {
     /// Cache buffer.
@@ -451,6 +1143,15 @@ pub trait ProgressHandler {
     fn update(&mut self, percent: u8) -> bool;
 }
 
+/// Progress handler for non-interactive operations: never interrupted.
+pub struct NoProgress;
+
+impl ProgressHandler for NoProgress {
+    fn update(&mut self, _percent: u8) -> bool {
+        true
+    }
+}
+
 #[cfg(test)]
 struct ProgressTest {}
 
@@ -469,33 +1170,27 @@ fn test_find() {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(true)
         .open(&path)
         .unwrap();
-    file.set_len(0).unwrap();
-    file.write_all(&vec![11; 4]).unwrap();
-    file.write_all(&vec![22, 33, 33, 33, 44]).unwrap();
-    file.write_all(&vec![55, 5]).unwrap();
+    file.write_all(&[11; 4]).unwrap();
+    file.write_all(&[22, 33, 33, 33, 44]).unwrap();
+    file.write_all(&[55, 5]).unwrap();
 
     let mut progress = ProgressTest {};
 
     let mut file = File::open(&path).unwrap();
     assert_eq!(
-        file.find(0, &vec![42], false, &mut progress)
+        file.find(0, &[42], false, &mut progress)
             .unwrap_err()
             .kind(),
         ErrorKind::NotFound
     );
 
+    assert_eq!(file.find(0, &[33, 33], false, &mut progress).unwrap(), 5);
+    assert_eq!(file.find(5, &[33, 33], false, &mut progress).unwrap(), 6);
     assert_eq!(
-        file.find(0, &vec![33, 33], false, &mut progress).unwrap(),
-        5
-    );
-    assert_eq!(
-        file.find(5, &vec![33, 33], false, &mut progress).unwrap(),
-        6
-    );
-    assert_eq!(
-        file.find(file.size, &vec![33, 33], true, &mut progress)
+        file.find(file.size, &[33, 33], true, &mut progress)
             .unwrap(),
         6
     );
@@ -510,12 +1205,12 @@ fn test_cut() {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(true)
         .open(&path)
         .unwrap();
-    file.set_len(0).unwrap();
-    file.write_all(&vec![11; 4]).unwrap();
-    file.write_all(&vec![22, 33, 44, 55]).unwrap();
-    file.write_all(&vec![66; 4]).unwrap();
+    file.write_all(&[11; 4]).unwrap();
+    file.write_all(&[22, 33, 44, 55]).unwrap();
+    file.write_all(&[66; 4]).unwrap();
 
     let mut progress = ProgressTest {};
 
@@ -536,15 +1231,15 @@ fn test_insert() {
         .read(true)
         .write(true)
         .create(true)
+        .truncate(true)
         .open(&path)
         .unwrap();
-    file.set_len(0).unwrap();
-    file.write_all(&vec![11, 22, 33, 44, 55, 66, 77]).unwrap();
+    file.write_all(&[11, 22, 33, 44, 55, 66, 77]).unwrap();
 
     let mut progress = ProgressTest {};
 
     let mut file = File::open(&path).unwrap();
-    file.insert(1, 4, &vec![88, 99], &mut progress).unwrap();
+    file.insert(1, 4, &[88, 99], &mut progress).unwrap();
     assert_eq!(
         file.read(0, 255).unwrap(),
         vec![11, 88, 99, 88, 99, 22, 33, 44, 55, 66, 77]
@@ -552,3 +1247,232 @@ fn test_insert() {
 
     std::fs::remove_file(path).unwrap();
 }
+
+#[test]
+fn test_save() {
+    let path = std::env::temp_dir().join("xvi_test_file.save");
+    std::fs::write(&path, [11, 22, 33, 44, 55, 66, 77]).unwrap();
+
+    let mut progress = ProgressTest {};
+
+    let mut file = File::open(&path).unwrap();
+    file.changes.insert(1, 88);
+    file.changes.insert(2, 99);
+    file.changes.insert(5, 0);
+    file.save(&mut progress).unwrap();
+    assert!(!file.is_modified());
+    assert_eq!(
+        std::fs::read(&path).unwrap(),
+        vec![11, 88, 99, 44, 55, 0, 77]
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_save_atomic() {
+    let path = std::env::temp_dir().join("xvi_test_file.atomic");
+    let backup = std::env::temp_dir().join("xvi_test_file.atomic~");
+    std::fs::write(&path, [11, 22, 33, 44]).unwrap();
+
+    let mut progress = ProgressTest {};
+
+    let mut file = File::open(&path).unwrap();
+    file.save_mode = SaveMode::Atomic;
+    file.backup = Backup::Simple;
+    file.changes.insert(0, 55);
+    file.changes.insert(3, 66);
+    file.save(&mut progress).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), vec![55, 22, 33, 66]);
+    assert_eq!(std::fs::read(&backup).unwrap(), vec![11, 22, 33, 44]);
+    assert_eq!(file.read(0, 255).unwrap(), vec![55, 22, 33, 66]);
+
+    // hard linked file is saved in place, the link is not broken
+    let link = std::env::temp_dir().join("xvi_test_file.atomic.link");
+    std::fs::remove_file(&link).ok();
+    std::fs::hard_link(&path, &link).unwrap();
+    assert_eq!(file.effective_save_mode(), SaveMode::InPlace);
+    file.backup = Backup::None;
+    file.changes.insert(1, 77);
+    file.save(&mut progress).unwrap();
+    assert_eq!(std::fs::read(&link).unwrap(), vec![55, 77, 33, 66]);
+    std::fs::remove_file(link).unwrap();
+    assert_eq!(file.effective_save_mode(), SaveMode::Atomic);
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(backup).unwrap();
+}
+
+#[test]
+fn test_backup_timestamp() {
+    assert_eq!(Backup::timestamp(0), "19700101-000000");
+    assert_eq!(Backup::timestamp(1_634_567_890), "20211018-143810");
+    assert_eq!(Backup::timestamp(951_825_600), "20000229-120000");
+}
+
+#[test]
+fn test_changed_on_disk() {
+    let path = std::env::temp_dir().join("xvi_test_file.ondisk");
+    std::fs::write(&path, [11, 22, 33]).unwrap();
+
+    let mut file = File::open(&path).unwrap();
+    assert!(!file.is_changed_on_disk());
+
+    std::fs::write(&path, [44, 55, 66, 77]).unwrap();
+    assert!(file.is_changed_on_disk());
+
+    file.changes.insert(0, 0);
+    file.reload().unwrap();
+    assert!(!file.is_changed_on_disk());
+    assert!(!file.is_modified());
+    assert_eq!(file.size, 4);
+    assert_eq!(file.read(0, 255).unwrap(), vec![44, 55, 66, 77]);
+
+    // appended data is ignored after keep until the next change
+    let mut append = OpenOptions::new().append(true).open(&path).unwrap();
+    append.write_all(&[88]).unwrap();
+    assert!(file.is_changed_on_disk());
+    file.keep();
+    assert!(!file.is_changed_on_disk());
+    append.write_all(&[99]).unwrap();
+    assert!(!file.is_changed_on_disk());
+    assert_eq!(file.size, 4);
+    std::fs::write(&path, [11]).unwrap();
+    assert!(file.is_changed_on_disk());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_grow() {
+    let path = std::env::temp_dir().join("xvi_test_file.grow");
+    std::fs::write(&path, [11, 22, 33]).unwrap();
+
+    let mut file = File::open(&path).unwrap();
+    assert_eq!(file.read(0, 255).unwrap(), vec![11, 22, 33]);
+    assert_eq!(file.grow().unwrap(), 0);
+
+    let mut append = OpenOptions::new().append(true).open(&path).unwrap();
+    append.write_all(&[44, 55]).unwrap();
+    assert_eq!(file.grow().unwrap(), 2);
+    assert_eq!(file.size, 5);
+    assert!(!file.is_changed_on_disk());
+    assert_eq!(file.read(0, 255).unwrap(), vec![11, 22, 33, 44, 55]);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_lock() {
+    let path = std::env::temp_dir().join("xvi_test_file.lock");
+    std::fs::write(&path, [0]).unwrap();
+
+    let mut file = File::open(&path).unwrap();
+    assert!(!file.is_locked());
+    assert_eq!(file.lock(), None);
+    assert!(file.is_locked());
+    assert_eq!(file.lock(), None);
+
+    file.changes.insert(0, 1);
+    let mut progress = ProgressTest {};
+    file.save(&mut progress).unwrap();
+    assert!(!file.is_locked());
+    let mut other = File::open(&path).unwrap();
+    assert_eq!(other.lock(), None);
+    other.unlock();
+
+    assert_eq!(file.lock(), None);
+    file.save_mode = SaveMode::Atomic;
+    file.changes.insert(0, 2);
+    file.save(&mut progress).unwrap();
+    assert!(!file.is_locked());
+    assert_eq!(other.lock(), None);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_export() {
+    let path = std::env::temp_dir().join("xvi_test_file.export.src");
+    let target = std::env::temp_dir().join("xvi_test_file.export.dst");
+    std::fs::write(&path, [0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+
+    let mut file = File::open(&path).unwrap();
+    file.changes.insert(3, 0xff);
+
+    let mut progress = ProgressTest {};
+    file.export(&(2..5), &target, false, &mut progress).unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), vec![2, 0xff, 4]);
+
+    file.export(&(6..8), &target, true, &mut progress).unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), vec![2, 0xff, 4, 6, 7]);
+
+    file.export(&(0..1), &target, false, &mut progress).unwrap();
+    assert_eq!(std::fs::read(&target).unwrap(), vec![0]);
+
+    // the source file can not be the target, even through a hard link
+    let err = file.export(&(0..1), &path, false, &mut progress);
+    assert_eq!(err.unwrap_err().kind(), ErrorKind::InvalidInput);
+    std::fs::remove_file(&target).unwrap();
+    std::fs::hard_link(&path, &target).unwrap();
+    assert!(file.export(&(0..1), &target, true, &mut progress).is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(target).unwrap();
+}
+
+#[test]
+fn test_insert_data() {
+    let path = std::env::temp_dir().join("xvi_test_file.insert_data");
+    std::fs::write(&path, [0, 1, 2, 3]).unwrap();
+
+    let mut file = File::open(&path).unwrap();
+    let mut progress = ProgressTest {};
+    let source = [0xaa, 0xbb, 0xcc];
+    file.insert_data(2, 2, &mut &source[..], &mut progress)
+        .unwrap();
+    assert_eq!(file.size, 6);
+    assert_eq!(std::fs::read(&path).unwrap(), vec![0, 1, 0xaa, 0xbb, 2, 3]);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_image() {
+    let path = std::env::temp_dir().join("xvi_test_file.image.hex");
+    let raw = std::env::temp_dir().join("xvi_test_file.image.bin");
+    std::fs::write(&path, ":0400100001020304E2\n:02001600AABB83\n:00000001FF\n").unwrap();
+
+    let mut file = File::open(&path).unwrap();
+    assert_eq!(file.format, Format::IntelHex);
+    assert_eq!(file.start(), 0x10);
+    assert_eq!(file.size, 0x18);
+    assert_eq!(file.gaps(&(0..0x18)), vec![0..0x10, 0x14..0x16]);
+    assert_eq!(
+        file.read(0x12, 6).unwrap(),
+        vec![3, 4, 0xff, 0xff, 0xaa, 0xbb]
+    );
+
+    // change data and fill the gap
+    file.changes.insert(0x10, 0x55);
+    file.changes.insert(0x14, 0x66);
+    let mut progress = ProgressTest {};
+    file.save(&mut progress).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        ":04001000550203048E\n:010014006685\n:02001600AABB83\n:00000001FF\n"
+    );
+
+    // convert to raw binary
+    file.save_as(&raw, Format::Raw, 0, &mut progress).unwrap();
+    assert_eq!(file.format, Format::Raw);
+    assert_eq!(file.size, 8);
+    assert_eq!(
+        std::fs::read(&raw).unwrap(),
+        vec![0x55, 2, 3, 4, 0x66, 0, 0xaa, 0xbb]
+    );
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(raw).unwrap();
+}
@@ -0,0 +1,238 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::clipboard;
+use std::fs;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+
+/// Source of data to fill the range.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Generator {
+    /// Repeating pattern.
+    Pattern(Vec<u8>),
+    /// Pseudo-random bytes (SplitMix64) produced from the seed.
+    Random(u64),
+    /// Counter of the specified unit size and byte order.
+    Counter {
+        start: u64,
+        increment: bool,
+        width: usize,
+        little_endian: bool,
+    },
+    /// Contents of the file, repeated if the file is shorter than the range.
+    File(String),
+    /// Contents of the system clipboard, repeated as a pattern.
+    Clipboard,
+}
+
+impl Generator {
+    /// Start generation.
+    ///
+    /// # Return value
+    ///
+    /// Stream of the generated data.
+    pub fn stream(&self) -> Result<Stream> {
+        let (source, unit) = match self {
+            Generator::Pattern(pattern) => (Source::Pattern, pattern.clone()),
+            Generator::Random(seed) => (Source::Random(*seed), Vec::new()),
+            Generator::Counter {
+                start,
+                increment,
+                width,
+                little_endian,
+            } => {
+                debug_assert!([1, 2, 4, 8].contains(width));
+                let counter = Source::Counter {
+                    value: *start,
+                    increment: *increment,
+                    width: *width,
+                    little_endian: *little_endian,
+                };
+                (counter, Vec::new())
+            }
+            Generator::File(path) => (Source::File(fs::File::open(path)?), Vec::new()),
+            Generator::Clipboard => (Source::Pattern, clipboard::paste()?),
+        };
+        if matches!(source, Source::Pattern) && unit.is_empty() {
+            return Err(Stream::no_data());
+        }
+        let pos = unit.len();
+        Ok(Stream { source, unit, pos })
+    }
+}
+
+/// Internal state of the generator.
+enum Source {
+    /// Repeating pattern, the unit is the pattern itself.
+    Pattern,
+    /// Pseudo-random generator state.
+    Random(u64),
+    /// Next value of the counter.
+    Counter {
+        value: u64,
+        increment: bool,
+        width: usize,
+        little_endian: bool,
+    },
+    /// Source file, rewound at the end.
+    File(fs::File),
+}
+
+/// Stream of the generated data: data is produced by units (a pattern, a
+/// random number, a counter value or a block of the file) and never ends.
+pub struct Stream {
+    /// Internal state of the generator.
+    source: Source,
+    /// Current unit of the data.
+    unit: Vec<u8>,
+    /// Position of the next byte in the current unit.
+    pos: usize,
+}
+
+impl Stream {
+    /// Max size of the block read from the source file.
+    const FILE_BLOCK: usize = 0x10000;
+
+    /// Produce the next unit of the data.
+    fn next_unit(&mut self) -> Result<()> {
+        match &mut self.source {
+            Source::Pattern => {}
+            Source::Random(state) => {
+                self.unit = splitmix64(state).to_le_bytes().to_vec();
+            }
+            Source::Counter {
+                value,
+                increment,
+                width,
+                little_endian,
+            } => {
+                let bytes = value.to_le_bytes();
+                self.unit = bytes[..*width].to_vec();
+                if !*little_endian {
+                    self.unit.reverse();
+                }
+                *value = if *increment {
+                    value.wrapping_add(1)
+                } else {
+                    value.wrapping_sub(1)
+                };
+            }
+            Source::File(file) => {
+                self.unit.resize(Stream::FILE_BLOCK, 0);
+                let mut size = file.read(&mut self.unit)?;
+                if size == 0 {
+                    // repeat the file from the beginning
+                    file.seek(SeekFrom::Start(0))?;
+                    size = file.read(&mut self.unit)?;
+                    if size == 0 {
+                        return Err(Stream::no_data());
+                    }
+                }
+                self.unit.truncate(size);
+            }
+        }
+        self.pos = 0;
+        Ok(())
+    }
+
+    /// Create an error for an empty data source.
+    fn no_data() -> Error {
+        Error::new(ErrorKind::InvalidData, "No data to fill")
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            if self.pos == self.unit.len() {
+                self.next_unit()?;
+            }
+            let size = (buf.len() - filled).min(self.unit.len() - self.pos);
+            buf[filled..filled + size].copy_from_slice(&self.unit[self.pos..self.pos + size]);
+            self.pos += size;
+            filled += size;
+        }
+        Ok(filled)
+    }
+}
+
+/// Get the next value of SplitMix64 pseudo-random generator.
+///
+/// # Arguments
+///
+/// * `state` - generator state
+///
+/// # Return value
+///
+/// Pseudo-random value.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[test]
+fn test_generate() {
+    let generate = |generator: &Generator, length: usize| {
+        let mut data = vec![0; length];
+        generator.stream()?.read_exact(&mut data)?;
+        Result::Ok(data)
+    };
+
+    assert_eq!(
+        generate(&Generator::Pattern(vec![1, 2, 3]), 5).unwrap(),
+        [1, 2, 3, 1, 2]
+    );
+    assert!(Generator::Pattern(vec![]).stream().is_err());
+
+    let random = generate(&Generator::Random(0), 10).unwrap();
+    assert_eq!(random, generate(&Generator::Random(0), 10).unwrap());
+    assert_ne!(random, generate(&Generator::Random(1), 10).unwrap());
+    assert_eq!(&random[..8], &0xe220_a839_7b1d_cdaf_u64.to_le_bytes());
+
+    let counter = |start: u64, increment: bool, width: usize, little_endian: bool| {
+        let counter = Generator::Counter {
+            start,
+            increment,
+            width,
+            little_endian,
+        };
+        generate(&counter, 6).unwrap()
+    };
+    assert_eq!(counter(0xfe, true, 1, true), [0xfe, 0xff, 0, 1, 2, 3]);
+    assert_eq!(counter(1, false, 2, true), [1, 0, 0, 0, 0xff, 0xff]);
+    assert_eq!(counter(0x10, true, 2, false), [0, 0x10, 0, 0x11, 0, 0x12]);
+    assert_eq!(counter(7, true, 4, false), [0, 0, 0, 7, 0, 0]);
+
+    // data is continued between the blocks
+    let mut stream = Generator::Counter {
+        start: 0,
+        increment: true,
+        width: 2,
+        little_endian: false,
+    }
+    .stream()
+    .unwrap();
+    let mut block = [0; 3];
+    stream.read_exact(&mut block).unwrap();
+    assert_eq!(block, [0, 0, 0]);
+    stream.read_exact(&mut block).unwrap();
+    assert_eq!(block, [1, 0, 2]);
+
+    let path = std::env::temp_dir().join("xvi_test_generator");
+    std::fs::write(&path, b"abc").unwrap();
+    let file = Generator::File(path.to_str().unwrap().to_string());
+    assert_eq!(generate(&file, 4).unwrap(), b"abca");
+    assert_eq!(generate(&file, 2).unwrap(), b"ab");
+    let mut stream = file.stream().unwrap();
+    let mut block = [0; 5];
+    stream.read_exact(&mut block).unwrap();
+    stream.read_exact(&mut block).unwrap();
+    assert_eq!(&block, b"cabca");
+    std::fs::write(&path, b"").unwrap();
+    assert!(generate(&file, 1).is_err());
+    std::fs::remove_file(path).unwrap();
+}
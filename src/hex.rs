@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use std::convert::TryFrom;
+
+/// Parse numeric value: hexadecimal if it has `0x` prefix or hex digits,
+/// decimal otherwise.
+///
+/// # Arguments
+///
+/// * `text` - text to parse
+///
+/// # Return value
+///
+/// Parsed value, `None` if the text is not a valid number.
+pub fn parse_number(text: &str) -> Option<u64> {
+    if let Some(hex) = text.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).ok()
+    } else if text.chars().any(|c| matches!(c, 'a'..='f' | 'A'..='F')) {
+        u64::from_str_radix(text, 16).ok()
+    } else {
+        text.parse::<u64>().ok()
+    }
+}
+
+/// Parse sequence of bytes from hex string, whitespaces are ignored.
+///
+/// # Arguments
+///
+/// * `text` - text to parse
+///
+/// # Return value
+///
+/// Parsed bytes, `None` if the text is empty, contains non-hex characters or
+/// an odd number of digits.
+pub fn parse_bytes(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let high = char::from(pair[0]).to_digit(16)?;
+            let low = char::from(pair[1]).to_digit(16)?;
+            u8::try_from((high << 4) | low).ok()
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_number() {
+    assert_eq!(parse_number("123"), Some(123));
+    assert_eq!(parse_number("0x123"), Some(0x123));
+    assert_eq!(parse_number("1f"), Some(0x1f));
+    assert_eq!(parse_number("DEAD"), Some(0xdead));
+    assert_eq!(parse_number("0x"), None);
+    assert_eq!(parse_number("-1"), None);
+    assert_eq!(parse_number("1g"), None);
+    assert_eq!(parse_number(""), None);
+}
+
+#[test]
+fn test_parse_bytes() {
+    assert_eq!(parse_bytes("00ff"), Some(vec![0x00, 0xff]));
+    assert_eq!(
+        parse_bytes(" de ad\nBE ef "),
+        Some(vec![0xde, 0xad, 0xbe, 0xef])
+    );
+    assert_eq!(parse_bytes("abc"), None);
+    assert_eq!(parse_bytes("+f"), None);
+    assert_eq!(parse_bytes("zz"), None);
+    assert_eq!(parse_bytes("\u{444}0"), None);
+    assert_eq!(parse_bytes(" "), None);
+    assert_eq!(parse_bytes(""), None);
+}
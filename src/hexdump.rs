@@ -0,0 +1,260 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::file::{File, ProgressHandler};
+use super::image::Image;
+use std::io::{BufRead, Error, ErrorKind, Result, Write};
+use std::ops::Range;
+
+/// Text dump format.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dump {
+    /// `xxd` style: `00000000: 6865 6c6c  he..`
+    Xxd,
+    /// `hexdump -C` style: `00000000  68 65 6c 6c  |he..|`
+    Hexdump,
+    /// `od -A x -t x1z` style: `000000 68 65 6c 6c  >he..<`
+    Od,
+}
+
+impl Dump {
+    /// All available formats.
+    pub const ALL: &'static [Dump] = &[Dump::Xxd, Dump::Hexdump, Dump::Od];
+
+    /// Get format by its ID.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id.to_lowercase().as_str() {
+            "xxd" => Some(Dump::Xxd),
+            "hexdump" => Some(Dump::Hexdump),
+            "od" => Some(Dump::Od),
+            _ => None,
+        }
+    }
+
+    /// Get human readable format name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Dump::Xxd => "xxd",
+            Dump::Hexdump => "hexdump -C",
+            Dump::Od => "od -A x -t x1z",
+        }
+    }
+
+    /// Format a single line of the dump.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - address of the first byte
+    /// * `data` - line data
+    /// * `columns` - number of bytes per line
+    pub fn line(self, offset: u64, data: &[u8], columns: usize) -> String {
+        let ascii: String = data
+            .iter()
+            .map(|&b| {
+                if (0x20..0x7f).contains(&b) {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        let mut line = match self {
+            Dump::Xxd => format!("{:08x}:", offset),
+            Dump::Hexdump => format!("{:08x}", offset),
+            Dump::Od => format!("{:06x}", offset),
+        };
+        for i in 0..columns {
+            match self {
+                Dump::Xxd if i % 2 == 0 => line.push(' '),
+                Dump::Hexdump if i % 8 == 0 => line.push_str("  "),
+                Dump::Hexdump | Dump::Od => line.push(' '),
+                Dump::Xxd => {}
+            }
+            if let Some(byte) = data.get(i) {
+                line.push_str(&format!("{:02x}", byte));
+            } else {
+                line.push_str("  ");
+            }
+        }
+        match self {
+            Dump::Xxd => format!("{}  {}", line, ascii),
+            Dump::Hexdump => format!("{}  |{}|", line, ascii),
+            Dump::Od => format!("{}  >{}<", line, ascii),
+        }
+    }
+
+    /// Write text dump of the file range.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - source file
+    /// * `range` - range to dump
+    /// * `columns` - number of bytes per line
+    /// * `out` - destination
+    /// * `progress` - long time operation handler
+    pub fn write(
+        self,
+        file: &mut File,
+        range: &Range<u64>,
+        columns: usize,
+        out: &mut dyn Write,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<()> {
+        debug_assert!(columns > 0);
+        // blocks contain whole lines
+        let block_size = columns * (0x10000 / columns).max(1);
+        file.read_blocks(range, block_size, progress, &mut |offset, data| {
+            for (index, line) in data.chunks(columns).enumerate() {
+                let offset = offset + (index * columns) as u64;
+                writeln!(out, "{}", self.line(offset, line, columns))?;
+            }
+            Ok(true)
+        })?;
+        // the final line with the end offset
+        match self {
+            Dump::Xxd => {}
+            Dump::Hexdump => writeln!(out, "{:08x}", range.end)?,
+            Dump::Od => writeln!(out, "{:06x}", range.end)?,
+        }
+        Ok(())
+    }
+}
+
+/// Parse text dump (`xxd`, `hexdump -C` or `od -A x -t x1`) back to binary.
+/// Offsets from the dump are used as addresses of data, the squeezed lines
+/// (`*`) are expanded.
+///
+/// # Arguments
+///
+/// * `text` - source of the text dump
+///
+/// # Return value
+///
+/// Memory image with the dump data.
+pub fn parse(text: &mut dyn BufRead) -> Result<Image> {
+    let mut image = Image::new();
+    let mut last: Option<(u64, Vec<u8>)> = None;
+    let mut squeeze = false;
+    for (index, line) in text.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "*" {
+            squeeze = true;
+            continue;
+        }
+        let (offset, data) = parse_line(line).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid dump format at line {}", index + 1),
+            )
+        })?;
+
+        // expand squeezed lines
+        if squeeze {
+            if let Some((mut last_offset, last_data)) = last.take() {
+                last_offset += last_data.len() as u64;
+                while !last_data.is_empty() && last_offset < offset {
+                    #[allow(clippy::cast_possible_truncation)]
+                    let size = (offset - last_offset).min(last_data.len() as u64) as usize;
+                    image.put(last_offset, &last_data[..size]);
+                    last_offset += size as u64;
+                }
+            }
+            squeeze = false;
+        }
+
+        image.put(offset, &data);
+        last = Some((offset, data));
+    }
+    Ok(image)
+}
+
+/// Parse a single line of the text dump.
+///
+/// # Return value
+///
+/// Line offset and data.
+fn parse_line(line: &str) -> Option<(u64, Vec<u8>)> {
+    // offset field
+    let end = line
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(line.len());
+    let offset = u64::from_str_radix(&line[..end], 16).ok()?;
+    let mut hex = &line[end..];
+
+    // cut off ascii field
+    if let Some(rest) = hex.strip_prefix(':') {
+        // xxd: ascii field is separated with two spaces
+        hex = rest.get(1..).unwrap_or_default();
+        if let Some(pos) = hex.find("  ") {
+            hex = &hex[..pos];
+        }
+    } else if let Some(pos) = hex.find(['|', '>']) {
+        hex = &hex[..pos];
+    }
+
+    // hex data, groups of bytes separated with spaces
+    let mut data = Vec::new();
+    for group in hex.split_whitespace() {
+        if group.len() % 2 != 0 {
+            return None;
+        }
+        for i in (0..group.len()).step_by(2) {
+            data.push(u8::from_str_radix(group.get(i..i + 2)?, 16).ok()?);
+        }
+    }
+    Some((offset, data))
+}
+
+#[test]
+fn test_line() {
+    let data = b"hello world 1234";
+    assert_eq!(
+        Dump::Xxd.line(0x10, data, 16),
+        "00000010: 6865 6c6c 6f20 776f 726c 6420 3132 3334  hello world 1234"
+    );
+    assert_eq!(
+        Dump::Xxd.line(0, &data[..5], 8),
+        "00000000: 6865 6c6c 6f         hello"
+    );
+    assert_eq!(
+        Dump::Hexdump.line(0x10, data, 16),
+        "00000010  68 65 6c 6c 6f 20 77 6f  72 6c 64 20 31 32 33 34  |hello world 1234|"
+    );
+    assert_eq!(Dump::Od.line(0, &[0, 0x41], 4), "000000 00 41        >.A<");
+}
+
+#[test]
+fn test_parse() {
+    let text = "00000000: 6865 6c6c 6f20 776f 726c 6420 3132 3334  hello world 1234\n\
+                00000010: 3536                                     56\n";
+    let image = parse(&mut text.as_bytes()).unwrap();
+    assert_eq!(image.to_raw(0), b"hello world 123456".to_vec());
+
+    // ascii field looks like hex
+    let text = "00000000: 6361 6665  cafe\n";
+    let image = parse(&mut text.as_bytes()).unwrap();
+    assert_eq!(image.to_raw(0), b"cafe".to_vec());
+
+    let text = "00000000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f  |................|\n\
+                00000010  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f  |................|\n\
+                *\n\
+                00000040  ff                                                |.|\n\
+                00000041\n";
+    let image = parse(&mut text.as_bytes()).unwrap();
+    let data = image.to_raw(0);
+    assert_eq!(data.len(), 0x41);
+    assert_eq!(data[0x3f], 0x0f);
+    assert_eq!(data[0x40], 0xff);
+
+    // gap
+    let text = "000004 01 02  >..<\n000010 03  >.<\n000011\n";
+    let image = parse(&mut text.as_bytes()).unwrap();
+    assert_eq!(image.start(), 4);
+    assert_eq!(image.gaps(&(0..0x11)), vec![0..4, 6..0x10]);
+
+    assert!(parse(&mut "hello".as_bytes()).is_err());
+}
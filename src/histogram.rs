@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::file::{File, ProgressHandler};
+use std::io::Result;
+use std::ops::Range;
+
+/// Byte values histogram.
+#[derive(Clone)]
+pub struct Histogram {
+    /// Number of occurrences of each byte value.
+    pub counts: [u64; 256],
+    /// Total number of bytes.
+    pub total: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; 256],
+            total: 0,
+        }
+    }
+}
+
+impl Histogram {
+    /// Number of the most frequent values in the summary.
+    pub const TOP: usize = 3;
+
+    /// Calculate histogram of the file range.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - file to process
+    /// * `range` - range to process
+    /// * `progress` - long time operation handler
+    ///
+    /// # Return value
+    ///
+    /// Histogram of the range.
+    pub fn calculate(
+        file: &mut File,
+        range: &Range<u64>,
+        progress: &mut dyn ProgressHandler,
+    ) -> Result<Self> {
+        const BLOCK_SIZE: usize = 0x10000;
+        let mut hist = Histogram::default();
+        file.read_blocks(range, BLOCK_SIZE, progress, &mut |_, data| {
+            hist.add(data);
+            Ok(true)
+        })?;
+        Ok(hist)
+    }
+
+    /// Add data to the histogram.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - data to add
+    pub fn add(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.counts[byte as usize] += 1;
+        }
+        self.total += data.len() as u64;
+    }
+
+    /// Get number of bytes matching the predicate.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - byte value predicate
+    ///
+    /// # Return value
+    ///
+    /// Number of bytes.
+    pub fn count(&self, filter: fn(u8) -> bool) -> u64 {
+        (0..=u8::MAX)
+            .filter(|&byte| filter(byte))
+            .map(|byte| self.counts[byte as usize])
+            .sum()
+    }
+
+    /// Get percentage of the byte value.
+    pub fn percent(&self, byte: u8) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.counts[byte as usize] as f64 * 100.0 / self.total as f64
+        }
+    }
+
+    /// Get number of distinct byte values.
+    pub fn distinct(&self) -> usize {
+        self.counts.iter().filter(|&&count| count != 0).count()
+    }
+
+    /// Get the most frequent byte values.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - max number of values
+    ///
+    /// # Return value
+    ///
+    /// Byte values sorted by frequency in descending order.
+    pub fn top(&self, max: usize) -> Vec<u8> {
+        let mut values: Vec<u8> = (0..=u8::MAX)
+            .filter(|&byte| self.counts[byte as usize] != 0)
+            .collect();
+        values.sort_by_key(|&byte| std::cmp::Reverse(self.counts[byte as usize]));
+        values.truncate(max);
+        values
+    }
+
+    /// Format histogram as CSV: table of byte values preceded by the summary
+    /// in comment lines (starting with `#`).
+    pub fn csv(&self) -> String {
+        let top: Vec<String> = self
+            .top(Histogram::TOP)
+            .into_iter()
+            .map(|byte| format!("0x{:02x}", byte))
+            .collect();
+        let mut csv = format!(
+            "# total: {}\n# distinct: {}\n# entropy: {:.4}\n# most frequent: {}\n",
+            self.total,
+            self.distinct(),
+            self.entropy(),
+            top.join(" ")
+        );
+        csv += "value,count,percent\n";
+        for byte in 0..=u8::MAX {
+            csv += &format!(
+                "0x{:02x},{},{:.4}\n",
+                byte,
+                self.counts[byte as usize],
+                self.percent(byte)
+            );
+        }
+        csv
+    }
+
+    /// Calculate Shannon entropy.
+    ///
+    /// # Return value
+    ///
+    /// Entropy in bits per byte (0.0-8.0).
+    pub fn entropy(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        let total = self.total as f64;
+        -self
+            .counts
+            .iter()
+            .filter(|&&count| count != 0)
+            .map(|&count| {
+                let p = count as f64 / total;
+                p * p.log2()
+            })
+            .sum::<f64>()
+    }
+}
+
+#[test]
+fn test_histogram() {
+    let mut hist = Histogram::default();
+    assert!(hist.entropy().abs() < f64::EPSILON);
+
+    hist.add(&[0; 16]);
+    assert_eq!(hist.total, 16);
+    assert!(hist.entropy().abs() < f64::EPSILON);
+
+    hist.add(&[0xff; 16]);
+    assert!((hist.entropy() - 1.0).abs() < f64::EPSILON);
+    assert_eq!(hist.count(|b| b >= 0x80), 16);
+    assert_eq!(hist.distinct(), 2);
+    assert!((hist.percent(0xff) - 50.0).abs() < f64::EPSILON);
+
+    let mut hist = Histogram::default();
+    hist.add(&(0..=u8::MAX).collect::<Vec<u8>>());
+    assert!((hist.entropy() - 8.0).abs() < f64::EPSILON);
+    assert_eq!(hist.distinct(), 256);
+
+    let mut hist = Histogram::default();
+    hist.add(b"abracadabra");
+    assert_eq!(hist.top(3), vec![b'a', b'b', b'r']);
+    let csv = hist.csv();
+    assert_eq!(csv.lines().count(), 261);
+    assert!(csv.starts_with(
+        "# total: 11\n# distinct: 5\n# entropy: 2.0404\n\
+         # most frequent: 0x61 0x62 0x72\nvalue,count,percent\n0x00,0,0.0000\n"
+    ));
+    assert!(csv.contains("\n0x61,5,45.4545\n"));
+    assert!(csv.ends_with("\n0xff,0,0.0000\n"));
+}
@@ -2,8 +2,12 @@
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
 use super::inifile::IniFile;
+use super::lock;
 use std::env;
-use std::path::PathBuf;
+use std::fs::{create_dir_all, OpenOptions};
+use std::io;
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 
 /// History: loads, holds and saves editor history (offsets, searches, etc).
 pub struct History {
@@ -84,42 +88,106 @@ impl History {
     /// Save history to the ini file.
     pub fn save(&self) {
         if let Some(file) = History::ini_file() {
-            let mut ini = IniFile::new();
-
-            // recent file position history
-            ini.sections.insert(
-                History::SEC_FILE.to_string(),
-                self.file_pos
-                    .iter()
-                    .take(History::MAX_FILE)
-                    .map(|(f, o)| format!("{}:{:x}", f, o))
-                    .collect(),
-            );
-
-            // search history
-            ini.sections.insert(
-                History::SEC_SEARCH.to_string(),
-                self.search
-                    .iter()
-                    .take(History::MAX_SEARCH)
-                    .map(|s| s.iter().map(|b| format!("{:02x}", b)).collect())
-                    .collect(),
-            );
-
-            // "goto" history
-            ini.sections.insert(
-                History::SEC_GOTO.to_string(),
-                self.goto
-                    .iter()
-                    .take(History::MAX_GOTO)
-                    .map(|o| format!("{:x}", o))
-                    .collect(),
-            );
-
-            ini.save(&file).ok();
+            self.save_to(&file).ok();
         }
     }
 
+    /// Save history to the specified file.
+    /// The file is locked while saving, entries written by other instances
+    /// in the meantime are merged with the current ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the history file
+    fn save_to(&self, path: &Path) -> io::Result<()> {
+        create_dir_all(path.parent().unwrap())?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        lock::lock(&file, true)?; // released on close
+
+        // merge with the history saved by other instances
+        let mut history = History {
+            file_pos: self.file_pos.clone(),
+            search: self.search.clone(),
+            search_backward: false,
+            goto: self.goto.clone(),
+            pattern: Vec::new(),
+        };
+        let mut stored = History {
+            file_pos: Vec::new(),
+            search: Vec::new(),
+            search_backward: false,
+            goto: Vec::new(),
+            pattern: Vec::new(),
+        };
+        stored.load(&IniFile::parse(&file));
+        history.merge(&stored);
+
+        let mut ini = IniFile::new();
+
+        // recent file position history
+        ini.sections.insert(
+            History::SEC_FILE.to_string(),
+            history
+                .file_pos
+                .iter()
+                .map(|(f, o)| format!("{}:{:x}", f, o))
+                .collect(),
+        );
+
+        // search history
+        ini.sections.insert(
+            History::SEC_SEARCH.to_string(),
+            history
+                .search
+                .iter()
+                .map(|s| s.iter().map(|b| format!("{:02x}", b)).collect())
+                .collect(),
+        );
+
+        // "goto" history
+        ini.sections.insert(
+            History::SEC_GOTO.to_string(),
+            history.goto.iter().map(|o| format!("{:x}", o)).collect(),
+        );
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        ini.write(&mut file)
+    }
+
+    /// Merge history with another one, current entries take precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - history to merge with
+    fn merge(&mut self, other: &History) {
+        for (file, offset) in &other.file_pos {
+            if !self.file_pos.iter().any(|(f, _)| f == file) {
+                self.file_pos.push((file.clone(), *offset));
+            }
+        }
+        self.file_pos.truncate(History::MAX_FILE);
+
+        for seq in &other.search {
+            if !self.search.contains(seq) {
+                self.search.push(seq.clone());
+            }
+        }
+        self.search.truncate(History::MAX_SEARCH);
+
+        for offset in &other.goto {
+            if !self.goto.contains(offset) {
+                self.goto.push(*offset);
+            }
+        }
+        self.goto.truncate(History::MAX_GOTO);
+    }
+
     /// Get last position for the specified file.
     pub fn get_filepos(&self, file: &str) -> Option<u64> {
         // get absolute path
@@ -187,9 +255,12 @@ impl Default for History {
             pattern: vec![0],
         };
 
-        if let Some(file) = History::ini_file() {
-            if let Ok(ini) = IniFile::load(&file) {
-                instance.load(&ini);
+        if let Some(path) = History::ini_file() {
+            if let Ok(file) = OpenOptions::new().read(true).open(path) {
+                // wait while another instance is saving the history
+                if lock::lock(&file, false).is_ok() {
+                    instance.load(&IniFile::parse(&file));
+                }
             }
         }
 
@@ -277,3 +348,56 @@ fn test_filepos() {
     assert_eq!(history.get_filepos("/path/file2"), Some(2));
     assert_eq!(history.get_filepos("file3"), None);
 }
+
+#[test]
+fn test_merge() {
+    let mut history = History {
+        file_pos: vec![("file1".to_string(), 1), ("file2".to_string(), 2)],
+        search: vec![vec![1]],
+        search_backward: false,
+        goto: vec![1, 2],
+        pattern: Vec::new(),
+    };
+    let other = History {
+        file_pos: vec![("file3".to_string(), 3), ("file1".to_string(), 4)],
+        search: vec![vec![2], vec![1]],
+        search_backward: false,
+        goto: vec![2, 3],
+        pattern: Vec::new(),
+    };
+
+    history.merge(&other);
+    assert_eq!(
+        history.file_pos,
+        vec![
+            ("file1".to_string(), 1),
+            ("file2".to_string(), 2),
+            ("file3".to_string(), 3)
+        ]
+    );
+    assert_eq!(history.search, vec![vec![1], vec![2]]);
+    assert_eq!(history.goto, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_save() {
+    let path = std::env::temp_dir().join("xvi_test_history");
+    std::fs::write(&path, "[goto]\nabc\n").unwrap();
+
+    let history = History {
+        file_pos: Vec::new(),
+        search: Vec::new(),
+        search_backward: false,
+        goto: vec![0x123],
+        pattern: Vec::new(),
+    };
+    history.save_to(&path).unwrap();
+
+    let ini = IniFile::load(&path).unwrap();
+    assert_eq!(
+        ini.sections.get(History::SEC_GOTO),
+        Some(&vec!["123".to_string(), "abc".to_string()])
+    );
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,572 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::hex;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+use std::path::Path;
+
+/// File content format.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Format {
+    /// Raw binary data.
+    Raw,
+    /// Intel HEX.
+    IntelHex,
+    /// Motorola S-record.
+    SRecord,
+}
+
+impl Format {
+    /// Get format ID.
+    pub fn id(self) -> &'static str {
+        match self {
+            Format::Raw => "raw",
+            Format::IntelHex => "ihex",
+            Format::SRecord => "srec",
+        }
+    }
+
+    /// Get human readable format name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Raw => "Raw binary",
+            Format::IntelHex => "Intel HEX",
+            Format::SRecord => "Motorola S-record",
+        }
+    }
+
+    /// Detect format by the file name extension.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the file
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "hex" | "ihex" | "ihx" => Format::IntelHex,
+            "srec" | "s19" | "s28" | "s37" | "mot" => Format::SRecord,
+            _ => Format::Raw,
+        }
+    }
+}
+
+/// Sparse memory image loaded from Intel HEX or S-record file.
+#[derive(Clone)]
+pub struct Image {
+    /// Data segments (start address -> data), never overlapped or adjacent.
+    segments: BTreeMap<u64, Vec<u8>>,
+    /// Entry point: record type and address.
+    entry: Option<(u8, u32)>,
+    /// Header data (S-record S0).
+    header: Vec<u8>,
+    /// Max number of data bytes per record.
+    record_len: usize,
+    /// Size of the address field (S-record: 2, 3 or 4 bytes).
+    addr_len: usize,
+}
+
+impl Image {
+    /// Default number of data bytes per record.
+    const RECORD_LEN: usize = 16;
+
+    /// Create an empty image.
+    pub fn new() -> Self {
+        Self {
+            segments: BTreeMap::new(),
+            entry: None,
+            header: Vec::new(),
+            record_len: Image::RECORD_LEN,
+            addr_len: 2,
+        }
+    }
+
+    /// Create image from raw binary data, blocks filled with the specified
+    /// byte are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - raw data
+    /// * `fill` - value of the unused bytes
+    pub fn from_raw(data: &[u8], fill: u8) -> Self {
+        let mut image = Image::new();
+        for (index, block) in data.chunks(Image::RECORD_LEN).enumerate() {
+            if block.iter().any(|&b| b != fill) {
+                image.put((index * Image::RECORD_LEN) as u64, block);
+            }
+        }
+        image
+    }
+
+    /// Parse text of Intel HEX or S-record file.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - file content
+    /// * `format` - file format
+    pub fn parse(text: &[u8], format: Format) -> Result<Self> {
+        let mut image = Image::new();
+        image.record_len = 0;
+        let mut base = 0;
+        for (index, line) in text.split(|&c| c == b'\n').enumerate() {
+            let line = String::from_utf8_lossy(line);
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let status = match format {
+                Format::IntelHex => image.parse_ihex(line, &mut base),
+                Format::SRecord => image.parse_srec(line),
+                Format::Raw => unreachable!(),
+            };
+            match status {
+                Ok(true) => {}
+                Ok(false) => break, // end of file
+                Err(err) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("{} at line {}", err, index + 1),
+                    ));
+                }
+            }
+        }
+        if image.segments.is_empty() {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "No data records"));
+        }
+        if image.record_len == 0 {
+            image.record_len = Image::RECORD_LEN;
+        }
+        Ok(image)
+    }
+
+    /// Parse Intel HEX record.
+    ///
+    /// # Return value
+    ///
+    /// `false` if the end of file record was found.
+    fn parse_ihex(&mut self, line: &str, base: &mut u64) -> std::result::Result<bool, String> {
+        let record = line
+            .strip_prefix(':')
+            .ok_or_else(|| "Invalid start code".to_string())
+            .and_then(Image::decode)?;
+        if record.len() < 5 || record.len() != record[0] as usize + 5 {
+            return Err("Invalid record length".to_string());
+        }
+        if record.iter().fold(0_u8, |s, &b| s.wrapping_add(b)) != 0 {
+            return Err("Invalid checksum".to_string());
+        }
+        let address = u64::from(record[1]) << 8 | u64::from(record[2]);
+        let data = &record[4..record.len() - 1];
+        let value = data.iter().fold(0_u32, |v, &b| v << 8 | u32::from(b));
+        match record[3] {
+            0x00 => {
+                self.record_len = self.record_len.max(data.len());
+                self.put(*base + address, data);
+            }
+            0x01 => return Ok(false),
+            0x02 if data.len() == 2 => *base = u64::from(value) << 4,
+            0x04 if data.len() == 2 => *base = u64::from(value) << 16,
+            0x03 | 0x05 if data.len() == 4 => self.entry = Some((record[3], value)),
+            _ => return Err("Invalid record".to_string()),
+        }
+        Ok(true)
+    }
+
+    /// Parse S-record.
+    ///
+    /// # Return value
+    ///
+    /// `false` if the termination record was found.
+    fn parse_srec(&mut self, line: &str) -> std::result::Result<bool, String> {
+        let mut chars = line.chars();
+        if chars.next() != Some('S') {
+            return Err("Invalid start code".to_string());
+        }
+        let rtype = chars
+            .next()
+            .and_then(|c| c.to_digit(10))
+            .ok_or_else(|| "Invalid record type".to_string())?;
+        let record = Image::decode(chars.as_str())?;
+        if record.is_empty() || record.len() != record[0] as usize + 1 {
+            return Err("Invalid record length".to_string());
+        }
+        if record.iter().fold(0_u8, |s, &b| s.wrapping_add(b)) != 0xff {
+            return Err("Invalid checksum".to_string());
+        }
+        let addr_len = match rtype {
+            0 | 1 | 5 | 9 => 2,
+            2 | 6 | 8 => 3,
+            3 | 7 => 4,
+            _ => return Err("Invalid record type".to_string()),
+        };
+        if record.len() < addr_len + 2 {
+            return Err("Invalid record length".to_string());
+        }
+        let address = record[1..=addr_len]
+            .iter()
+            .fold(0_u32, |v, &b| v << 8 | u32::from(b));
+        let data = &record[addr_len + 1..record.len() - 1];
+        match rtype {
+            0 => self.header = data.to_vec(),
+            1..=3 => {
+                self.addr_len = self.addr_len.max(addr_len);
+                self.record_len = self.record_len.max(data.len());
+                self.put(u64::from(address), data);
+            }
+            5 | 6 => {} // record count
+            _ => {
+                #[allow(clippy::cast_possible_truncation)]
+                let rtype = rtype as u8;
+                self.addr_len = self.addr_len.max(addr_len);
+                self.entry = Some((rtype, address));
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Decode hex string.
+    fn decode(text: &str) -> std::result::Result<Vec<u8>, String> {
+        hex::parse_bytes(text).ok_or_else(|| "Invalid hex data".to_string())
+    }
+
+    /// Compose text of the Intel HEX or S-record file.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - file format
+    pub fn write(&self, format: Format) -> Vec<u8> {
+        match format {
+            Format::IntelHex => self.write_ihex(),
+            Format::SRecord => self.write_srec(),
+            Format::Raw => unreachable!(),
+        }
+        .into_bytes()
+    }
+
+    /// Compose Intel HEX text.
+    fn write_ihex(&self) -> String {
+        let mut text = String::new();
+        let mut base = 0;
+        for (&start, data) in &self.segments {
+            let mut offset = 0;
+            while offset < data.len() {
+                let address = start + offset as u64;
+                if address >> 16 != base {
+                    base = address >> 16;
+                    #[allow(clippy::cast_possible_truncation)]
+                    let base = base as u16;
+                    text += &Image::ihex_record(0x04, 0, &base.to_be_bytes());
+                }
+                // records must not cross 64KiB boundary
+                #[allow(clippy::cast_possible_truncation)]
+                let max = 0x10000 - (address & 0xffff) as usize;
+                let len = self.record_len.min(data.len() - offset).min(max);
+                #[allow(clippy::cast_possible_truncation)]
+                let address = address as u16;
+                text += &Image::ihex_record(0x00, address, &data[offset..offset + len]);
+                offset += len;
+            }
+        }
+        if let Some((rtype, value)) = self.entry {
+            let rtype = if rtype == 0x03 { 0x03 } else { 0x05 };
+            text += &Image::ihex_record(rtype, 0, &value.to_be_bytes());
+        }
+        text += &Image::ihex_record(0x01, 0, &[]);
+        text
+    }
+
+    /// Compose Intel HEX record.
+    fn ihex_record(rtype: u8, address: u16, data: &[u8]) -> String {
+        #[allow(clippy::cast_possible_truncation)]
+        let mut record = vec![data.len() as u8];
+        record.extend_from_slice(&address.to_be_bytes());
+        record.push(rtype);
+        record.extend_from_slice(data);
+        let sum = record.iter().fold(0_u8, |s, &b| s.wrapping_add(b));
+        record.push(0_u8.wrapping_sub(sum));
+        format!(":{}\n", Image::encode(&record))
+    }
+
+    /// Compose S-record text.
+    fn write_srec(&self) -> String {
+        let end = self.end();
+        let mut addr_len = self.addr_len;
+        while addr_len < 4 && end > 1 << (addr_len * 8) {
+            addr_len += 1;
+        }
+
+        let mut text = String::new();
+        if !self.header.is_empty() {
+            text += &Image::srec_record(0, 2, 0, &self.header);
+        }
+        let mut count = 0;
+        for (&start, data) in &self.segments {
+            for (index, chunk) in data.chunks(self.record_len).enumerate() {
+                let address = start + (index * self.record_len) as u64;
+                #[allow(clippy::cast_possible_truncation)]
+                let rtype = addr_len as u8 - 1;
+                #[allow(clippy::cast_possible_truncation)]
+                let address = address as u32;
+                text += &Image::srec_record(rtype, addr_len, address, chunk);
+                count += 1;
+            }
+        }
+        if count <= 0xffff {
+            text += &Image::srec_record(5, 2, count, &[]);
+        } else if count <= 0xff_ffff {
+            text += &Image::srec_record(6, 3, count, &[]);
+        }
+        let entry = self.entry.map_or(0, |(_, address)| address);
+        #[allow(clippy::cast_possible_truncation)]
+        let rtype = 11 - addr_len as u8;
+        text += &Image::srec_record(rtype, addr_len, entry, &[]);
+        text
+    }
+
+    /// Compose S-record.
+    fn srec_record(rtype: u8, addr_len: usize, address: u32, data: &[u8]) -> String {
+        #[allow(clippy::cast_possible_truncation)]
+        let mut record = vec![(addr_len + data.len() + 1) as u8];
+        record.extend_from_slice(&address.to_be_bytes()[4 - addr_len..]);
+        record.extend_from_slice(data);
+        let sum = record.iter().fold(0_u8, |s, &b| s.wrapping_add(b));
+        record.push(!sum);
+        format!("S{}{}\n", rtype, Image::encode(&record))
+    }
+
+    /// Encode data to hex string.
+    fn encode(data: &[u8]) -> String {
+        data.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    /// Get the lowest address of the image.
+    pub fn start(&self) -> u64 {
+        self.segments.keys().next().copied().unwrap_or(0)
+    }
+
+    /// Get the end address (next to the highest one) of the image.
+    pub fn end(&self) -> u64 {
+        self.segments
+            .iter()
+            .next_back()
+            .map_or(0, |(start, data)| start + data.len() as u64)
+    }
+
+    /// Get data segments sorted by address.
+    pub fn segments(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.segments
+            .iter()
+            .map(|(&start, data)| (start, data.as_slice()))
+    }
+
+    /// Get addresses without data (gaps) in the specified range.
+    pub fn gaps(&self, range: &Range<u64>) -> Vec<Range<u64>> {
+        let mut gaps = Vec::new();
+        let mut address = range.start;
+        while address < range.end {
+            let mapped = self
+                .segments
+                .range(..=address)
+                .next_back()
+                .map(|(start, data)| start + data.len() as u64)
+                .filter(|&end| address < end);
+            if let Some(end) = mapped {
+                address = end;
+            } else {
+                let next = self
+                    .segments
+                    .range(address..)
+                    .next()
+                    .map_or(range.end, |(&start, _)| start.min(range.end));
+                gaps.push(address..next);
+                address = next;
+            }
+        }
+        gaps
+    }
+
+    /// Read data from the image.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start address
+    /// * `size` - number of bytes to read
+    /// * `fill` - value used for gaps
+    pub fn read(&self, offset: u64, size: usize, fill: u8) -> Vec<u8> {
+        let mut data = vec![fill; size];
+        let end = offset + size as u64;
+        let first = self
+            .segments
+            .range(..=offset)
+            .next_back()
+            .map_or(offset, |(&start, _)| start);
+        for (&start, segment) in self.segments.range(first..end) {
+            let seg_end = start + segment.len() as u64;
+            if seg_end <= offset {
+                continue;
+            }
+            let from = start.max(offset);
+            let to = seg_end.min(end);
+            #[allow(clippy::cast_possible_truncation)]
+            data[(from - offset) as usize..(to - offset) as usize]
+                .copy_from_slice(&segment[(from - start) as usize..(to - start) as usize]);
+        }
+        data
+    }
+
+    /// Write data to the image (creates new segment if needed).
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - start address
+    /// * `data` - data to write
+    pub fn put(&mut self, address: u64, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let end = address + data.len() as u64;
+
+        // fast path: data is inside or at the end of a single segment
+        let next = self.segments.range(address + 1..).next().map(|(&s, _)| s);
+        if let Some((&start, segment)) = self.segments.range_mut(..=address).next_back() {
+            if address <= start + segment.len() as u64 && next.is_none_or(|n| n > end) {
+                #[allow(clippy::cast_possible_truncation)]
+                let (pos, len) = ((address - start) as usize, (end - start) as usize);
+                if segment.len() < len {
+                    segment.resize(len, 0);
+                }
+                segment[pos..len].copy_from_slice(data);
+                return;
+            }
+        }
+
+        // collect all overlapped and adjacent segments
+        let first = self
+            .segments
+            .range(..=address)
+            .next_back()
+            .filter(|(&start, segment)| start + segment.len() as u64 >= address)
+            .map_or(address, |(&start, _)| start);
+        let affected: Vec<u64> = self
+            .segments
+            .range(first..=end)
+            .map(|(&start, _)| start)
+            .collect();
+
+        // merge them into a single segment
+        let mut merged_start = address;
+        let mut merged_end = end;
+        for start in &affected {
+            merged_start = merged_start.min(*start);
+            merged_end = merged_end.max(start + self.segments[start].len() as u64);
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let mut merged = vec![0; (merged_end - merged_start) as usize];
+        for start in affected {
+            let segment = self.segments.remove(&start).unwrap();
+            #[allow(clippy::cast_possible_truncation)]
+            let pos = (start - merged_start) as usize;
+            merged[pos..pos + segment.len()].copy_from_slice(&segment);
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let pos = (address - merged_start) as usize;
+        merged[pos..pos + data.len()].copy_from_slice(data);
+        self.segments.insert(merged_start, merged);
+    }
+
+    /// Get raw binary data from the lowest address to the end of image.
+    ///
+    /// # Arguments
+    ///
+    /// * `fill` - value used for gaps
+    pub fn to_raw(&self, fill: u8) -> Vec<u8> {
+        let start = self.start();
+        #[allow(clippy::cast_possible_truncation)]
+        let size = (self.end() - start) as usize;
+        self.read(start, size, fill)
+    }
+}
+
+#[test]
+fn test_ihex() {
+    let text = b":020000040800F2\n\
+                 :0400100001020304E2\n\
+                 :02001400AABB85\n\
+                 :04000005080001C12D\n\
+                 :00000001FF\n";
+    let image = Image::parse(text, Format::IntelHex).unwrap();
+    assert_eq!(image.start(), 0x0800_0010);
+    assert_eq!(image.end(), 0x0800_0016);
+    assert_eq!(
+        image.read(0x0800_000f, 8, 0xff),
+        vec![0xff, 1, 2, 3, 4, 0xaa, 0xbb, 0xff]
+    );
+    assert_eq!(
+        image.gaps(&(0x0800_000f..0x0800_0017)),
+        vec![0x0800_000f..0x0800_0010, 0x0800_0016..0x0800_0017]
+    );
+    assert_eq!(image.entry, Some((5, 0x0800_01c1)));
+
+    assert_eq!(image.write(Format::IntelHex), text.to_vec());
+
+    assert!(Image::parse(b":0400100001020304E3\n", Format::IntelHex).is_err());
+    assert!(Image::parse(b":00000001FF\n", Format::IntelHex).is_err());
+}
+
+#[test]
+fn test_srec() {
+    let text = b"S00600004844521B\n\
+                 S107010001020304ED\n\
+                 S1050108AABB8C\n\
+                 S5030002FA\n\
+                 S9030000FC\n";
+    let image = Image::parse(text, Format::SRecord).unwrap();
+    assert_eq!(image.start(), 0x100);
+    assert_eq!(image.end(), 0x10a);
+    assert_eq!(
+        image.gaps(&(0xfe..0x10c)),
+        vec![0xfe..0x100, 0x104..0x108, 0x10a..0x10c]
+    );
+    assert_eq!(image.header, b"HDR".to_vec());
+
+    assert_eq!(image.write(Format::SRecord), text.to_vec());
+
+    assert!(Image::parse(b"S107010001020304EE\n", Format::SRecord).is_err());
+}
+
+#[test]
+fn test_put() {
+    let mut image = Image::new();
+    image.put(10, &[1, 2]);
+    image.put(14, &[5]);
+    image.put(12, &[3, 4]);
+    assert_eq!(image.segments.len(), 1);
+    assert_eq!(image.to_raw(0), vec![1, 2, 3, 4, 5]);
+
+    image.put(8, &[9, 9, 9]);
+    assert_eq!(image.segments.len(), 1);
+    assert_eq!(image.to_raw(0), vec![9, 9, 9, 2, 3, 4, 5]);
+    image.put(20, &[7]);
+    assert_eq!(image.segments.len(), 2);
+    assert_eq!(image.to_raw(0), vec![9, 9, 9, 2, 3, 4, 5, 0, 0, 0, 0, 0, 7]);
+    assert_eq!(
+        image.segments().collect::<Vec<_>>(),
+        vec![(8, &[9, 9, 9, 2, 3, 4, 5][..]), (20, &[7][..])]
+    );
+}
+
+#[test]
+fn test_from_raw() {
+    let mut data = vec![0xff; 48];
+    data[20] = 1;
+    data[47] = 2;
+    let image = Image::from_raw(&data, 0xff);
+    assert_eq!(image.start(), 16);
+    assert_eq!(image.end(), 48);
+    assert_eq!(image.gaps(&(0..48)), vec![0..16]);
+}
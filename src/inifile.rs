@@ -2,7 +2,7 @@
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
 use std::collections::BTreeMap;
-use std::fs::{create_dir_all, File};
+use std::fs::File;
 use std::io;
 use std::io::prelude::*;
 use std::io::BufReader;
@@ -23,12 +23,19 @@ impl IniFile {
 
     /// Load configuration from the file.
     pub fn load(file: &Path) -> io::Result<Self> {
-        let ini_file = File::open(file)?;
+        Ok(IniFile::parse(File::open(file)?))
+    }
 
+    /// Parse INI data.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - source of the INI data
+    pub fn parse<R: Read>(reader: R) -> Self {
         let mut instance = IniFile::new();
         let mut last_section = String::new();
 
-        for line in BufReader::new(ini_file).lines().flatten() {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
             let line = line.trim();
             // skip comments and empty lines
             if line.is_empty() || line.starts_with('#') {
@@ -43,21 +50,23 @@ impl IniFile {
             instance
                 .sections
                 .entry(last_section.clone())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(line.to_string());
         }
 
-        Ok(instance)
+        instance
     }
 
-    /// Save configuration to the file.
-    pub fn save(&self, file: &Path) -> io::Result<()> {
-        create_dir_all(file.parent().unwrap())?;
-        let mut ini = File::create(file)?;
+    /// Write INI data.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - destination of the INI data
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
         for (name, params) in &self.sections {
-            ini.write_all(format!("[{}]\n", name).as_bytes())?;
+            writer.write_all(format!("[{}]\n", name).as_bytes())?;
             for line in params.iter() {
-                ini.write_all(format!("{}\n", line).as_bytes())?;
+                writer.write_all(format!("{}\n", line).as_bytes())?;
             }
         }
         Ok(())
@@ -69,7 +78,7 @@ impl IniFile {
         let lc_key = key.to_lowercase();
         let new_line = format!("{} = {}", lc_key, value);
         let section = section.to_lowercase();
-        let section = self.sections.entry(section).or_insert_with(Vec::new);
+        let section = self.sections.entry(section).or_default();
         for (index, line) in section.iter().enumerate() {
             if let Some((ck_key, _)) = IniFile::keyval(line) {
                 if ck_key == lc_key {
@@ -117,13 +126,13 @@ impl IniFile {
     /// Parse and convert line to the Key/Value pair.
     pub fn keyval(line: &str) -> Option<(String, String)> {
         let split: Vec<&str> = line.splitn(2, '=').collect();
-        return if split.len() == 2 {
+        if split.len() == 2 {
             let key = String::from(split[0].trim()).to_lowercase();
             let value = String::from(split[1].trim());
             Some((key, value))
         } else {
             None
-        };
+        }
     }
 }
 
@@ -159,7 +168,7 @@ sectionLine3"#;
 }
 
 #[test]
-fn test_save() {
+fn test_write() {
     let mut ini = IniFile::new();
     ini.sections.insert(
         "section1".to_string(),
@@ -174,16 +183,12 @@ fn test_save() {
         ],
     );
 
-    let tmp_file = std::env::temp_dir().join("xvi_test_ini.save");
-    ini.save(&tmp_file).unwrap();
-
-    let ini_data = std::fs::read_to_string(&tmp_file).unwrap();
+    let mut ini_data = Vec::new();
+    ini.write(&mut ini_data).unwrap();
     assert_eq!(
-        ini_data,
+        String::from_utf8(ini_data).unwrap(),
         "[section1]\nline\nline\n[section2]\nline1\nline2\nline3\n"
     );
-
-    std::fs::remove_file(tmp_file).unwrap();
 }
 
 #[test]
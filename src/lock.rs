@@ -0,0 +1,155 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use std::fmt;
+use std::fs::File;
+use std::io::{Error, Result};
+use std::os::unix::io::AsRawFd;
+
+// POSIX record locks report the PID of the owner, but belong to the process:
+// they never conflict inside a single process and any closed descriptor of
+// the locked file releases them.
+
+/// Owner of the conflicting lock.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Holder {
+    /// Process with the known PID (POSIX record lock).
+    Process(u32),
+    /// Unknown owner (the PID is not reported, e.g. by a network file system).
+    Unknown,
+}
+
+impl fmt::Display for Holder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Holder::Process(pid) => write!(f, "another process (PID {})", pid),
+            Holder::Unknown => write!(f, "another process"),
+        }
+    }
+}
+
+/// Try to take an advisory write lock on the whole file. The lock is held
+/// until any handle of the file is closed by the current process.
+///
+/// # Arguments
+///
+/// * `file` - file opened for writing
+///
+/// # Return value
+///
+/// Owner of the lock, `None` if the lock was taken.
+pub fn try_lock(file: &File) -> Result<Option<Holder>> {
+    loop {
+        let lock = new_lock(libc::F_WRLCK);
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &lock) } == 0 {
+            return Ok(None);
+        }
+        let err = Error::last_os_error();
+        if !matches!(err.raw_os_error(), Some(libc::EACCES | libc::EAGAIN)) {
+            return Err(err);
+        }
+
+        // get the lock owner
+        let mut lock = new_lock(libc::F_WRLCK);
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) } != 0 {
+            return Err(Error::last_os_error());
+        }
+        if lock.l_type != libc::F_UNLCK as libc::c_short {
+            #[allow(clippy::cast_sign_loss)]
+            return Ok(Some(if lock.l_pid > 0 {
+                Holder::Process(lock.l_pid as u32)
+            } else {
+                Holder::Unknown
+            }));
+        }
+        // the lock has just been released, try again
+    }
+}
+
+/// Take an advisory lock on the whole file, waits until the lock is released
+/// by other processes.
+///
+/// # Arguments
+///
+/// * `file` - file to lock
+/// * `write` - lock type: exclusive (write) or shared (read)
+pub fn lock(file: &File, write: bool) -> Result<()> {
+    let lock = new_lock(if write { libc::F_WRLCK } else { libc::F_RDLCK });
+    loop {
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_SETLKW, &lock) } == 0 {
+            return Ok(());
+        }
+        let err = Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EINTR) {
+            return Err(err);
+        }
+    }
+}
+
+/// Create lock description for the whole file.
+///
+/// # Arguments
+///
+/// * `lock_type` - lock type (`F_WRLCK`, `F_RDLCK`)
+fn new_lock(lock_type: libc::c_int) -> libc::flock {
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        lock.l_type = lock_type as libc::c_short;
+        lock.l_whence = libc::SEEK_SET as libc::c_short;
+    }
+    lock
+}
+
+#[test]
+fn test_lock() {
+    let path = std::env::temp_dir().join("xvi_test_lock");
+    std::fs::write(&path, [0]).unwrap();
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .unwrap();
+
+    // take the lock in a child process
+    let mut pipe = [0; 2];
+    assert_eq!(unsafe { libc::pipe(pipe.as_mut_ptr()) }, 0);
+    let pid = unsafe { libc::fork() };
+    assert!(pid >= 0);
+    if pid == 0 {
+        unsafe {
+            let lock = new_lock(libc::F_WRLCK);
+            libc::fcntl(file.as_raw_fd(), libc::F_SETLK, &lock);
+            libc::write(pipe[1], [0_u8].as_ptr().cast(), 1);
+            libc::sleep(10);
+            libc::_exit(0);
+        }
+    }
+    let mut buf = [0_u8];
+    unsafe { libc::read(pipe[0], buf.as_mut_ptr().cast(), 1) };
+
+    #[allow(clippy::cast_sign_loss)]
+    let holder = Holder::Process(pid as u32);
+    assert_eq!(try_lock(&file).unwrap(), Some(holder));
+
+    unsafe {
+        libc::kill(pid, libc::SIGKILL);
+        libc::waitpid(pid, std::ptr::null_mut(), 0);
+        libc::close(pipe[0]);
+        libc::close(pipe[1]);
+    }
+
+    // released with the process termination
+    assert_eq!(try_lock(&file).unwrap(), None);
+    // the same handle never conflicts
+    assert_eq!(try_lock(&file).unwrap(), None);
+    lock(&file, true).unwrap();
+
+    // another handle of the same process never conflicts
+    let other = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    assert_eq!(try_lock(&other).unwrap(), None);
+    drop(other);
+    drop(file);
+
+    std::fs::remove_file(path).unwrap();
+}
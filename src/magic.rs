@@ -0,0 +1,434 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::file::ProgressHandler;
+use std::io::{Error, ErrorKind, Result};
+
+/// Getter of the data size from its header.
+type SizeFn = fn(&[u8]) -> Option<u64>;
+
+/// Signature (magic bytes) of the known data type.
+pub struct Signature {
+    /// Name of the data type.
+    pub name: &'static str,
+    /// Offset of the magic bytes from the start of the data.
+    offset: usize,
+    /// Magic bytes.
+    magic: &'static [u8],
+    /// Additional validation of the data.
+    check: Option<fn(&[u8]) -> bool>,
+    /// Get size of the data from its header.
+    size: Option<SizeFn>,
+    /// Search for the signature while scanning, weak signatures are only
+    /// used to identify data at a known offset.
+    scan: bool,
+}
+
+impl Signature {
+    /// Size of the data block required to check all signatures.
+    pub const WINDOW: usize = 0x8006;
+
+    /// Identify data type.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - data to check, the block should contain `WINDOW` bytes
+    ///   if there is enough data in the file
+    ///
+    /// # Return value
+    ///
+    /// Signature of the detected data type.
+    pub fn identify(data: &[u8]) -> Option<&'static Signature> {
+        Signature::LIST.iter().find(|sig| sig.matches(data))
+    }
+
+    /// Check if the data matches the signature.
+    fn matches(&self, data: &[u8]) -> bool {
+        let end = self.offset + self.magic.len();
+        end <= data.len()
+            && data[self.offset..end] == *self.magic
+            && self.check.is_none_or(|check| check(data))
+    }
+
+    /// Known signatures, the first match wins.
+    const LIST: &'static [Signature] = &[
+        // executables
+        Signature::new("ELF executable", 0, b"\x7fELF"),
+        Signature::with_check("PE executable", 0, b"MZ", is_pe),
+        Signature::new("Mach-O executable", 0, b"\xfe\xed\xfa\xce"),
+        Signature::new("Mach-O executable", 0, b"\xce\xfa\xed\xfe"),
+        Signature::new("Mach-O executable", 0, b"\xfe\xed\xfa\xcf"),
+        Signature::new("Mach-O executable", 0, b"\xcf\xfa\xed\xfe"),
+        Signature::with_check("Java class", 0, b"\xca\xfe\xba\xbe", is_java),
+        Signature::new("WebAssembly", 0, b"\0asm"),
+        // images
+        Signature::new("PNG image", 0, b"\x89PNG\r\n\x1a\n"),
+        Signature::new("JPEG image", 0, b"\xff\xd8\xff"),
+        Signature::new("GIF image", 0, b"GIF87a"),
+        Signature::new("GIF image", 0, b"GIF89a"),
+        Signature::new("TIFF image", 0, b"II*\0"),
+        Signature::new("TIFF image", 0, b"MM\0*"),
+        Signature::with_check("WebP image", 8, b"WEBP", is_riff).sized(riff_size),
+        // media
+        Signature::with_check("WAV audio", 8, b"WAVE", is_riff).sized(riff_size),
+        Signature::with_check("AVI video", 8, b"AVI ", is_riff).sized(riff_size),
+        Signature::new("Ogg media", 0, b"OggS"),
+        Signature::new("FLAC audio", 0, b"fLaC"),
+        Signature::new("MP3 audio", 0, b"ID3"),
+        Signature::new("Matroska media", 0, b"\x1a\x45\xdf\xa3"),
+        Signature::new("MP4 media", 4, b"ftyp"),
+        // documents and databases
+        Signature::new("PDF document", 0, b"%PDF-"),
+        Signature::new("SQLite database", 0, b"SQLite format 3\0"),
+        // archives and compressed data
+        Signature::new("ZIP archive", 0, b"PK\x03\x04"),
+        Signature::new("ZIP archive", 0, b"PK\x05\x06"),
+        Signature::new("7-Zip archive", 0, b"7z\xbc\xaf\x27\x1c"),
+        Signature::new("RAR archive", 0, b"Rar!\x1a\x07"),
+        Signature::new("gzip compressed data", 0, b"\x1f\x8b\x08"),
+        Signature::new("xz compressed data", 0, b"\xfd7zXZ\0"),
+        Signature::with_check("bzip2 compressed data", 0, b"BZh", is_bzip2),
+        Signature::new("Zstandard compressed data", 0, b"\x28\xb5\x2f\xfd"),
+        Signature::new("LZ4 compressed data", 0, b"\x04\x22\x4d\x18"),
+        Signature::with_check("LZMA compressed data", 0, b"\x5d\0\0", is_lzma),
+        // two bytes header is too weak to search for it in arbitrary data
+        Signature::with_check("zlib compressed data", 0, b"\x78", is_zlib).identify_only(),
+        Signature::new("cpio archive", 0, b"070701"),
+        Signature::new("cpio archive", 0, b"070702"),
+        Signature::new("cpio archive", 0, b"070707"),
+        Signature::new("tar archive", 257, b"ustar"),
+        // firmware and file systems
+        Signature::with_check("Device tree blob (FIT/DTB)", 0, b"\xd0\x0d\xfe\xed", is_dtb)
+            .sized(dtb_size),
+        Signature::new("U-Boot image", 0, b"\x27\x05\x19\x56").sized(uimage_size),
+        Signature::new("Android boot image", 0, b"ANDROID!"),
+        Signature::new("Android sparse image", 0, b"\x3a\xff\x26\xed"),
+        Signature::new("UBI image", 0, b"UBI#"),
+        Signature::new("UBIFS image", 0, b"\x31\x18\x10\x06"),
+        Signature::new("SquashFS file system", 0, b"hsqs").sized(squashfs_size),
+        Signature::new("SquashFS file system", 0, b"sqsh").sized(squashfs_size),
+        Signature::new("CramFS file system", 0, b"\x45\x3d\xcd\x28").sized(cramfs_size),
+        Signature::with_check("JFFS2 file system", 0, b"\x85\x19", is_jffs2),
+        Signature::with_check("JFFS2 file system", 0, b"\x19\x85", is_jffs2),
+        Signature::new("UEFI firmware volume", 40, b"_FVH").sized(uefi_fv_size),
+        Signature::new("NTFS file system", 3, b"NTFS    "),
+        Signature::with_check("FAT file system", 0x36, b"FAT1", is_boot_sector),
+        Signature::with_check("FAT file system", 0x52, b"FAT32", is_boot_sector),
+        Signature::new("ext2/3/4 file system", 0x438, b"\x53\xef").sized(ext_size),
+        Signature::new("ISO 9660 image", 0x8001, b"CD001").sized(iso_size),
+    ];
+
+    /// Create signature description.
+    const fn new(name: &'static str, offset: usize, magic: &'static [u8]) -> Self {
+        Self {
+            name,
+            offset,
+            magic,
+            check: None,
+            size: None,
+            scan: true,
+        }
+    }
+
+    /// Create signature description with additional validation.
+    const fn with_check(
+        name: &'static str,
+        offset: usize,
+        magic: &'static [u8],
+        check: fn(&[u8]) -> bool,
+    ) -> Self {
+        Self {
+            name,
+            offset,
+            magic,
+            check: Some(check),
+            size: None,
+            scan: true,
+        }
+    }
+
+    /// Set the data size getter.
+    const fn sized(mut self, size: SizeFn) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Exclude the signature from scanning.
+    const fn identify_only(mut self) -> Self {
+        self.scan = false;
+        self
+    }
+}
+
+/// Signature found by the scanner.
+pub struct Hit {
+    /// Offset of the data.
+    pub offset: u64,
+    /// Guessed size of the data.
+    pub size: u64,
+    /// Name of the data type.
+    pub name: &'static str,
+}
+
+impl Hit {
+    /// Get text representation of the hit.
+    pub fn line(&self) -> String {
+        format!("{:08x}  {:>10x}  {}", self.offset, self.size, self.name)
+    }
+}
+
+/// Max number of hits reported by the scanner.
+const MAX_HITS: usize = 10000;
+/// Size of the block checked per iteration.
+const SCAN_BLOCK: usize = 0x10000;
+
+/// Scan data for embedded signatures.
+///
+/// The size of the data is taken from its header if the format allows it,
+/// otherwise it is guessed as the distance to the next hit.
+///
+/// # Arguments
+///
+/// * `size` - size of the file
+/// * `read` - data reader: offset and size of the block
+/// * `progress` - long time operation handler
+///
+/// # Return value
+///
+/// List of hits sorted by offset.
+pub fn scan(
+    size: u64,
+    read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>,
+    progress: &mut dyn ProgressHandler,
+) -> Result<Vec<Hit>> {
+    let mut hits = Vec::new();
+    let mut known = Vec::new();
+    let mut offset = 0;
+
+    while offset < size && hits.len() < MAX_HITS {
+        let percent = (100.0 / size as f64) * offset as f64;
+        if !progress.update(percent as u8) {
+            return Err(Error::new(ErrorKind::Interrupted, "Aborted by user"));
+        }
+
+        let data = read(offset, SCAN_BLOCK + Signature::WINDOW)?;
+        let end = data.len().min(SCAN_BLOCK);
+        for pos in 0..end {
+            let sig = Signature::LIST
+                .iter()
+                .find(|sig| sig.scan && sig.matches(&data[pos..]));
+            if let Some(sig) = sig {
+                let start = offset + pos as u64;
+                let length = sig
+                    .size
+                    .and_then(|get| get(&data[pos..]))
+                    .filter(|&len| len != 0 && len <= size - start);
+                hits.push(Hit {
+                    offset: start,
+                    size: length.unwrap_or(size - start),
+                    name: sig.name,
+                });
+                known.push(length.is_some());
+                if hits.len() == MAX_HITS {
+                    break;
+                }
+            }
+        }
+        offset += end as u64;
+    }
+
+    // guess unknown sizes as the distance to the next hit
+    for index in 0..hits.len().saturating_sub(1) {
+        if !known[index] {
+            let next = hits[index + 1].offset;
+            hits[index].size = next - hits[index].offset;
+        }
+    }
+
+    Ok(hits)
+}
+
+/// Get 32-bit number from the data.
+fn u32_at(data: &[u8], offset: usize, le: bool) -> Option<u64> {
+    let val = data.get(offset..offset + 4)?;
+    let val = [val[0], val[1], val[2], val[3]];
+    Some(u64::from(if le {
+        u32::from_le_bytes(val)
+    } else {
+        u32::from_be_bytes(val)
+    }))
+}
+
+/// Get 64-bit number from the data.
+fn u64_at(data: &[u8], offset: usize, le: bool) -> Option<u64> {
+    let lo = u32_at(data, offset, le)?;
+    let hi = u32_at(data, offset + 4, le)?;
+    Some(if le { hi << 32 | lo } else { lo << 32 | hi })
+}
+
+/// Check for PE signature pointed by the MZ header.
+fn is_pe(data: &[u8]) -> bool {
+    data.get(0x3c..0x40)
+        .map(|val| u32::from_le_bytes([val[0], val[1], val[2], val[3]]) as usize)
+        .and_then(|offset| data.get(offset..offset + 4))
+        .is_some_and(|sig| sig == b"PE\0\0")
+}
+
+/// Check for Java class version (Mach-O fat binary has the same magic).
+fn is_java(data: &[u8]) -> bool {
+    data.get(6..8)
+        .is_some_and(|val| (45..100).contains(&u16::from_be_bytes([val[0], val[1]])))
+}
+
+/// Check for RIFF container.
+fn is_riff(data: &[u8]) -> bool {
+    data.starts_with(b"RIFF")
+}
+
+/// Check for bzip2 block size.
+fn is_bzip2(data: &[u8]) -> bool {
+    matches!(data.get(3), Some(b'1'..=b'9'))
+}
+
+/// Check for sane size of the device tree blob.
+fn is_dtb(data: &[u8]) -> bool {
+    data.get(4..8)
+        .is_some_and(|val| u32::from_be_bytes([val[0], val[1], val[2], val[3]]) >= 0x28)
+}
+
+/// Check for known JFFS2 node type.
+fn is_jffs2(data: &[u8]) -> bool {
+    data.get(2..4).is_some_and(|val| {
+        let node = if data[0] == 0x85 {
+            u16::from_le_bytes([val[0], val[1]])
+        } else {
+            u16::from_be_bytes([val[0], val[1]])
+        };
+        matches!(
+            node,
+            0xe001 | 0xe002 | 0x2003 | 0x2004 | 0x2006 | 0xe008 | 0xe009
+        )
+    })
+}
+
+/// Check for boot sector signature.
+fn is_boot_sector(data: &[u8]) -> bool {
+    data.get(510..512).is_some_and(|val| val == b"\x55\xaa")
+}
+
+/// Check for sane LZMA header: dictionary and uncompressed sizes.
+fn is_lzma(data: &[u8]) -> bool {
+    u32_at(data, 1, true).is_some_and(|dict| dict.is_power_of_two() && dict >= 0x10000)
+        && u64_at(data, 5, true).is_some_and(|size| size == u64::MAX || size < 1 << 40)
+}
+
+/// Check for zlib header: compression info, header checksum and deflate block type.
+fn is_zlib(data: &[u8]) -> bool {
+    matches!(data.get(1), Some(0x01 | 0x5e | 0x9c | 0xda))
+        && data.get(2).is_some_and(|&block| (block >> 1) & 3 != 3)
+}
+
+/// Get size of the RIFF container.
+fn riff_size(data: &[u8]) -> Option<u64> {
+    Some(u32_at(data, 4, true)? + 8)
+}
+
+/// Get size of the device tree blob.
+fn dtb_size(data: &[u8]) -> Option<u64> {
+    u32_at(data, 4, false)
+}
+
+/// Get size of the U-Boot image: header and payload.
+fn uimage_size(data: &[u8]) -> Option<u64> {
+    Some(u32_at(data, 12, false)? + 64)
+}
+
+/// Get size of the SquashFS image.
+fn squashfs_size(data: &[u8]) -> Option<u64> {
+    u64_at(data, 40, data[0] == b'h')
+}
+
+/// Get size of the CramFS image.
+fn cramfs_size(data: &[u8]) -> Option<u64> {
+    u32_at(data, 4, true)
+}
+
+/// Get size of the UEFI firmware volume.
+fn uefi_fv_size(data: &[u8]) -> Option<u64> {
+    u64_at(data, 32, true)
+}
+
+/// Get size of the ext2/3/4 file system.
+fn ext_size(data: &[u8]) -> Option<u64> {
+    let blocks = u32_at(data, 0x404, true)?;
+    let shift = u32_at(data, 0x418, true).filter(|&shift| shift < 16)?;
+    blocks.checked_mul(1024 << shift)
+}
+
+/// Get size of the ISO 9660 image.
+fn iso_size(data: &[u8]) -> Option<u64> {
+    let blocks = u32_at(data, 0x8050, true)?;
+    let block = data.get(0x8080..0x8082)?;
+    Some(blocks * u64::from(u16::from_le_bytes([block[0], block[1]])))
+}
+
+#[test]
+fn test_identify() {
+    let name = |data: &[u8]| Signature::identify(data).map(|sig| sig.name);
+
+    assert_eq!(name(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"), Some("PNG image"));
+    assert_eq!(name(b"\x7fELF\x02\x01\x01"), Some("ELF executable"));
+    assert_eq!(name(b"RIFF\0\0\0\0WEBPVP8 "), Some("WebP image"));
+    assert_eq!(name(b"BZh9"), Some("bzip2 compressed data"));
+    assert_eq!(name(b"BZh0"), None);
+    assert_eq!(name(b"\x85\x19\x01\xe0"), Some("JFFS2 file system"));
+    assert_eq!(name(b"\x19\x85\xe0\x02"), Some("JFFS2 file system"));
+    assert_eq!(name(b"\x85\x19\x00\x00"), None);
+
+    let mut data = vec![0; 0x200];
+    data[0..2].copy_from_slice(b"MZ");
+    assert_eq!(name(&data), None);
+    data[0x3c] = 0x80;
+    data[0x80..0x84].copy_from_slice(b"PE\0\0");
+    assert_eq!(name(&data), Some("PE executable"));
+
+    let mut data = vec![0; 0x200];
+    data[257..263].copy_from_slice(b"ustar\0");
+    assert_eq!(name(&data), Some("tar archive"));
+    assert_eq!(name(&data[..260]), None);
+
+    let mut data = vec![0; Signature::WINDOW];
+    data[0x8001..0x8006].copy_from_slice(b"CD001");
+    assert_eq!(name(&data), Some("ISO 9660 image"));
+
+    assert_eq!(name(b"\x78\x9c\x01"), Some("zlib compressed data"));
+    assert_eq!(name(b""), None);
+    assert_eq!(name(b"plain text"), None);
+}
+
+#[test]
+fn test_scan() {
+    let mut data = vec![0; 0x30000];
+    data[0x100..0x108].copy_from_slice(b"\x89PNG\r\n\x1a\n");
+    data[0x1000..0x1004].copy_from_slice(b"RIFF");
+    data[0x1004..0x1008].copy_from_slice(&0x18_u32.to_le_bytes());
+    data[0x1008..0x100c].copy_from_slice(b"WAVE");
+    data[0xfffe..0x10002].copy_from_slice(b"\x1f\x8b\x08\0");
+    data[0x20000..0x20003].copy_from_slice(b"\x78\x9c\x01");
+
+    let size = data.len() as u64;
+    let mut read = |offset: u64, len: usize| {
+        let end = (offset as usize + len).min(data.len());
+        Ok(data[offset as usize..end].to_vec())
+    };
+    let hits = scan(size, &mut read, &mut super::file::NoProgress).unwrap();
+    let hits: Vec<(u64, u64, &str)> = hits.iter().map(|h| (h.offset, h.size, h.name)).collect();
+    assert_eq!(
+        hits,
+        vec![
+            (0x100, 0xf00, "PNG image"),
+            (0x1000, 0x20, "WAV audio"),
+            (0xfffe, 0x20002, "gzip compressed data"),
+        ]
+    );
+}
@@ -2,21 +2,47 @@
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
 mod ascii;
+mod batch;
 mod changes;
+mod checksum;
+mod clipboard;
 mod config;
 mod controller;
 mod curses;
 mod cursor;
 mod editor;
+mod elf;
+mod exec;
 mod file;
+mod generator;
+mod hex;
+mod hexdump;
+mod histogram;
 mod history;
+mod image;
 mod inifile;
+mod lock;
+mod magic;
+mod minimap;
+mod patch;
+mod patchlist;
+mod pe;
+mod printer;
+mod strings;
+mod template;
+mod transform;
 mod ui;
 mod view;
 
+use batch::{Batch, Command};
 use config::Config;
 use controller::Controller;
 use curses::Curses;
+use file::{File, NoProgress};
+use hexdump::Dump;
+use printer::Printer;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
 
 // Exit codes
 const EFAULT: i32 = 14;
@@ -42,6 +68,37 @@ fn main() {
         std::process::exit(EINVAL);
     }
 
+    // apply edit commands
+    if !args.commands.is_empty() || args.script.is_some() {
+        run_batch(&args);
+        return;
+    }
+
+    // print files to stdout
+    if args.dump {
+        if let Err(err) = print_dump(&args) {
+            exit_error(&err, &args.files.join(", "));
+        }
+        return;
+    }
+
+    // non-interactive conversions
+    if args.export.is_some() || args.reverse {
+        if args.files.len() > 2 {
+            eprintln!("Too many files, expected input and output files");
+            std::process::exit(EINVAL);
+        }
+        let result = if let Some(format) = args.export {
+            export_text(&args, format)
+        } else {
+            reverse_text(&args)
+        };
+        if let Err(err) = result {
+            exit_error(&err, &args.files[0]);
+        }
+        return;
+    }
+
     // install custom panic hook to close curses before printing error info
     let default_panic = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
@@ -56,21 +113,193 @@ fn main() {
 
     Curses::initialize(&config.colors);
 
-    if let Err(err) = Controller::run(&args.files, args.offset, config) {
+    if let Err(err) = Controller::run(&args.files, args.offset, args.follow, config) {
         Curses::close();
-        eprintln!("{}: {}", err, args.files.join(", "));
-        let mut exit_code = EFAULT;
-        if let Some(errno) = err.raw_os_error() {
-            if errno != 0 {
-                exit_code = errno;
-            }
-        }
-        std::process::exit(exit_code);
+        exit_error(&err, &args.files.join(", "));
     };
 
     Curses::close();
 }
 
+/// Print error message and exit the process.
+///
+/// # Arguments
+///
+/// * `err` - error to report
+/// * `files` - names of the files being processed
+fn exit_error(err: &io::Error, files: &str) -> ! {
+    eprintln!("{}: {}", err, files);
+    let mut exit_code = EFAULT;
+    if let Some(errno) = err.raw_os_error() {
+        if errno != 0 {
+            exit_code = errno;
+        }
+    }
+    std::process::exit(exit_code);
+}
+
+/// Open output stream: the file if specified or stdout.
+///
+/// # Arguments
+///
+/// * `path` - path to the output file
+fn open_output(path: Option<&String>) -> io::Result<Box<dyn Write>> {
+    Ok(match path {
+        Some(path) => Box::new(BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    })
+}
+
+/// Apply edit commands from the command line and the script file to the
+/// files, exit the process on errors.
+///
+/// # Arguments
+///
+/// * `args` - command line arguments
+fn run_batch(args: &CmdLineArgs) {
+    // collect and parse all commands before editing
+    let mut commands: Vec<(String, String)> = args
+        .commands
+        .iter()
+        .enumerate()
+        .map(|(index, cmd)| (format!("command {}", index + 1), cmd.clone()))
+        .collect();
+    if let Some(script) = &args.script {
+        let text = if script == "-" {
+            io::read_to_string(io::stdin())
+        } else {
+            std::fs::read_to_string(script)
+        };
+        let text = text.unwrap_or_else(|err| exit_error(&err, script));
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                commands.push((format!("{}:{}", script, index + 1), line.to_string()));
+            }
+        }
+    }
+    let mut parsed = Vec::with_capacity(commands.len());
+    for (source, text) in &commands {
+        match Command::parse(text) {
+            Ok(cmd) => parsed.push(cmd),
+            Err(err) => {
+                eprintln!("{}: {}", source, err);
+                std::process::exit(EINVAL);
+            }
+        }
+    }
+
+    let config = Config::load();
+    for file in &args.files {
+        let mut batch =
+            Batch::open(Path::new(file), &config).unwrap_or_else(|err| exit_error(&err, file));
+        for (cmd, (source, text)) in parsed.iter().zip(commands.iter()) {
+            if let Err(err) = batch.execute(cmd) {
+                // remove the working copy, the process exits without unwinding
+                drop(batch);
+                exit_error(&err, &format!("{} ({}: {})", file, source, text));
+            }
+        }
+        if let Err(err) = batch.save() {
+            exit_error(&err, file);
+        }
+    }
+}
+
+/// Print files to stdout with the same layout as the viewer uses.
+///
+/// # Arguments
+///
+/// * `args` - command line arguments
+fn print_dump(args: &CmdLineArgs) -> io::Result<()> {
+    let config = Config::load();
+    let mut printer = Printer::new(&args.files, &config, terminal_width(), args.color)?;
+    let start = args.offset.unwrap_or(0);
+    let end = args
+        .length
+        .map_or(u64::MAX, |length| start.saturating_add(length));
+    let mut out = BufWriter::new(io::stdout().lock());
+    printer.print(&(start..end), &mut out)?;
+    out.flush()
+}
+
+/// Get width of the terminal attached to stdout.
+///
+/// # Return value
+///
+/// Number of columns, value of `COLUMNS` environment variable or 80 if
+/// stdout is not a terminal.
+fn terminal_width() -> usize {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0
+        && size.ws_col != 0
+    {
+        return size.ws_col as usize;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(80)
+}
+
+/// Write text dump of the input file, like `xxd` does.
+///
+/// # Arguments
+///
+/// * `args` - command line arguments
+/// * `format` - text dump format
+fn export_text(args: &CmdLineArgs, format: Dump) -> io::Result<()> {
+    let mut file = File::open(Path::new(&args.files[0]))?;
+    let start = args.offset.unwrap_or(0).min(file.size);
+    let end = args.length.map_or(file.size, |length| {
+        file.size.min(start.saturating_add(length))
+    });
+    let columns = args.columns.unwrap_or(16);
+    if let Some(target) = args.files.get(1) {
+        file.check_target(Path::new(target))?;
+    }
+    let mut out = open_output(args.files.get(1))?;
+    format.write(&mut file, &(start..end), columns, &mut out, &mut NoProgress)?;
+    out.flush()
+}
+
+/// Convert text dump back to binary, like `xxd -r` does: data is placed at
+/// the offsets from the dump, gaps are filled with zeros.
+///
+/// # Arguments
+///
+/// * `args` - command line arguments
+fn reverse_text(args: &CmdLineArgs) -> io::Result<()> {
+    let image = if args.files[0] == "-" {
+        hexdump::parse(&mut io::stdin().lock())?
+    } else {
+        hexdump::parse(&mut BufReader::new(std::fs::File::open(&args.files[0])?))?
+    };
+    let mut out: Box<dyn Write> = match args.files.get(1) {
+        Some(path) => {
+            let file = std::fs::File::create(path)?;
+            if file.metadata()?.is_file() {
+                // skip gaps, the file system fills them with zeros
+                let mut out = BufWriter::new(file);
+                for (address, data) in image.segments() {
+                    out.seek(SeekFrom::Start(address))?;
+                    out.write_all(data)?;
+                }
+                return out.flush();
+            }
+            Box::new(BufWriter::new(file))
+        }
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let mut position = 0;
+    for (address, data) in image.segments() {
+        io::copy(&mut io::repeat(0).take(address - position), &mut out)?;
+        out.write_all(data)?;
+        position = address + data.len() as u64;
+    }
+    out.flush()
+}
+
 /// Print program version.
 fn print_version() {
     println!(
@@ -84,7 +313,20 @@ fn print_version() {
 fn print_help() {
     print_version();
     println!("Usage: xvi [OPTION...] FILE...");
+    println!("       xvi -e FORMAT [-o ADDRESS] [-l LENGTH] [-c COLUMNS] FILE [OUTPUT]");
+    println!("       xvi -r DUMP|- [OUTPUT]");
+    println!("       xvi -d [--color] [-o ADDRESS] [-l LENGTH] FILE...");
+    println!("       xvi [-x COMMAND]... [-s SCRIPT] FILE...");
     println!("  -o, --offset ADDRESS   Set initial cursor offset");
+    println!("  -f, --follow           Follow data appended to files");
+    println!("  -e, --export FORMAT    Print text dump of the file (xxd, hexdump, od)");
+    println!("  -r, --reverse          Convert text dump back to binary");
+    println!("  -d, --dump             Print files to stdout like the viewer does");
+    println!("      --color            Use ANSI colors in the printed dump");
+    println!("  -x, --exec COMMAND     Apply edit command to files and exit");
+    println!("  -s, --script SCRIPT    Apply edit commands from the script file");
+    println!("  -l, --length LENGTH    Number of bytes to dump or export");
+    println!("  -c, --columns COLUMNS  Number of bytes per line in text dump");
     println!("  -v, --version          Print version info and exit");
     println!("  -h, --help             Print this help and exit");
 }
@@ -93,6 +335,24 @@ fn print_help() {
 struct CmdLineArgs {
     /// Initial cursor offset.
     offset: Option<u64>,
+    /// Flag to enable follow mode.
+    follow: bool,
+    /// Format of the text dump to export.
+    export: Option<Dump>,
+    /// Flag to convert text dump to binary.
+    reverse: bool,
+    /// Flag to print files to stdout.
+    dump: bool,
+    /// Flag to use ANSI colors in the printed dump.
+    color: bool,
+    /// Edit commands to apply.
+    commands: Vec<String>,
+    /// Script file with edit commands.
+    script: Option<String>,
+    /// Number of bytes to dump or export.
+    length: Option<u64>,
+    /// Number of bytes per line in text dump.
+    columns: Option<usize>,
     /// Flag to print version info.
     version: bool,
     /// Flag to print help.
@@ -118,6 +378,15 @@ impl CmdLineArgs {
         let mut instance = Self {
             files: Vec::new(),
             offset: None,
+            follow: false,
+            export: None,
+            reverse: false,
+            dump: false,
+            color: false,
+            commands: Vec::new(),
+            script: None,
+            length: None,
+            columns: None,
             version: false,
             help: false,
         };
@@ -125,21 +394,14 @@ impl CmdLineArgs {
         let mut last_index = args.len();
         let mut it = args.iter().enumerate();
         while let Some((index, arg)) = it.next() {
-            if !arg.starts_with('-') {
+            if !arg.starts_with('-') || arg == "-" {
                 last_index = index;
                 break;
             }
             match arg.as_ref() {
                 "-o" | "--offset" => {
                     if let Some((_, text)) = it.next() {
-                        let (start, radix) = if text.starts_with("0x") {
-                            (2 /* skip 0x */, 16)
-                        } else if text.to_lowercase().chars().any(|c| matches!(c, 'a'..='f')) {
-                            (0, 16)
-                        } else {
-                            (0, 10)
-                        };
-                        if let Ok(offset) = u64::from_str_radix(&text[start..], radix) {
+                        if let Some(offset) = hex::parse_number(text) {
                             instance.offset = Some(offset);
                         } else {
                             return Err(format!("Invalid offset value: {}", text));
@@ -148,6 +410,64 @@ impl CmdLineArgs {
                         return Err("Offset not specified".to_string());
                     }
                 }
+                "-f" | "--follow" => {
+                    instance.follow = true;
+                }
+                "-e" | "--export" => {
+                    if let Some((_, text)) = it.next() {
+                        if let Some(format) = Dump::from_id(text) {
+                            instance.export = Some(format);
+                        } else {
+                            return Err(format!("Invalid dump format: {}", text));
+                        }
+                    } else {
+                        return Err("Dump format not specified".to_string());
+                    }
+                }
+                "-r" | "--reverse" => {
+                    instance.reverse = true;
+                }
+                "-d" | "--dump" => {
+                    instance.dump = true;
+                }
+                "--color" => {
+                    instance.color = true;
+                }
+                "-x" | "--exec" => {
+                    if let Some((_, text)) = it.next() {
+                        instance.commands.push(text.clone());
+                    } else {
+                        return Err("Command not specified".to_string());
+                    }
+                }
+                "-s" | "--script" => {
+                    if let Some((_, text)) = it.next() {
+                        instance.script = Some(text.clone());
+                    } else {
+                        return Err("Script file not specified".to_string());
+                    }
+                }
+                "-l" | "--length" => {
+                    if let Some((_, text)) = it.next() {
+                        if let Some(length) = hex::parse_number(text) {
+                            instance.length = Some(length);
+                        } else {
+                            return Err(format!("Invalid length value: {}", text));
+                        }
+                    } else {
+                        return Err("Length not specified".to_string());
+                    }
+                }
+                "-c" | "--columns" => {
+                    if let Some((_, text)) = it.next() {
+                        match text.parse::<usize>() {
+                            Ok(columns) if columns > 0 => instance.columns = Some(columns),
+                            _ => return Err(format!("Invalid number of columns: {}", text)),
+                        }
+                    } else {
+                        return Err("Number of columns not specified".to_string());
+                    }
+                }
                 "-v" | "--version" => {
                     instance.version = true;
                 }
@@ -232,7 +552,7 @@ fn test_files() {
     assert!(!args.help);
     assert!(!args.version);
     assert_eq!(args.files.len(), 1);
-    assert_eq!(args.files.get(0), Some(&"file".to_string()));
+    assert_eq!(args.files.first(), Some(&"file".to_string()));
 
     let args = [
         "-v".to_string(),
@@ -242,6 +562,100 @@ fn test_files() {
     ];
     let args = CmdLineArgs::parse(args.to_vec()).unwrap();
     assert_eq!(args.files.len(), 2);
-    assert_eq!(args.files.get(0), Some(&"--file1".to_string()));
+    assert_eq!(args.files.first(), Some(&"--file1".to_string()));
     assert_eq!(args.files.get(1), Some(&"file2".to_string()));
 }
+
+#[test]
+fn test_follow() {
+    let args = ["file".to_string()];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert!(!args.follow);
+
+    let args = ["--follow".to_string(), "file".to_string()];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert!(args.follow);
+    assert_eq!(args.files.len(), 1);
+
+    let args = ["-f".to_string(), "-o".to_string(), "10".to_string()];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert!(args.follow);
+    assert_eq!(args.offset, Some(10));
+}
+
+#[test]
+fn test_export() {
+    let args = ["-e".to_string(), "xxd".to_string(), "file".to_string()];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert_eq!(args.export, Some(Dump::Xxd));
+    assert!(!args.reverse);
+    assert_eq!(args.files.len(), 1);
+
+    let args = [
+        "--export".to_string(),
+        "hexdump".to_string(),
+        "-l".to_string(),
+        "0x100".to_string(),
+        "-c".to_string(),
+        "8".to_string(),
+    ];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert_eq!(args.export, Some(Dump::Hexdump));
+    assert_eq!(args.length, Some(0x100));
+    assert_eq!(args.columns, Some(8));
+
+    let args = ["-r".to_string(), "-".to_string(), "out".to_string()];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert!(args.reverse);
+    assert_eq!(args.files.len(), 2);
+
+    let args = ["-e".to_string(), "base64".to_string()];
+    assert!(CmdLineArgs::parse(args.to_vec()).is_err());
+    let args = ["-c".to_string(), "0".to_string()];
+    assert!(CmdLineArgs::parse(args.to_vec()).is_err());
+}
+
+#[test]
+fn test_dump() {
+    let args = ["file".to_string()];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert!(!args.dump);
+    assert!(!args.color);
+
+    let args = [
+        "--dump".to_string(),
+        "--color".to_string(),
+        "-o".to_string(),
+        "0x10".to_string(),
+        "--length".to_string(),
+        "32".to_string(),
+        "file1".to_string(),
+        "file2".to_string(),
+    ];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert!(args.dump);
+    assert!(args.color);
+    assert_eq!(args.offset, Some(0x10));
+    assert_eq!(args.length, Some(32));
+    assert_eq!(args.files.len(), 2);
+}
+
+#[test]
+fn test_batch() {
+    let args = [
+        "-x".to_string(),
+        "set 0 00".to_string(),
+        "--exec".to_string(),
+        "cut 0+1".to_string(),
+        "--script".to_string(),
+        "script".to_string(),
+        "file".to_string(),
+    ];
+    let args = CmdLineArgs::parse(args.to_vec()).unwrap();
+    assert_eq!(args.commands, ["set 0 00", "cut 0+1"]);
+    assert_eq!(args.script, Some("script".to_string()));
+    assert_eq!(args.files.len(), 1);
+
+    let args = ["-x".to_string()];
+    assert!(CmdLineArgs::parse(args.to_vec()).is_err());
+}
@@ -0,0 +1,376 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::histogram::Histogram;
+use std::collections::BTreeSet;
+use std::io::Result;
+use std::ops::Range;
+
+/// Coloring mode of the minimap.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MapMode {
+    /// Shannon entropy of the data.
+    Entropy,
+    /// Prevailing class of the bytes.
+    Class,
+}
+
+impl MapMode {
+    /// All available modes.
+    pub const ALL: &'static [MapMode] = &[MapMode::Entropy, MapMode::Class];
+
+    /// Get minimap mode by its ID.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id.to_lowercase().as_str() {
+            "entropy" => Some(MapMode::Entropy),
+            "class" => Some(MapMode::Class),
+            _ => None,
+        }
+    }
+
+    /// Get mode name.
+    pub fn name(self) -> &'static str {
+        match self {
+            MapMode::Entropy => "Entropy",
+            MapMode::Class => "Byte class",
+        }
+    }
+}
+
+/// Prevailing class of the bytes in the file slice.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ByteClass {
+    /// Zero bytes (padding).
+    Zero,
+    /// Printable ASCII text.
+    Text,
+    /// High bytes (0x80-0xff), including erased flash (0xff).
+    High,
+    /// High entropy data: compressed or encrypted.
+    Random,
+    /// Mixed binary data.
+    Binary,
+}
+
+/// Statistics of the file slice.
+#[derive(Copy, Clone, Debug)]
+pub struct Slice {
+    /// Shannon entropy in bits per byte (0.0-8.0).
+    pub entropy: f64,
+    /// Prevailing class of the bytes.
+    pub class: ByteClass,
+}
+
+impl Slice {
+    /// Entropy level of random (compressed or encrypted) data.
+    const RANDOM: f64 = 7.0;
+
+    /// Create slice description from its histogram.
+    fn new(hist: &Histogram) -> Self {
+        let entropy = hist.entropy();
+        let total = hist.total.max(1);
+        let class = if hist.counts[0] * 10 >= total * 9 {
+            ByteClass::Zero
+        } else if entropy >= Slice::RANDOM {
+            ByteClass::Random
+        } else if hist
+            .count(|b| b == b'\t' || b == b'\n' || b == b'\r' || (0x20..0x7f).contains(&b))
+            * 4
+            >= total * 3
+        {
+            ByteClass::Text
+        } else if hist.count(|b| b >= 0x80) * 2 >= total {
+            ByteClass::High
+        } else {
+            ByteClass::Binary
+        };
+        Self { entropy, class }
+    }
+}
+
+/// File overview: statistics of equal slices of the file.
+pub struct Minimap {
+    /// Size of the file.
+    pub file_size: u64,
+    /// Number of slices.
+    pub cells: usize,
+    /// Statistics of the already calculated slices.
+    pub slices: Vec<Slice>,
+    /// Calculated slices with outdated statistics.
+    outdated: BTreeSet<usize>,
+    /// Histogram of the slice in progress.
+    histogram: Histogram,
+    /// Index of the next block in the slice in progress.
+    block: usize,
+}
+
+impl Minimap {
+    /// Size of the single data block.
+    const BLOCK_SIZE: u64 = 0x10000;
+    /// Max number of blocks read from a single slice (large slices are sampled).
+    const MAX_BLOCKS: u64 = 16;
+    /// Number of blocks processed per update step.
+    const STEP: usize = 16;
+
+    /// Create new minimap.
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - number of slices
+    /// * `file_size` - size of the file
+    ///
+    /// # Return value
+    ///
+    /// Minimap instance.
+    pub fn new(cells: usize, file_size: u64) -> Self {
+        Self {
+            file_size,
+            cells: (cells as u64).min(file_size) as usize,
+            slices: Vec::new(),
+            outdated: BTreeSet::new(),
+            histogram: Histogram::default(),
+            block: 0,
+        }
+    }
+
+    /// Check if all slices are calculated.
+    pub fn is_complete(&self) -> bool {
+        self.next().is_none()
+    }
+
+    /// Get progress of calculation.
+    pub fn progress(&self) -> u8 {
+        ((self.slices.len() - self.outdated.len()) * 100 / self.cells.max(1)) as u8
+    }
+
+    /// Get index of the slice to calculate: outdated slices go first.
+    fn next(&self) -> Option<usize> {
+        self.outdated
+            .iter()
+            .next()
+            .copied()
+            .or_else(|| Some(self.slices.len()).filter(|&cell| cell < self.cells))
+    }
+
+    /// Mark slices covering the modified data as outdated, they are
+    /// recalculated by the next updates.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - modified file range
+    pub fn invalidate(&mut self, range: &Range<u64>) {
+        if range.start >= range.end || range.start >= self.file_size {
+            return;
+        }
+        let first = self.cell(range.start);
+        let last = self.cell(range.end.min(self.file_size) - 1);
+        let calculated = self.slices.len();
+        self.outdated
+            .extend((first..=last).filter(|&cell| cell < calculated));
+        // the slice in progress may contain outdated data, start it again
+        self.histogram = Histogram::default();
+        self.block = 0;
+    }
+
+    /// Get file range of the slice.
+    ///
+    /// # Arguments
+    ///
+    /// * `cell` - index of the slice
+    ///
+    /// # Return value
+    ///
+    /// File range.
+    pub fn range(&self, cell: usize) -> Range<u64> {
+        let offset = |cell: usize| {
+            (u128::from(self.file_size) * cell as u128 / self.cells.max(1) as u128) as u64
+        };
+        offset(cell)..offset(cell + 1)
+    }
+
+    /// Get index of the slice containing specified offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - file offset
+    ///
+    /// # Return value
+    ///
+    /// Index of the slice.
+    pub fn cell(&self, offset: u64) -> usize {
+        let cell = (u128::from(offset) + 1) * self.cells as u128;
+        let cell = (cell.div_ceil(u128::from(self.file_size.max(1)))) as usize;
+        cell.saturating_sub(1).min(self.cells.saturating_sub(1))
+    }
+
+    /// Calculate the next portion of slices.
+    ///
+    /// # Arguments
+    ///
+    /// * `read` - data reader: offset and size of the block
+    ///
+    /// # Return value
+    ///
+    /// Operation status.
+    pub fn update(&mut self, read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>) -> Result<()> {
+        for _ in 0..Minimap::STEP {
+            let cell = match self.next() {
+                Some(cell) => cell,
+                None => break,
+            };
+            let range = self.range(cell);
+            let length = range.end - range.start;
+            let blocks = length
+                .div_ceil(Minimap::BLOCK_SIZE)
+                .min(Minimap::MAX_BLOCKS);
+            // large slices are sampled, blocks must not overlap
+            let step = if blocks < Minimap::MAX_BLOCKS {
+                Minimap::BLOCK_SIZE
+            } else {
+                length / Minimap::MAX_BLOCKS
+            };
+            let start = range.start + self.block as u64 * step;
+            #[allow(clippy::cast_possible_truncation)]
+            let size = (range.end - start).min(Minimap::BLOCK_SIZE).min(step) as usize;
+            self.histogram.add(&read(start, size)?);
+            self.block += 1;
+            if self.block as u64 >= blocks {
+                let slice = Slice::new(&self.histogram);
+                if self.outdated.remove(&cell) {
+                    self.slices[cell] = slice;
+                } else {
+                    self.slices.push(slice);
+                }
+                self.histogram = Histogram::default();
+                self.block = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_minimap() {
+    let mut data = vec![0_u8; 0x10000];
+    data[0x4000..0x8000].fill(0xff);
+    for (index, byte) in data[0x8000..0xc000].iter_mut().enumerate() {
+        *byte = b"The quick brown fox\n"[index % 20];
+    }
+    let mut seed: u32 = 1;
+    for byte in &mut data[0xc000..] {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        *byte = (seed >> 16) as u8;
+    }
+
+    let mut map = Minimap::new(8, data.len() as u64);
+    assert_eq!(map.range(1), 0x2000..0x4000);
+    assert_eq!(map.cell(0), 0);
+    assert_eq!(map.cell(0x1fff), 0);
+    assert_eq!(map.cell(0x2000), 1);
+    assert_eq!(map.cell(0xffff), 7);
+
+    let mut read =
+        |offset: u64, size: usize| Ok(data[offset as usize..offset as usize + size].to_vec());
+    while !map.is_complete() {
+        map.update(&mut read).unwrap();
+    }
+    assert_eq!(map.progress(), 100);
+    let classes: Vec<ByteClass> = map.slices.iter().map(|s| s.class).collect();
+    assert_eq!(
+        classes,
+        vec![
+            ByteClass::Zero,
+            ByteClass::Zero,
+            ByteClass::High,
+            ByteClass::High,
+            ByteClass::Text,
+            ByteClass::Text,
+            ByteClass::Random,
+            ByteClass::Random,
+        ]
+    );
+    assert!(map.slices[0].entropy.abs() < f64::EPSILON);
+    assert!(map.slices[7].entropy > 7.9);
+
+    // only the slices covering the modified data are recalculated
+    data[0x4000..0x6000].fill(0);
+    map.invalidate(&(0x5fff..0x6001));
+    map.invalidate(&(0x10000..0x10001));
+    assert!(!map.is_complete());
+    assert_eq!(map.progress(), 75);
+    let mut reads = Vec::new();
+    let mut read = |offset: u64, size: usize| {
+        reads.push(offset);
+        Ok(data[offset as usize..offset as usize + size].to_vec())
+    };
+    while !map.is_complete() {
+        map.update(&mut read).unwrap();
+    }
+    assert_eq!(reads, vec![0x4000, 0x6000]);
+    assert_eq!(map.slices[2].class, ByteClass::Zero);
+    assert_eq!(map.slices[3].class, ByteClass::High);
+
+    // slices larger than the max number of blocks are sampled
+    let mut map = Minimap::new(1, 0x200000);
+    let mut reads = 0;
+    let mut read = |_: u64, size: usize| {
+        reads += 1;
+        Ok(vec![0; size])
+    };
+    map.update(&mut read).unwrap();
+    assert!(map.is_complete());
+    assert_eq!(reads, Minimap::MAX_BLOCKS as usize);
+
+    // sampled blocks don't overlap
+    let length = Minimap::MAX_BLOCKS * Minimap::BLOCK_SIZE - 1;
+    let mut map = Minimap::new(1, length);
+    let mut blocks = Vec::new();
+    let mut read = |offset: u64, size: usize| {
+        blocks.push(offset..offset + size as u64);
+        Ok(vec![0; size])
+    };
+    map.update(&mut read).unwrap();
+    assert!(map.is_complete());
+    assert!(blocks.windows(2).all(|b| b[0].end <= b[1].start));
+    assert!(blocks.last().unwrap().end <= length);
+
+    // small file: number of cells is limited by the file size
+    let map = Minimap::new(10, 3);
+    assert_eq!(map.cells, 3);
+    assert_eq!(map.range(2), 2..3);
+    assert_eq!(map.cell(2), 2);
+}
+
+#[test]
+fn test_minimap_malformed() {
+    // empty file
+    let mut map = Minimap::new(10, 0);
+    assert_eq!(map.cells, 0);
+    assert!(map.is_complete());
+    assert_eq!(map.progress(), 0);
+    assert_eq!(map.cell(0), 0);
+    assert_eq!(map.cell(u64::MAX), 0);
+    map.invalidate(&(0..10));
+    assert!(map.is_complete());
+    map.update(&mut |_, _| unreachable!()).unwrap();
+
+    // offsets at the end of address space
+    let mut map = Minimap::new(3, u64::MAX);
+    assert_eq!(map.range(2).end, u64::MAX);
+    assert_eq!(map.cell(u64::MAX), 2);
+    map.invalidate(&(u64::MAX - 1..u64::MAX));
+    map.invalidate(&(5..5));
+
+    // read errors are reported, short reads don't break the calculation
+    let mut map = Minimap::new(2, 0x40000);
+    let mut read = |_: u64, _: usize| Err(std::io::ErrorKind::UnexpectedEof.into());
+    let err = map.update(&mut read).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert!(map.slices.is_empty());
+    map.update(&mut |_, _| Ok(Vec::new())).unwrap();
+    assert!(map.is_complete());
+    assert!(map
+        .slices
+        .iter()
+        .all(|slice| slice.entropy.abs() < f64::EPSILON));
+}
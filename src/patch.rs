@@ -0,0 +1,576 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::checksum::crc32;
+use std::convert::TryFrom;
+use std::io::{Error, ErrorKind, Result};
+
+/// Binary patch format.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PatchFormat {
+    /// International Patching System.
+    Ips,
+    /// Beat patch system.
+    Bps,
+    /// Universal patching system.
+    Ups,
+}
+
+impl PatchFormat {
+    /// All available formats.
+    pub const ALL: &'static [PatchFormat] = &[PatchFormat::Ips, PatchFormat::Bps, PatchFormat::Ups];
+
+    /// Max offset that can be addressed in IPS patch.
+    const IPS_MAX_OFFSET: usize = 0x00ff_ffff;
+    /// Max size of a single IPS record.
+    const IPS_MAX_RECORD: usize = 0xffff;
+    /// IPS footer, can not be used as a record offset.
+    const IPS_EOF: usize = 0x0045_4f46;
+    /// Size of BPS/UPS footer (three CRC-32 values).
+    const FOOTER_SIZE: usize = 12;
+    /// Max number of target bytes per byte of BPS/UPS patch beyond the
+    /// source size, protects from allocating memory for bogus target sizes.
+    const MAX_GROWTH: usize = 0x100;
+
+    /// Get format ID (used as file extension).
+    pub fn id(self) -> &'static str {
+        match self {
+            PatchFormat::Ips => "ips",
+            PatchFormat::Bps => "bps",
+            PatchFormat::Ups => "ups",
+        }
+    }
+
+    /// Get human readable format name.
+    pub fn name(self) -> &'static str {
+        match self {
+            PatchFormat::Ips => "IPS",
+            PatchFormat::Bps => "BPS",
+            PatchFormat::Ups => "UPS",
+        }
+    }
+
+    /// Get signature of the format.
+    fn magic(self) -> &'static [u8] {
+        match self {
+            PatchFormat::Ips => b"PATCH",
+            PatchFormat::Bps => b"BPS1",
+            PatchFormat::Ups => b"UPS1",
+        }
+    }
+
+    /// Detect format of the patch by its signature.
+    ///
+    /// # Arguments
+    ///
+    /// * `patch` - patch data
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        PatchFormat::ALL
+            .iter()
+            .copied()
+            .find(|f| patch.starts_with(f.magic()))
+    }
+
+    /// Create patch that converts the source data to the target one.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - original data
+    /// * `target` - modified data
+    ///
+    /// # Return value
+    ///
+    /// Patch data.
+    pub fn create(self, source: &[u8], target: &[u8]) -> Result<Vec<u8>> {
+        let mut patch = self.magic().to_vec();
+        match self {
+            PatchFormat::Ips => PatchFormat::create_ips(&mut patch, source, target)?,
+            PatchFormat::Bps => PatchFormat::create_bps(&mut patch, source, target),
+            PatchFormat::Ups => PatchFormat::create_ups(&mut patch, source, target),
+        }
+        if self != PatchFormat::Ips {
+            patch.extend_from_slice(&crc32(source).to_le_bytes());
+            patch.extend_from_slice(&crc32(target).to_le_bytes());
+            patch.extend_from_slice(&crc32(&patch).to_le_bytes());
+        }
+        Ok(patch)
+    }
+
+    /// Apply patch to the source data, the format is detected automatically.
+    ///
+    /// # Arguments
+    ///
+    /// * `patch` - patch data
+    /// * `source` - original data
+    ///
+    /// # Return value
+    ///
+    /// Patched data.
+    pub fn apply(patch: &[u8], source: &[u8]) -> Result<Vec<u8>> {
+        let format = PatchFormat::detect(patch)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Unknown patch format"))?;
+        if format == PatchFormat::Ips {
+            return PatchFormat::apply_ips(patch, source);
+        }
+
+        // check footer
+        if patch.len() < format.magic().len() + PatchFormat::FOOTER_SIZE {
+            return Err(Reader::eof());
+        }
+        let footer = patch.len() - PatchFormat::FOOTER_SIZE;
+        let crc = |pos: usize| {
+            let mut value = [0; 4];
+            value.copy_from_slice(&patch[footer + pos..footer + pos + 4]);
+            u32::from_le_bytes(value)
+        };
+        if crc(8) != crc32(&patch[..footer + 8]) {
+            return Err(PatchFormat::checksum_error("patch"));
+        }
+        if crc(0) != crc32(source) {
+            return Err(PatchFormat::checksum_error("source"));
+        }
+
+        let mut reader = Reader {
+            data: &patch[..footer],
+            pos: format.magic().len(),
+        };
+        let target = match format {
+            PatchFormat::Bps => PatchFormat::apply_bps(&mut reader, source)?,
+            _ => PatchFormat::apply_ups(&mut reader, source)?,
+        };
+
+        if crc(4) != crc32(&target) {
+            return Err(PatchFormat::checksum_error("target"));
+        }
+        Ok(target)
+    }
+
+    /// Create IPS patch body.
+    fn create_ips(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) -> Result<()> {
+        let mut offset = 0;
+        while offset < target.len() {
+            if source.get(offset) == Some(&target[offset]) {
+                offset += 1;
+                continue;
+            }
+
+            // record start, the offset must not look like the footer
+            let mut start = offset;
+            if start == PatchFormat::IPS_EOF {
+                start -= 1;
+            }
+            if start > PatchFormat::IPS_MAX_OFFSET {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "IPS patch can not address data beyond 16 MiB",
+                ));
+            }
+            let mut end = offset;
+            while end < target.len()
+                && end - start < PatchFormat::IPS_MAX_RECORD
+                && source.get(end) != Some(&target[end])
+            {
+                end += 1;
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                patch.extend_from_slice(&(start as u32).to_be_bytes()[1..]);
+                patch.extend_from_slice(&((end - start) as u16).to_be_bytes());
+            }
+            patch.extend_from_slice(&target[start..end]);
+            offset = end;
+        }
+        patch.extend_from_slice(b"EOF");
+
+        // truncation extension
+        if target.len() < source.len() {
+            #[allow(clippy::cast_possible_truncation)]
+            patch.extend_from_slice(&(target.len() as u32).to_be_bytes()[1..]);
+        }
+        Ok(())
+    }
+
+    /// Apply IPS patch.
+    fn apply_ips(patch: &[u8], source: &[u8]) -> Result<Vec<u8>> {
+        let mut target = source.to_vec();
+        let mut reader = Reader {
+            data: patch,
+            pos: PatchFormat::Ips.magic().len(),
+        };
+        loop {
+            let offset = reader.number(3)?;
+            if offset == PatchFormat::IPS_EOF {
+                break;
+            }
+            let mut size = reader.number(2)?;
+            let rle = if size == 0 {
+                size = reader.number(2)?;
+                Some(reader.bytes(1)?[0])
+            } else {
+                None
+            };
+            if target.len() < offset + size {
+                target.resize(offset + size, 0);
+            }
+            match rle {
+                Some(value) => target[offset..offset + size].fill(value),
+                None => target[offset..offset + size].copy_from_slice(reader.bytes(size)?),
+            }
+        }
+        // truncation extension
+        if reader.pos + 3 == patch.len() {
+            target.truncate(reader.number(3)?);
+        }
+        Ok(target)
+    }
+
+    /// Create BPS patch body: unchanged data is read from the source, the
+    /// rest is stored in the patch.
+    fn create_bps(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+        encode_number(patch, source.len() as u64);
+        encode_number(patch, target.len() as u64);
+        encode_number(patch, 0); // no metadata
+
+        let mut offset = 0;
+        while offset < target.len() {
+            let same = source.get(offset) == Some(&target[offset]);
+            let mut end = offset + 1;
+            while end < target.len() && (source.get(end) == Some(&target[end])) == same {
+                end += 1;
+            }
+            let action = if same { 0 } else { 1 }; // SourceRead / TargetRead
+            encode_number(patch, ((end - offset - 1) as u64) << 2 | action);
+            if !same {
+                patch.extend_from_slice(&target[offset..end]);
+            }
+            offset = end;
+        }
+    }
+
+    /// Apply BPS patch actions.
+    fn apply_bps(reader: &mut Reader, source: &[u8]) -> Result<Vec<u8>> {
+        let source_size = reader.size()?;
+        let target_size = reader.size()?;
+        let metadata = reader.size()?;
+        reader.bytes(metadata)?;
+        if source_size != source.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "Source size mismatch"));
+        }
+        PatchFormat::check_target_size(target_size, source, reader)?;
+
+        let mut target = Vec::with_capacity(target_size);
+        let mut source_pos: usize = 0;
+        let mut target_pos: usize = 0;
+        while !reader.is_empty() {
+            let action = reader.size()?;
+            let length = (action >> 2) + 1;
+            if target.len() + length > target_size {
+                return Err(PatchFormat::invalid_action());
+            }
+            match action & 3 {
+                0 => {
+                    // SourceRead
+                    let start = target.len();
+                    let data = source
+                        .get(start..start + length)
+                        .ok_or_else(PatchFormat::invalid_action)?;
+                    target.extend_from_slice(data);
+                }
+                1 => {
+                    // TargetRead
+                    target.extend_from_slice(reader.bytes(length)?);
+                }
+                2 => {
+                    // SourceCopy
+                    source_pos = reader.relative(source_pos)?;
+                    let data = source
+                        .get(source_pos..source_pos + length)
+                        .ok_or_else(PatchFormat::invalid_action)?;
+                    target.extend_from_slice(data);
+                    source_pos += length;
+                }
+                _ => {
+                    // TargetCopy, the regions can overlap
+                    target_pos = reader.relative(target_pos)?;
+                    if target_pos >= target.len() {
+                        return Err(PatchFormat::invalid_action());
+                    }
+                    for _ in 0..length {
+                        target.push(target[target_pos]);
+                        target_pos += 1;
+                    }
+                }
+            }
+        }
+        if target.len() != target_size {
+            return Err(Error::new(ErrorKind::InvalidData, "Target size mismatch"));
+        }
+        Ok(target)
+    }
+
+    /// Create UPS patch body: XOR of the changed blocks.
+    fn create_ups(patch: &mut Vec<u8>, source: &[u8], target: &[u8]) {
+        encode_number(patch, source.len() as u64);
+        encode_number(patch, target.len() as u64);
+
+        let size = source.len().max(target.len());
+        let byte = |data: &[u8], pos: usize| data.get(pos).copied().unwrap_or(0);
+        let mut last = 0;
+        let mut offset = 0;
+        while offset < size {
+            if byte(source, offset) == byte(target, offset) {
+                offset += 1;
+                continue;
+            }
+            encode_number(patch, (offset - last) as u64);
+            while offset < size && byte(source, offset) != byte(target, offset) {
+                patch.push(byte(source, offset) ^ byte(target, offset));
+                offset += 1;
+            }
+            patch.push(0);
+            offset += 1;
+            last = offset;
+        }
+    }
+
+    /// Apply UPS patch blocks.
+    fn apply_ups(reader: &mut Reader, source: &[u8]) -> Result<Vec<u8>> {
+        let source_size = reader.size()?;
+        let target_size = reader.size()?;
+        if source_size != source.len() {
+            return Err(Error::new(ErrorKind::InvalidData, "Source size mismatch"));
+        }
+        PatchFormat::check_target_size(target_size, source, reader)?;
+
+        let mut target: Vec<u8> = (0..target_size)
+            .map(|pos| source.get(pos).copied().unwrap_or(0))
+            .collect();
+        let mut offset: usize = 0;
+        while !reader.is_empty() {
+            offset = offset.saturating_add(reader.size()?);
+            loop {
+                let xor = reader.bytes(1)?[0];
+                if xor == 0 {
+                    offset += 1;
+                    break;
+                }
+                if let Some(byte) = target.get_mut(offset) {
+                    *byte ^= xor;
+                }
+                offset += 1;
+            }
+        }
+        Ok(target)
+    }
+
+    /// Check the target size declared in BPS/UPS patch header.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - declared target size
+    /// * `source` - original data
+    /// * `reader` - patch reader
+    ///
+    /// # Return value
+    ///
+    /// Error if the size can not be produced by the patch.
+    fn check_target_size(size: usize, source: &[u8], reader: &Reader) -> Result<()> {
+        let max = reader
+            .data
+            .len()
+            .saturating_mul(PatchFormat::MAX_GROWTH)
+            .saturating_add(source.len());
+        if size > max {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Target size is out of range",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Create "checksum mismatch" error.
+    fn checksum_error(name: &str) -> Error {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Checksum mismatch ({})", name),
+        )
+    }
+
+    /// Create "invalid action" error.
+    fn invalid_action() -> Error {
+        Error::new(ErrorKind::InvalidData, "Invalid patch action")
+    }
+}
+
+/// Write variable length number (BPS/UPS encoding).
+fn encode_number(patch: &mut Vec<u8>, mut value: u64) {
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        let bits = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            patch.push(0x80 | bits);
+            break;
+        }
+        patch.push(bits);
+        value -= 1;
+    }
+}
+
+/// Patch data reader.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    /// Check if all data was read.
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    /// Read sequence of bytes.
+    fn bytes(&mut self, size: usize) -> Result<&[u8]> {
+        let end = self.pos.checked_add(size).ok_or_else(Reader::eof)?;
+        let data = self.data.get(self.pos..end).ok_or_else(Reader::eof)?;
+        self.pos = end;
+        Ok(data)
+    }
+
+    /// Read big endian number (IPS).
+    fn number(&mut self, size: usize) -> Result<usize> {
+        Ok(self
+            .bytes(size)?
+            .iter()
+            .fold(0, |acc, &b| acc << 8 | usize::from(b)))
+    }
+
+    /// Read variable length number (BPS/UPS).
+    fn size(&mut self) -> Result<usize> {
+        let mut value: u64 = 0;
+        let mut shift: u64 = 1;
+        loop {
+            let byte = self.bytes(1)?[0];
+            value = u64::from(byte & 0x7f)
+                .checked_mul(shift)
+                .and_then(|v| v.checked_add(value))
+                .ok_or_else(Reader::eof)?;
+            if byte & 0x80 != 0 {
+                break;
+            }
+            shift = shift.checked_shl(7).ok_or_else(Reader::eof)?;
+            value = value.checked_add(shift).ok_or_else(Reader::eof)?;
+        }
+        usize::try_from(value).map_err(|_| Reader::eof())
+    }
+
+    /// Read relative offset (BPS) and apply it to the base position.
+    fn relative(&mut self, base: usize) -> Result<usize> {
+        let value = self.size()?;
+        let delta = value >> 1;
+        let pos = if value & 1 != 0 {
+            base.checked_sub(delta)
+        } else {
+            base.checked_add(delta)
+        };
+        pos.ok_or_else(PatchFormat::invalid_action)
+    }
+
+    /// Create "unexpected end" error.
+    fn eof() -> Error {
+        Error::new(ErrorKind::InvalidData, "Invalid patch: unexpected end")
+    }
+}
+
+#[test]
+fn test_number() {
+    for value in [0, 1, 0x7f, 0x80, 0x1234, 0x4080, u64::from(u32::MAX)] {
+        let mut data = Vec::new();
+        encode_number(&mut data, value);
+        let mut reader = Reader {
+            data: &data,
+            pos: 0,
+        };
+        assert_eq!(reader.size().unwrap() as u64, value);
+        assert!(reader.is_empty());
+    }
+}
+
+#[test]
+fn test_ips() {
+    let source = b"0123456789";
+    let target = b"0a23bb678";
+    let patch = PatchFormat::Ips.create(source, target).unwrap();
+    assert_eq!(
+        patch,
+        b"PATCH\x00\x00\x01\x00\x01a\x00\x00\x04\x00\x02bbEOF\x00\x00\x09".to_vec()
+    );
+    assert_eq!(PatchFormat::apply(&patch, source).unwrap(), target.to_vec());
+
+    // RLE record, enlarge
+    let patch = b"PATCH\x00\x00\x08\x00\x00\x00\x04zEOF";
+    assert_eq!(
+        PatchFormat::apply(patch, source).unwrap(),
+        b"01234567zzzz".to_vec()
+    );
+
+    assert!(PatchFormat::apply(b"PATCH\x00\x00\x01\x00\x05a", source).is_err());
+}
+
+#[test]
+fn test_bps_ups() {
+    let source = b"Hello, world! Hello, world!".to_vec();
+    let targets = [
+        b"Hello, World! Hello, world!".to_vec(),
+        b"Hello!".to_vec(),
+        b"Hello, world! Hello, world! And more data".to_vec(),
+    ];
+    for format in [PatchFormat::Bps, PatchFormat::Ups] {
+        for target in &targets {
+            let patch = format.create(&source, target).unwrap();
+            assert_eq!(PatchFormat::detect(&patch), Some(format));
+            assert_eq!(&PatchFormat::apply(&patch, &source).unwrap(), target);
+
+            // wrong source
+            let err = PatchFormat::apply(&patch, b"Hello").unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+            // corrupted patch
+            let mut corrupted = patch.clone();
+            corrupted[6] ^= 1;
+            assert!(PatchFormat::apply(&corrupted, &source).is_err());
+        }
+    }
+
+    // hand made BPS patch with SourceCopy and TargetCopy actions
+    let mut patch = b"BPS1".to_vec();
+    encode_number(&mut patch, 4);
+    encode_number(&mut patch, 8);
+    encode_number(&mut patch, 0);
+    encode_number(&mut patch, (2 - 1) << 2 | 2); // SourceCopy 2 bytes from +2
+    encode_number(&mut patch, 2 << 1);
+    encode_number(&mut patch, (6 - 1) << 2 | 3); // TargetCopy 6 bytes from 0
+    encode_number(&mut patch, 0);
+    patch.extend_from_slice(&crc32(b"abcd").to_le_bytes());
+    patch.extend_from_slice(&crc32(b"cdcdcdcd").to_le_bytes());
+    patch.extend_from_slice(&crc32(&patch).to_le_bytes());
+    assert_eq!(
+        PatchFormat::apply(&patch, b"abcd").unwrap(),
+        b"cdcdcdcd".to_vec()
+    );
+
+    // valid checksums, but huge target size
+    for magic in [b"BPS1", b"UPS1"] {
+        let mut patch = magic.to_vec();
+        encode_number(&mut patch, 4);
+        encode_number(&mut patch, 1 << 40);
+        encode_number(&mut patch, 0);
+        patch.extend_from_slice(&crc32(b"abcd").to_le_bytes());
+        patch.extend_from_slice(&0_u32.to_le_bytes());
+        patch.extend_from_slice(&crc32(&patch).to_le_bytes());
+        let err = PatchFormat::apply(&patch, b"abcd").unwrap_err();
+        assert_eq!(err.to_string(), "Target size is out of range");
+    }
+}
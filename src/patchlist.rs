@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::hex;
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Result};
+
+/// Run of modified bytes.
+#[derive(Debug, PartialEq)]
+pub struct Hunk {
+    /// Address of the first byte.
+    pub offset: u64,
+    /// Origin values.
+    pub old: Vec<u8>,
+    /// New values.
+    pub new: Vec<u8>,
+}
+
+/// Human-readable patch: list of `OFFSET: OLD -> NEW` lines, where offset is
+/// a hex address and values are space separated hex bytes, e.g.
+/// `00000010: 41 42 -> 61 62`.
+#[derive(Debug, Default, PartialEq)]
+pub struct PatchList {
+    /// Runs of modified bytes.
+    pub hunks: Vec<Hunk>,
+}
+
+impl PatchList {
+    /// Max number of bytes in a single line.
+    const LINE_BYTES: usize = 8;
+
+    /// Create patch list from the map of changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `changes` - map of changes: offset -> (origin value, new value)
+    ///
+    /// # Return value
+    ///
+    /// Patch list with adjacent changes coalesced into runs.
+    pub fn from_changes(changes: &BTreeMap<u64, (u8, u8)>) -> Self {
+        let mut hunks: Vec<Hunk> = Vec::new();
+        for (&offset, &(old, new)) in changes {
+            if let Some(last) = hunks.last_mut() {
+                if last.offset + last.old.len() as u64 == offset
+                    && last.old.len() < PatchList::LINE_BYTES
+                {
+                    last.old.push(old);
+                    last.new.push(new);
+                    continue;
+                }
+            }
+            hunks.push(Hunk {
+                offset,
+                old: vec![old],
+                new: vec![new],
+            });
+        }
+        Self { hunks }
+    }
+
+    /// Check if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// Get text representation of the patch list.
+    ///
+    /// # Return value
+    ///
+    /// Text lines, one per hunk.
+    pub fn lines(&self) -> Vec<String> {
+        let hex = |data: &[u8]| -> String {
+            data.iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<String>>()
+                .join(" ")
+        };
+        self.hunks
+            .iter()
+            .map(|h| format!("{:08x}: {} -> {}", h.offset, hex(&h.old), hex(&h.new)))
+            .collect()
+    }
+
+    /// Parse patch list from text, empty lines and lines started with `#`
+    /// are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - text to parse
+    ///
+    /// # Return value
+    ///
+    /// Patch list.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut hunks = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let hunk = PatchList::parse_line(line).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid patch list at line {}", index + 1),
+                )
+            })?;
+            hunks.push(hunk);
+        }
+        if hunks.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "Patch list is empty"));
+        }
+        Ok(Self { hunks })
+    }
+
+    /// Parse single line of the patch list.
+    fn parse_line(line: &str) -> Option<Hunk> {
+        let (offset, values) = line.split_once(':')?;
+        let (old, new) = values.split_once("->")?;
+        let offset = u64::from_str_radix(offset.trim(), 16).ok()?;
+        let old = hex::parse_bytes(old)?;
+        let new = hex::parse_bytes(new)?;
+        if old.is_empty() || old.len() != new.len() {
+            return None;
+        }
+        offset.checked_add(old.len() as u64)?;
+        Some(Hunk { offset, old, new })
+    }
+}
+
+#[test]
+fn test_patchlist() {
+    let mut changes = BTreeMap::new();
+    changes.insert(0x10, (0x41, 0x61));
+    changes.insert(0x11, (0x42, 0x62));
+    changes.insert(0x20, (0x00, 0xff));
+    for offset in 0x30..0x3a {
+        changes.insert(offset, (0x01, 0x02));
+    }
+    let list = PatchList::from_changes(&changes);
+    let lines = list.lines();
+    assert_eq!(
+        lines,
+        vec![
+            "00000010: 41 42 -> 61 62",
+            "00000020: 00 -> ff",
+            "00000030: 01 01 01 01 01 01 01 01 -> 02 02 02 02 02 02 02 02",
+            "00000038: 01 01 -> 02 02",
+        ]
+    );
+
+    let text = format!("# comment\n\n{}\n", lines.join("\n"));
+    assert_eq!(PatchList::parse(&text).unwrap(), list);
+
+    assert!(PatchList::parse("").is_err());
+    assert!(PatchList::parse("10: 41 -> 61 62").is_err());
+    assert!(PatchList::parse("10: 41 61").is_err());
+    assert!(PatchList::parse("xx: 41 -> 61").is_err());
+    assert!(PatchList::parse("10: 4 -> 6").is_err());
+}
@@ -0,0 +1,893 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::exec::{name, string, Node, Reader};
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+
+/// PE/COFF file description.
+pub struct Pe {
+    /// Offset of the PE signature.
+    pub header: u64,
+    /// PE32+ (64-bit) format.
+    pub is64: bool,
+    /// Values of the COFF header fields (see `Pe::COFF`).
+    coff: Vec<u64>,
+    /// Values of the optional header fields (see `Pe::OPTIONAL`), empty
+    /// if the optional header is absent.
+    optional: Vec<u64>,
+    /// Data directories: RVA (file offset for certificates) and size.
+    pub directories: Vec<(u64, u64)>,
+    /// Section table.
+    pub sections: Vec<Section>,
+}
+
+/// Section header.
+pub struct Section {
+    /// Offset of the section header.
+    pub header: u64,
+    /// Section name.
+    pub name: String,
+    /// Relative virtual address.
+    pub address: u64,
+    /// Size of the section in memory.
+    pub virtual_size: u64,
+    /// Offset of the section data.
+    pub offset: u64,
+    /// Size of the section data in the file.
+    pub size: u64,
+    /// Section characteristics.
+    pub flags: u32,
+}
+
+impl Pe {
+    /// File signature (MZ header).
+    pub const SIGNATURE: &'static [u8] = b"MZ";
+    /// Size of the MZ header.
+    const MZ_SIZE: u64 = 0x40;
+    /// Offset of the PE header pointer inside the MZ header.
+    const MZ_LFANEW: u64 = 0x3c;
+    /// Size of the PE signature.
+    const PE_SIGNATURE_SIZE: u64 = 4;
+    /// COFF header fields: name and size.
+    const COFF: &'static [(&'static str, usize)] = &[
+        ("Machine", 2),
+        ("Number of sections", 2),
+        ("Time stamp", 4),
+        ("Symbol table", 4),
+        ("Number of symbols", 4),
+        ("Optional header size", 2),
+        ("Characteristics", 2),
+    ];
+    /// Optional header fields: name, size in PE32 and size in PE32+
+    /// (zero size means the field is absent).
+    const OPTIONAL: &'static [(&'static str, usize, usize)] = &[
+        ("Magic", 2, 2),
+        ("Linker major version", 1, 1),
+        ("Linker minor version", 1, 1),
+        ("Size of code", 4, 4),
+        ("Size of initialized data", 4, 4),
+        ("Size of uninitialized data", 4, 4),
+        ("Entry point", 4, 4),
+        ("Base of code", 4, 4),
+        ("Base of data", 4, 0),
+        ("Image base", 4, 8),
+        ("Section alignment", 4, 4),
+        ("File alignment", 4, 4),
+        ("OS major version", 2, 2),
+        ("OS minor version", 2, 2),
+        ("Image major version", 2, 2),
+        ("Image minor version", 2, 2),
+        ("Subsystem major version", 2, 2),
+        ("Subsystem minor version", 2, 2),
+        ("Win32 version", 4, 4),
+        ("Size of image", 4, 4),
+        ("Size of headers", 4, 4),
+        ("Checksum", 4, 4),
+        ("Subsystem", 2, 2),
+        ("DLL characteristics", 2, 2),
+        ("Stack reserve", 4, 8),
+        ("Stack commit", 4, 8),
+        ("Heap reserve", 4, 8),
+        ("Heap commit", 4, 8),
+        ("Loader flags", 4, 4),
+        ("Number of directories", 4, 4),
+    ];
+    /// Index of the "Size of headers" optional header field.
+    const OPT_HEADERS_SIZE: usize = 20;
+    /// Index of the "Number of directories" optional header field.
+    const OPT_DIRECTORIES: usize = 29;
+    /// PE32+ magic value.
+    const MAGIC_PE32_PLUS: u64 = 0x20b;
+    /// Size of the section header.
+    const SECTION_SIZE: u64 = 40;
+    /// Data directory indexes.
+    const DIR_EXPORT: usize = 0;
+    const DIR_IMPORT: usize = 1;
+    const DIR_CERTIFICATE: usize = 4;
+    /// Max number of data directories.
+    const MAX_DIRECTORIES: u64 = 16;
+    /// Max number of imported and exported entries to load.
+    const MAX_ENTRIES: usize = 10_000;
+    /// Max length of the name.
+    const MAX_NAME: usize = 256;
+
+    /// Parse PE headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    /// * `read` - data reader: offset and size of the block
+    ///
+    /// # Return value
+    ///
+    /// PE description.
+    pub fn parse(size: u64, read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>) -> Result<Self> {
+        let mut reader = Reader::new(read, size, true);
+
+        let not_pe = || Error::new(ErrorKind::InvalidData, "Not a PE file");
+        let header = reader.numbers(Pe::MZ_LFANEW, &[4]).map_err(|_| not_pe())?[0];
+        if reader.bytes(0, 2)? != Pe::SIGNATURE
+            || reader
+                .bytes(header, Pe::PE_SIGNATURE_SIZE as usize)
+                .map_err(|_| not_pe())?
+                != b"PE\0\0"
+        {
+            return Err(not_pe());
+        }
+
+        // COFF header
+        let sizes: Vec<usize> = Pe::COFF.iter().map(|&(_, size)| size).collect();
+        let coff_offset = header + Pe::PE_SIGNATURE_SIZE;
+        let coff = reader.numbers(coff_offset, &sizes)?;
+        let opt_offset = coff_offset + sizes.iter().sum::<usize>() as u64;
+        let opt_size = coff[5];
+
+        let mut pe = Self {
+            header,
+            is64: false,
+            coff,
+            optional: Vec::new(),
+            directories: Vec::new(),
+            sections: Vec::new(),
+        };
+
+        // optional header
+        if opt_size != 0 {
+            pe.is64 = reader.numbers(opt_offset, &[2])?[0] == Pe::MAGIC_PE32_PLUS;
+            let sizes = pe.optional_sizes();
+            let fixed_size = sizes.iter().sum::<usize>() as u64;
+            if opt_size < fixed_size {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid optional header size",
+                ));
+            }
+            pe.optional = reader.numbers(opt_offset, &sizes)?;
+            let count = pe.optional[Pe::OPT_DIRECTORIES]
+                .min(Pe::MAX_DIRECTORIES)
+                .min((opt_size - fixed_size) / 8);
+            for index in 0..count {
+                let val = reader.numbers(opt_offset + fixed_size + index * 8, &[4, 4])?;
+                pe.directories.push((val[0], val[1]));
+            }
+        }
+
+        // section table
+        let table = opt_offset + opt_size;
+        for index in 0..pe.coff[1] {
+            let header = table + index * Pe::SECTION_SIZE;
+            let name = string(&reader.bytes(header, 8)?, 0);
+            let val = reader.numbers(header + 8, &[4, 4, 4, 4, 4, 4, 2, 2, 4])?;
+            #[allow(clippy::cast_possible_truncation)]
+            pe.sections.push(Section {
+                header,
+                name,
+                virtual_size: val[0],
+                address: val[1],
+                size: val[2],
+                offset: val[3],
+                flags: val[8] as u32,
+            });
+        }
+
+        Ok(pe)
+    }
+
+    /// Convert relative virtual address to the file offset.
+    ///
+    /// # Arguments
+    ///
+    /// * `rva` - relative virtual address
+    ///
+    /// # Return value
+    ///
+    /// File offset, `None` if the address is not mapped to the file.
+    pub fn rva_to_offset(&self, rva: u64) -> Option<u64> {
+        if rva < self.headers_size() {
+            return Some(rva);
+        }
+        self.sections.iter().find_map(|section| {
+            let delta = rva.checked_sub(section.address)?;
+            let size = if section.virtual_size == 0 {
+                section.size
+            } else {
+                section.size.min(section.virtual_size)
+            };
+            if delta < size {
+                section.offset.checked_add(delta)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Convert file offset to the relative virtual address.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - file offset
+    ///
+    /// # Return value
+    ///
+    /// Relative virtual address, `None` if the offset is not mapped to memory.
+    pub fn offset_to_rva(&self, offset: u64) -> Option<u64> {
+        if offset < self.headers_size() {
+            return Some(offset);
+        }
+        self.sections.iter().find_map(|section| {
+            let delta = offset.checked_sub(section.offset)?;
+            if delta < section.size {
+                section.address.checked_add(delta)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Get named regions of the file: headers and sections data.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    ///
+    /// # Return value
+    ///
+    /// List of regions sorted by start offset.
+    pub fn regions(&self, size: u64) -> Vec<(Range<u64>, String)> {
+        let mut regions = vec![
+            (0..Pe::MZ_SIZE, "MZ header".to_string()),
+            (Pe::MZ_SIZE..self.header, "DOS stub".to_string()),
+            (self.header..self.table().start, "PE header".to_string()),
+            (self.table(), "Section table".to_string()),
+        ];
+        for section in &self.sections {
+            if let Some(range) = section.range() {
+                regions.push((range, section.name.clone()));
+            }
+        }
+        if let Some(&(offset, len)) = self.directories.get(Pe::DIR_CERTIFICATE) {
+            if offset != 0 && len != 0 {
+                let range = offset..offset.saturating_add(len);
+                regions.push((range, Pe::DIRECTORIES[Pe::DIR_CERTIFICATE].to_string()));
+            }
+        }
+
+        let mut regions: Vec<(Range<u64>, String)> = regions
+            .into_iter()
+            .filter(|(range, _)| range.start < size && !range.is_empty())
+            .map(|(range, name)| (range.start..range.end.min(size), name))
+            .collect();
+        regions.sort_by_key(|(range, _)| range.start);
+        regions
+    }
+
+    /// Build the structure tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - size of the file
+    /// * `read` - data reader: offset and size of the block
+    ///
+    /// # Return value
+    ///
+    /// List of nodes in the tree order (parent before its children).
+    pub fn tree(
+        &self,
+        size: u64,
+        read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>,
+    ) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        let mut push = |depth: usize, text: String, range: Range<u64>| {
+            nodes.push(Node { depth, text, range });
+        };
+
+        // MZ header
+        push(0, "MZ header".to_string(), 0..Pe::MZ_SIZE);
+        push(1, "Signature: MZ".to_string(), 0..2);
+        push(
+            1,
+            format!("PE header: 0x{:x}", self.header),
+            Pe::MZ_LFANEW..Pe::MZ_LFANEW + 4,
+        );
+
+        // COFF header
+        let mut offset = self.header + Pe::PE_SIGNATURE_SIZE;
+        push(0, "PE header".to_string(), self.header..self.table().start);
+        push(
+            1,
+            "Signature: PE".to_string(),
+            self.header..self.header + Pe::PE_SIGNATURE_SIZE,
+        );
+        for (&(field, size), &value) in Pe::COFF.iter().zip(self.coff.iter()) {
+            let size = size as u64;
+            #[allow(clippy::cast_possible_truncation)]
+            let value = match field {
+                "Machine" => name(Pe::MACHINES, value as u32),
+                _ => Pe::format(field, value),
+            };
+            push(1, format!("{}: {}", field, value), offset..offset + size);
+            offset += size;
+        }
+
+        // optional header
+        if !self.optional.is_empty() {
+            let sizes = self.optional_sizes();
+            let end = offset + sizes.iter().sum::<usize>() as u64;
+            push(0, "Optional header".to_string(), offset..end);
+            for ((&(field, _, _), &size), &value) in Pe::OPTIONAL
+                .iter()
+                .zip(sizes.iter())
+                .zip(self.optional.iter())
+            {
+                if size == 0 {
+                    continue;
+                }
+                let size = size as u64;
+                #[allow(clippy::cast_possible_truncation)]
+                let value = match field {
+                    "Magic" => name(Pe::MAGICS, value as u32),
+                    "Subsystem" => name(Pe::SUBSYSTEMS, value as u32),
+                    _ => Pe::format(field, value),
+                };
+                push(1, format!("{}: {}", field, value), offset..offset + size);
+                offset += size;
+            }
+
+            // data directories
+            let count = self.directories.len() as u64;
+            push(
+                0,
+                format!("Data directories ({})", count),
+                offset..offset + count * 8,
+            );
+            for (index, &(address, len)) in self.directories.iter().enumerate() {
+                let entry = offset + index as u64 * 8;
+                let start = if index == Pe::DIR_CERTIFICATE {
+                    Some(address).filter(|&offset| offset != 0 && offset < size)
+                } else {
+                    self.rva_to_offset(address).filter(|_| address != 0)
+                };
+                let range = start.map_or(entry..entry + 8, |start| {
+                    start..start.saturating_add(len.max(1))
+                });
+                let text = format!(
+                    "[{}] {}: {} 0x{:x}, size 0x{:x}",
+                    index,
+                    Pe::DIRECTORIES.get(index).unwrap_or(&"Reserved"),
+                    if index == Pe::DIR_CERTIFICATE {
+                        "offset"
+                    } else {
+                        "rva"
+                    },
+                    address,
+                    len
+                );
+                push(1, text, range);
+            }
+        }
+
+        // section table
+        let table = self.table();
+        push(
+            0,
+            format!("Sections ({})", self.sections.len()),
+            table.clone(),
+        );
+        for (index, section) in self.sections.iter().enumerate() {
+            let flags: String = [(0x4000_0000, 'R'), (0x8000_0000, 'W'), (0x2000_0000, 'X')]
+                .iter()
+                .map(|&(bit, chr)| if section.flags & bit != 0 { chr } else { '-' })
+                .collect();
+            let text = format!(
+                "[{}] {} {} size 0x{:x}, rva 0x{:x}",
+                index,
+                if section.name.is_empty() {
+                    "-"
+                } else {
+                    &section.name
+                },
+                flags,
+                section.size,
+                section.address
+            );
+            let range = section
+                .range()
+                .unwrap_or(section.header..section.header + Pe::SECTION_SIZE);
+            push(1, text, range);
+        }
+
+        // import and export tables
+        let mut reader = Reader::new(read, size, true);
+        self.exports(&mut reader, &mut nodes);
+        self.imports(&mut reader, &mut nodes);
+
+        nodes
+    }
+
+    /// Add exported functions to the structure tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - file reader
+    /// * `nodes` - structure tree
+    ///
+    /// # Return value
+    ///
+    /// `None` if the table is broken.
+    fn exports(&self, reader: &mut Reader, nodes: &mut Vec<Node>) -> Option<()> {
+        let &(rva, len) = self.directories.get(Pe::DIR_EXPORT)?;
+        if rva == 0 {
+            return None;
+        }
+        let offset = self.rva_to_offset(rva)?;
+        // flags, time, major, minor, name, base, functions, names,
+        // address of functions, address of names, address of ordinals
+        let dir = reader
+            .numbers(offset, &[4, 4, 2, 2, 4, 4, 4, 4, 4, 4, 4])
+            .ok()?;
+        let name = self.name(reader, dir[4]).unwrap_or_default();
+        nodes.push(Node {
+            depth: 0,
+            text: format!("Exports ({})", name),
+            range: offset..offset.saturating_add(len.max(1)),
+        });
+
+        #[allow(clippy::cast_possible_truncation)]
+        let count = (dir[7] as usize).min(Pe::MAX_ENTRIES);
+        let names = self.rva_to_offset(dir[9])?;
+        let ordinals = self.rva_to_offset(dir[10])?;
+        let functions = self.rva_to_offset(dir[8])?;
+        for index in 0..count as u64 {
+            let name_rva = reader.numbers(names + index * 4, &[4]).ok()?[0];
+            let ordinal = reader.numbers(ordinals + index * 2, &[2]).ok()?[0];
+            let function = if ordinal < dir[6] {
+                reader.numbers(functions + ordinal * 4, &[4]).ok()?[0]
+            } else {
+                0
+            };
+            let name = self.name(reader, name_rva).unwrap_or_default();
+            let range = self.rva_to_offset(function).map_or_else(
+                || names + index * 4..names + index * 4 + 4,
+                |offset| offset..offset + 1,
+            );
+            nodes.push(Node {
+                depth: 1,
+                text: format!("{} ordinal {} rva 0x{:x}", name, dir[5] + ordinal, function),
+                range,
+            });
+        }
+        Some(())
+    }
+
+    /// Add imported functions to the structure tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - file reader
+    /// * `nodes` - structure tree
+    ///
+    /// # Return value
+    ///
+    /// `None` if the table is broken.
+    fn imports(&self, reader: &mut Reader, nodes: &mut Vec<Node>) -> Option<()> {
+        let &(rva, len) = self.directories.get(Pe::DIR_IMPORT)?;
+        if rva == 0 {
+            return None;
+        }
+        let offset = self.rva_to_offset(rva)?;
+        nodes.push(Node {
+            depth: 0,
+            text: "Imports".to_string(),
+            range: offset..offset.saturating_add(len.max(1)),
+        });
+
+        let (thunk_size, ordinal_flag) = if self.is64 {
+            (8, 1 << 63)
+        } else {
+            (4, 1 << 31)
+        };
+        let mut total = 0;
+        for index in 0.. {
+            // original first thunk, time, forwarder, name, first thunk
+            let descriptor = offset + index * 20;
+            let val = reader.numbers(descriptor, &[4, 4, 4, 4, 4]).ok()?;
+            if val.iter().all(|&v| v == 0) {
+                break;
+            }
+            nodes.push(Node {
+                depth: 1,
+                text: self.name(reader, val[3]).unwrap_or_default(),
+                range: descriptor..descriptor + 20,
+            });
+            let lookup = self.rva_to_offset(if val[0] != 0 { val[0] } else { val[4] })?;
+            for thunk in 0.. {
+                total += 1;
+                if total > Pe::MAX_ENTRIES {
+                    return Some(());
+                }
+                let entry = lookup + thunk * thunk_size;
+                let value = reader.numbers(entry, &[thunk_size as usize]).ok()?[0];
+                if value == 0 {
+                    break;
+                }
+                let text = if value & ordinal_flag != 0 {
+                    format!("ordinal {}", value & 0xffff)
+                } else {
+                    // hint and name
+                    self.name(reader, value + 2).unwrap_or_default()
+                };
+                // point to the import address table entry
+                let iat = self
+                    .rva_to_offset(val[4] + thunk * thunk_size)
+                    .unwrap_or(entry);
+                nodes.push(Node {
+                    depth: 2,
+                    text,
+                    range: iat..iat + thunk_size,
+                });
+            }
+        }
+        Some(())
+    }
+
+    /// Read null-terminated name at specified RVA.
+    fn name(&self, reader: &mut Reader, rva: u64) -> Option<String> {
+        let offset = self.rva_to_offset(rva)?;
+        reader.string(offset, Pe::MAX_NAME).ok()
+    }
+
+    /// Get sizes of the optional header fields.
+    fn optional_sizes(&self) -> Vec<usize> {
+        Pe::OPTIONAL
+            .iter()
+            .map(|&(_, size32, size64)| if self.is64 { size64 } else { size32 })
+            .collect()
+    }
+
+    /// Get file range of the section table.
+    fn table(&self) -> Range<u64> {
+        let start = self.header + Pe::PE_SIGNATURE_SIZE + 20 + self.coff[5];
+        start..start + self.sections.len() as u64 * Pe::SECTION_SIZE
+    }
+
+    /// Get size of the headers mapped to memory as is.
+    fn headers_size(&self) -> u64 {
+        self.optional
+            .get(Pe::OPT_HEADERS_SIZE)
+            .copied()
+            .unwrap_or_else(|| self.table().end)
+    }
+
+    /// Format value of the header field: counters and versions are decimal.
+    fn format(field: &str, value: u64) -> String {
+        if field.starts_with("Number") || field.ends_with("version") {
+            value.to_string()
+        } else {
+            format!("0x{:x}", value)
+        }
+    }
+
+    /// Names of the data directories.
+    const DIRECTORIES: &'static [&'static str] = &[
+        "Export",
+        "Import",
+        "Resource",
+        "Exception",
+        "Certificate",
+        "Base relocation",
+        "Debug",
+        "Architecture",
+        "Global pointer",
+        "TLS",
+        "Load config",
+        "Bound import",
+        "IAT",
+        "Delay import",
+        "CLR runtime",
+        "Reserved",
+    ];
+
+    /// Target architectures.
+    const MACHINES: &'static [(u32, &'static str)] = &[
+        (0, "Unknown"),
+        (0x14c, "x86"),
+        (0x1c0, "ARM"),
+        (0x1c4, "ARM Thumb-2"),
+        (0x200, "IA-64"),
+        (0xebc, "EFI byte code"),
+        (0x5032, "RISC-V32"),
+        (0x5064, "RISC-V64"),
+        (0x6264, "LoongArch64"),
+        (0x8664, "x86-64"),
+        (0xaa64, "ARM64"),
+    ];
+
+    /// Optional header magic values.
+    const MAGICS: &'static [(u32, &'static str)] =
+        &[(0x107, "ROM"), (0x10b, "PE32"), (0x20b, "PE32+")];
+
+    /// Subsystems.
+    const SUBSYSTEMS: &'static [(u32, &'static str)] = &[
+        (0, "Unknown"),
+        (1, "Native"),
+        (2, "Windows GUI"),
+        (3, "Windows console"),
+        (5, "OS/2 console"),
+        (7, "POSIX console"),
+        (9, "Windows CE"),
+        (10, "EFI application"),
+        (11, "EFI boot driver"),
+        (12, "EFI runtime driver"),
+        (13, "EFI ROM"),
+        (14, "Xbox"),
+        (16, "Boot application"),
+    ];
+}
+
+impl Section {
+    /// Get file range of the section data.
+    ///
+    /// # Return value
+    ///
+    /// Range of the section data, `None` if section has no data in the file.
+    pub fn range(&self) -> Option<Range<u64>> {
+        if self.offset == 0 || self.size == 0 {
+            return None;
+        }
+        Some(self.offset..self.offset.checked_add(self.size)?)
+    }
+}
+
+#[test]
+fn test_pe() {
+    // build a minimal PE32+ image: headers, .text and .idata sections
+    fn put(data: &mut Vec<u8>, value: u64, size: usize) {
+        data.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+    let mut data = b"MZ".to_vec();
+    data.resize(0x3c, 0);
+    put(&mut data, 0x40, 4); // e_lfanew
+    data.extend_from_slice(b"PE\0\0");
+    put(&mut data, 0x8664, 2); // machine
+    put(&mut data, 2, 2); // number of sections
+    put(&mut data, 0, 8); // time stamp, symbol table
+    put(&mut data, 0, 4); // number of symbols
+    put(&mut data, 112 + 16 * 8, 2); // optional header size
+    put(&mut data, 0x22, 2); // characteristics
+    let mut optional = Vec::new();
+    for (index, &(_, _, size)) in Pe::OPTIONAL.iter().enumerate() {
+        let value = match index {
+            0 => 0x20b,
+            6 => 0x1010, // entry point
+            Pe::OPT_HEADERS_SIZE => 0x200,
+            22 => 3, // subsystem
+            Pe::OPT_DIRECTORIES => 16,
+            _ => 0,
+        };
+        put(&mut optional, value, size);
+    }
+    data.extend_from_slice(&optional);
+    for index in 0..16 {
+        let (rva, size) = if index == Pe::DIR_IMPORT {
+            (0x2000, 40)
+        } else {
+            (0, 0)
+        };
+        put(&mut data, rva, 4);
+        put(&mut data, size, 4);
+    }
+    let sections = [
+        (b".text\0\0\0", 0x1000, 0x200, 0x6000_0020),
+        (b".idata\0\0", 0x2000, 0x400, 0xc000_0040),
+    ];
+    for &(name, rva, offset, flags) in &sections {
+        data.extend_from_slice(name);
+        put(&mut data, 0x100, 4); // virtual size
+        put(&mut data, rva, 4);
+        put(&mut data, 0x100, 4); // raw size
+        put(&mut data, offset, 4);
+        put(&mut data, 0, 8); // relocations, line numbers
+        put(&mut data, 0, 4); // number of relocations and line numbers
+        put(&mut data, flags, 4);
+    }
+    data.resize(0x400, 0xcc);
+    // import descriptor: lookup table at 0x2040, name at 0x2060, IAT at 0x2050
+    put(&mut data, 0x2040, 4);
+    put(&mut data, 0, 8);
+    put(&mut data, 0x2060, 4);
+    put(&mut data, 0x2050, 4);
+    data.resize(0x440, 0);
+    put(&mut data, 0x2070, 8); // by name
+    put(&mut data, (1 << 63) | 7, 8); // by ordinal
+    data.resize(0x460, 0);
+    data.extend_from_slice(b"KERNEL32.dll\0");
+    data.resize(0x470, 0);
+    put(&mut data, 0, 2); // hint
+    data.extend_from_slice(b"ExitProcess\0");
+    data.resize(0x500, 0);
+
+    let size = data.len() as u64;
+    let mut read = |offset: u64, size: usize| -> Result<Vec<u8>> {
+        Ok(data[offset as usize..offset as usize + size].to_vec())
+    };
+    let pe = Pe::parse(size, &mut read).unwrap();
+    assert!(pe.is64);
+    assert_eq!(pe.sections.len(), 2);
+    assert_eq!(pe.sections[1].name, ".idata");
+
+    assert_eq!(pe.rva_to_offset(0x10), Some(0x10));
+    assert_eq!(pe.rva_to_offset(0x1010), Some(0x210));
+    assert_eq!(pe.rva_to_offset(0x1100), None);
+    assert_eq!(pe.offset_to_rva(0x450), Some(0x2050));
+    assert_eq!(pe.offset_to_rva(0x300), None);
+
+    let regions = pe.regions(size);
+    let names: Vec<&str> = regions.iter().map(|(_, name)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["MZ header", "PE header", "Section table", ".text", ".idata"]
+    );
+    assert_eq!(regions[3].0, 0x200..0x300);
+
+    let tree = pe.tree(size, &mut read);
+    let lines: Vec<String> = tree.iter().map(Node::line).collect();
+    assert!(lines.contains(&"00000044    Machine: x86-64".to_string()));
+    assert!(lines.contains(&"00000068    Entry point: 0x1010".to_string()));
+    assert!(lines.contains(&"0000009c    Subsystem: Windows console".to_string()));
+    let imports: Vec<&str> = tree
+        .iter()
+        .skip_while(|node| node.text != "Imports")
+        .map(|node| node.text.as_str())
+        .collect();
+    assert_eq!(
+        imports,
+        ["Imports", "KERNEL32.dll", "ExitProcess", "ordinal 7"]
+    );
+    assert_eq!(tree.last().unwrap().range, 0x458..0x460);
+
+    // DOS executable
+    let mut read = |_: u64, size: usize| -> Result<Vec<u8>> {
+        let mut data = vec![0; size];
+        data[0..2].copy_from_slice(b"MZ");
+        Ok(data)
+    };
+    assert_eq!(
+        Pe::parse(0x100, &mut read).err().unwrap().to_string(),
+        "Not a PE file"
+    );
+}
+
+#[test]
+fn test_pe_malformed() {
+    fn put(data: &mut Vec<u8>, value: u64, size: usize) {
+        data.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+    fn set(data: &mut [u8], offset: usize, value: u64, size: usize) {
+        data[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+    fn parse(data: &[u8]) -> Result<(Pe, Vec<Node>)> {
+        let mut read = |offset: u64, size: usize| -> Result<Vec<u8>> {
+            Ok(data[offset as usize..offset as usize + size].to_vec())
+        };
+        let size = data.len() as u64;
+        let pe = Pe::parse(size, &mut read)?;
+        pe.regions(size);
+        let tree = pe.tree(size, &mut read);
+        Ok((pe, tree))
+    }
+    // PE32+ image with a single section (rva 0x1000, offset 0x200, size
+    // 0x200), import directory entry at 0xd0, section header at 0x148
+    let mut image = b"MZ".to_vec();
+    image.resize(0x3c, 0);
+    put(&mut image, 0x40, 4); // e_lfanew
+    image.extend_from_slice(b"PE\0\0");
+    put(&mut image, 0x8664, 2); // machine
+    put(&mut image, 1, 2); // number of sections
+    put(&mut image, 0, 8); // time stamp, symbol table
+    put(&mut image, 0, 4); // number of symbols
+    put(&mut image, 112 + 16 * 8, 2); // optional header size
+    put(&mut image, 0x22, 2); // characteristics
+    for (index, &(_, _, size)) in Pe::OPTIONAL.iter().enumerate() {
+        let value = match index {
+            0 => 0x20b,
+            Pe::OPT_HEADERS_SIZE => 0x200,
+            Pe::OPT_DIRECTORIES => 16,
+            _ => 0,
+        };
+        put(&mut image, value, size);
+    }
+    image.resize(0x148, 0);
+    image.extend_from_slice(b".idata\0\0");
+    put(&mut image, 0x200, 4); // virtual size
+    put(&mut image, 0x1000, 4); // rva
+    put(&mut image, 0x200, 4); // raw size
+    put(&mut image, 0x200, 4); // raw offset
+    put(&mut image, 0, 8); // relocations, line numbers
+    put(&mut image, 0, 4); // number of relocations and line numbers
+    put(&mut image, 0xc000_0040, 4); // flags
+    image.resize(0x400, 0);
+    assert_eq!(parse(&image).unwrap().0.sections.len(), 1);
+
+    // broken headers
+    let mut data = image.clone();
+    set(&mut data, 0x3c, 0xffff_fff0, 4);
+    assert_eq!(parse(&data).err().unwrap().to_string(), "Not a PE file");
+    assert!(parse(&image[..0x50]).is_err());
+    let mut data = image.clone();
+    set(&mut data, 0x46, 0xffff, 2);
+    assert!(parse(&data).is_err());
+    let mut data = image.clone();
+    set(&mut data, 0x54, 8, 2);
+    assert_eq!(
+        parse(&data).err().unwrap().to_string(),
+        "Invalid optional header size"
+    );
+
+    // import directory outside of the sections
+    let mut data = image.clone();
+    set(&mut data, 0xd0, 0x9000, 4);
+    let (pe, tree) = parse(&data).unwrap();
+    assert_eq!(pe.rva_to_offset(0x9000), None);
+    assert!(tree.iter().all(|node| node.text != "Imports"));
+
+    // section data outside of the file
+    let mut data = image.clone();
+    set(&mut data, 0x15c, 0xffff_ff00, 4);
+    set(&mut data, 0xd0, 0x1000, 4);
+    let (pe, tree) = parse(&data).unwrap();
+    assert_eq!(pe.rva_to_offset(0x1000), Some(0xffff_ff00));
+    assert!(pe.regions(0x400).iter().all(|(_, name)| name != ".idata"));
+    assert_eq!(tree.last().unwrap().text, "Imports");
+
+    // import lookup table outside of the file
+    let mut data = image.clone();
+    set(&mut data, 0xd0, 0x1000, 4);
+    set(&mut data, 0x200, 0x7fff_0000, 4); // lookup table
+    set(&mut data, 0x20c, 0x1100, 4); // name
+    set(&mut data, 0x210, 0x7fff_0000, 4); // address table
+    data[0x300..0x306].copy_from_slice(b"X.dll\0");
+    let (_, tree) = parse(&data).unwrap();
+    let imports: Vec<&str> = tree
+        .iter()
+        .skip_while(|node| node.text != "Imports")
+        .map(|node| node.text.as_str())
+        .collect();
+    assert_eq!(imports, ["Imports", "X.dll"]);
+
+    // export tables with huge number of entries
+    let mut data = image.clone();
+    set(&mut data, 0xc8, 0x1000, 4);
+    set(&mut data, 0x210, 1, 4); // base
+    set(&mut data, 0x214, 0xffff_ffff, 4); // functions
+    set(&mut data, 0x218, 0xffff_ffff, 4); // names
+    set(&mut data, 0x21c, 0x1100, 4); // address of functions
+    set(&mut data, 0x220, 0x1100, 4); // address of names
+    set(&mut data, 0x224, 0x1100, 4); // address of ordinals
+    let (_, tree) = parse(&data).unwrap();
+    let exports = tree
+        .iter()
+        .skip_while(|node| !node.text.starts_with("Exports"))
+        .count();
+    assert!(exports > 1 && exports <= 0x100 / 4 + 1);
+}
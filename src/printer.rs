@@ -0,0 +1,301 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::ascii::Table;
+use super::config::Config;
+use super::curses::Color;
+use super::file::File;
+use super::view::{Layout, View};
+use std::collections::BTreeSet;
+use std::io::{Result, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// Non-interactive viewer: prints files with the same layout as the
+/// document view uses.
+pub struct Printer {
+    /// Files to print.
+    files: Vec<File>,
+    /// ASCII characters table (None hides the field).
+    ascii_table: Option<&'static Table>,
+    /// Color scheme, `None` for plain text.
+    colors: Option<Vec<(Color, i16, i16)>>,
+    /// Layout of the fields.
+    layout: Layout,
+}
+
+impl Printer {
+    /// Create new printer instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - files to print
+    /// * `config` - app configuration
+    /// * `width` - width of the output (terminal)
+    /// * `colors` - use ANSI escape sequences to colorize the output
+    ///
+    /// # Return value
+    ///
+    /// Printer instance.
+    pub fn new(files: &[String], config: &Config, width: usize, colors: bool) -> Result<Self> {
+        debug_assert!(!files.is_empty());
+
+        let mut opened = Vec::with_capacity(files.len());
+        for file in files {
+            opened.push(File::open(Path::new(file))?);
+        }
+
+        let max_offset = opened.iter().map(|f| f.size).max().unwrap_or(0);
+        let layout = View::layout(
+            width.max(View::MIN_WIDTH),
+            config.fixed_width,
+            config.ascii_table.is_some(),
+            max_offset,
+        );
+
+        Ok(Self {
+            files: opened,
+            ascii_table: config.ascii_table,
+            colors: if colors {
+                Some(config.colors.clone())
+            } else {
+                None
+            },
+            layout,
+        })
+    }
+
+    /// Print the range of all files, each file is preceded with its name
+    /// if there are several of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - range to print
+    /// * `out` - output stream
+    pub fn print(&mut self, range: &Range<u64>, out: &mut dyn Write) -> Result<()> {
+        let columns = self.layout.columns as u64;
+        for index in 0..self.files.len() {
+            if self.files.len() > 1 {
+                let mut header = String::new();
+                let path = self.files[index].path.clone();
+                let mut color = None;
+                self.paint(&mut header, &mut color, Color::Bar, &path);
+                if color.is_some() {
+                    header.push_str("\x1b[0m");
+                }
+                writeln!(out, "{}", header)?;
+            }
+            let end = range.end.min(self.files[index].size);
+            if range.start >= end {
+                continue;
+            }
+            let mut offset = range.start - range.start % columns;
+            while offset < end {
+                let line = self.line(index, offset, &(range.start..end))?;
+                writeln!(out, "{}", line)?;
+                offset += columns;
+            }
+        }
+        Ok(())
+    }
+
+    /// Compose a single line: offset, hex and ascii fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the file
+    /// * `offset` - start address of the line
+    /// * `range` - range to print, bytes outside of it are not displayed
+    ///
+    /// # Return value
+    ///
+    /// Text line.
+    fn line(&mut self, index: usize, offset: u64, range: &Range<u64>) -> Result<String> {
+        let columns = self.layout.columns;
+        let start = offset.max(range.start);
+        let end = (offset + columns as u64).min(range.end);
+        #[allow(clippy::cast_possible_truncation)]
+        let data = self.files[index].read(start, (end - start) as usize)?;
+        let gaps: BTreeSet<u64> = self.files[index]
+            .gaps(&(start..end))
+            .into_iter()
+            .flatten()
+            .collect();
+        let differs = self.differs(index, start, &data)?;
+
+        let byte = |x: usize| -> Option<u8> {
+            let address = offset + x as u64;
+            if address < start || address >= end || gaps.contains(&address) {
+                None
+            } else {
+                #[allow(clippy::cast_possible_truncation)]
+                Some(data[(address - start) as usize])
+            }
+        };
+
+        let mut line = String::new();
+        let mut color = None;
+
+        // offset field
+        let text = format!("{:0width$x}", offset, width = self.layout.offset_width);
+        self.paint(&mut line, &mut color, Color::Offset, &text);
+        let margin = " ".repeat(View::FIELD_MARGIN);
+        self.paint(&mut line, &mut color, Color::HexNorm, &margin);
+
+        // hex field
+        for x in 0..columns {
+            if x != 0 {
+                let delimiter = if x % View::BYTES_IN_WORD == 0 {
+                    "  "
+                } else {
+                    " "
+                };
+                self.paint(&mut line, &mut color, Color::HexNorm, delimiter);
+            }
+            let address = offset + x as u64;
+            let text = match byte(x) {
+                Some(value) => format!("{:02x}", value),
+                None if gaps.contains(&address) => "--".to_string(),
+                None => "  ".to_string(),
+            };
+            let hex_color = if differs.contains(&address) {
+                Color::HexDiff
+            } else {
+                Color::HexNorm
+            };
+            self.paint(&mut line, &mut color, hex_color, &text);
+        }
+
+        // ascii field
+        if let Some(table) = self.ascii_table {
+            self.paint(&mut line, &mut color, Color::HexNorm, &margin);
+            for x in 0..columns {
+                let chr = byte(x).map_or(' ', |value| table.charset[value as usize]);
+                let ascii_color = if differs.contains(&(offset + x as u64)) {
+                    Color::AsciiDiff
+                } else {
+                    Color::AsciiNorm
+                };
+                self.paint(&mut line, &mut color, ascii_color, &chr.to_string());
+            }
+        }
+
+        if color.is_some() {
+            line.push_str("\x1b[0m");
+        } else {
+            line.truncate(line.trim_end().len());
+        }
+        Ok(line)
+    }
+
+    /// Get addresses of bytes that differ from the other files.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the file
+    /// * `offset` - start address
+    /// * `data` - data of the file
+    ///
+    /// # Return value
+    ///
+    /// Set of diff addresses.
+    fn differs(&mut self, index: usize, offset: u64, data: &[u8]) -> Result<BTreeSet<u64>> {
+        let mut diff = BTreeSet::new();
+        for (_, file) in self
+            .files
+            .iter_mut()
+            .enumerate()
+            .filter(|(i, _)| *i != index)
+        {
+            let data_r = if offset >= file.size {
+                vec![]
+            } else {
+                file.read(offset, data.len())?
+            };
+            for (i, byte_l) in data.iter().enumerate() {
+                if data_r.get(i) != Some(byte_l) {
+                    diff.insert(offset + i as u64);
+                }
+            }
+        }
+        Ok(diff)
+    }
+
+    /// Append text to the line, switch color if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - line to append text
+    /// * `current` - currently used color
+    /// * `color` - color of the text
+    /// * `text` - text to append
+    fn paint(&self, line: &mut String, current: &mut Option<Color>, color: Color, text: &str) {
+        if self.colors.is_some() && *current != Some(color) {
+            line.push_str(&self.escape(color));
+            *current = Some(color);
+        }
+        line.push_str(text);
+    }
+
+    /// Get ANSI escape sequence to set the color.
+    ///
+    /// # Arguments
+    ///
+    /// * `color` - color identifier
+    fn escape(&self, color: Color) -> String {
+        let (fg, bg) = self
+            .colors
+            .iter()
+            .flatten()
+            .find(|(c, _, _)| *c == color)
+            .map_or((-1, -1), |&(_, fg, bg)| (fg, bg));
+        let fg = if fg < 0 {
+            "39".to_string()
+        } else {
+            format!("38;5;{}", fg)
+        };
+        let bg = if bg < 0 {
+            "49".to_string()
+        } else {
+            format!("48;5;{}", bg)
+        };
+        format!("\x1b[0;{};{}m", fg, bg)
+    }
+}
+
+#[test]
+fn test_print() {
+    let path_l = std::env::temp_dir().join("xvi_test_printer.l");
+    let path_r = std::env::temp_dir().join("xvi_test_printer.r");
+    std::fs::write(&path_l, b"0123456789abcdefXYZ").unwrap();
+    std::fs::write(&path_r, b"0123456789Abcdef").unwrap();
+    let path_l = path_l.to_str().unwrap().to_string();
+    let path_r = path_r.to_str().unwrap().to_string();
+
+    let config = Config::default();
+    let mut printer = Printer::new(std::slice::from_ref(&path_l), &config, 80, false).unwrap();
+    let mut out = Vec::new();
+    printer.print(&(2..18), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("00000000         32 33  34 35 36 37  38 39 61 62"));
+    assert!(lines[0].ends_with("  23456789abcdef"));
+    assert!(lines[1].starts_with("00000010   58 59      "));
+
+    let files = [path_l.clone(), path_r.clone()];
+    let mut printer = Printer::new(&files, &config, 80, true).unwrap();
+    let mut out = Vec::new();
+    printer.print(&(0..16), &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].contains(&path_l));
+    assert!(lines[1].ends_with("\x1b[0m"));
+    let diff = printer.escape(Color::HexDiff);
+    assert!(lines[1].contains(&format!("{}61", diff)));
+    assert!(lines[3].contains(&format!("{}41", diff)));
+
+    std::fs::remove_file(path_l).unwrap();
+    std::fs::remove_file(path_r).unwrap();
+}
@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::file::{File, ProgressHandler};
+use std::io::Result;
+use std::ops::Range;
+
+/// Text encoding of the found string.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Ascii,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// Get encoding name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Ascii => "ASCII",
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+        }
+    }
+}
+
+/// String options.
+#[derive(Clone, Copy)]
+pub struct Options {
+    /// Min number of characters in the string.
+    pub min_length: usize,
+    /// Search for UTF-8 strings (with non-ASCII characters).
+    pub utf8: bool,
+    /// Search for UTF-16 little endian strings.
+    pub utf16le: bool,
+    /// Search for UTF-16 big endian strings.
+    pub utf16be: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            min_length: 4,
+            utf8: true,
+            utf16le: true,
+            utf16be: false,
+        }
+    }
+}
+
+/// String found in the file.
+pub struct Text {
+    /// Offset of the string.
+    pub offset: u64,
+    /// Text encoding.
+    pub encoding: Encoding,
+    /// String value.
+    pub text: String,
+}
+
+impl Text {
+    /// Get text representation of the string.
+    pub fn line(&self) -> String {
+        format!(
+            "{:08x}  {:<8}  {}",
+            self.offset,
+            self.encoding.name(),
+            self.text.replace('\t', " ")
+        )
+    }
+}
+
+/// Max number of strings reported by the scanner.
+pub const MAX_STRINGS: usize = 50000;
+
+/// Extract printable strings from the range.
+///
+/// # Arguments
+///
+/// * `file` - file to scan
+/// * `range` - range to scan
+/// * `options` - string options
+/// * `progress` - long time operation handler
+///
+/// # Return value
+///
+/// List of strings sorted by offset.
+pub fn extract(
+    file: &mut File,
+    range: &Range<u64>,
+    options: Options,
+    progress: &mut dyn ProgressHandler,
+) -> Result<Vec<Text>> {
+    const BLOCK_SIZE: usize = 0x10000;
+    let mut scanner = Scanner::new(options);
+    file.read_blocks(range, BLOCK_SIZE, progress, &mut |offset, data| {
+        scanner.update(offset, data);
+        Ok(scanner.found.len() < MAX_STRINGS)
+    })?;
+    Ok(scanner.finish())
+}
+
+/// Check if the character is a printable part of the string.
+fn is_printable(ch: char) -> bool {
+    ch == '\t' || !ch.is_control()
+}
+
+/// Run of printable characters.
+#[derive(Default)]
+struct Run {
+    /// Offset of the first character.
+    start: u64,
+    /// Characters of the run.
+    text: String,
+    /// Number of characters.
+    count: usize,
+    /// Run contains non-ASCII characters.
+    unicode: bool,
+}
+
+impl Run {
+    /// Append character to the run.
+    fn push(&mut self, start: u64, ch: char) {
+        if self.count == 0 {
+            self.start = start;
+        }
+        self.text.push(ch);
+        self.count += 1;
+        self.unicode |= !ch.is_ascii();
+    }
+
+    /// Finish the run, move it to the list if it's long enough.
+    fn flush(&mut self, min: usize, encoding: Encoding, found: &mut Vec<Text>) {
+        if self.count >= min {
+            found.push(Text {
+                offset: self.start,
+                encoding: if encoding == Encoding::Ascii && self.unicode {
+                    Encoding::Utf8
+                } else {
+                    encoding
+                },
+                text: std::mem::take(&mut self.text),
+            });
+        }
+        *self = Run::default();
+    }
+}
+
+/// Incremental string scanner.
+struct Scanner {
+    /// String options.
+    options: Options,
+    /// Current ASCII/UTF-8 run.
+    ascii: Run,
+    /// Incomplete UTF-8 sequence: offset and bytes.
+    utf8: (u64, Vec<u8>),
+    /// Current UTF-16 runs: LE/BE for even/odd offsets.
+    utf16: [Run; 4],
+    /// Previous byte of the data, `None` at the start of the range.
+    prev: Option<u8>,
+    /// Found strings.
+    found: Vec<Text>,
+}
+
+impl Scanner {
+    /// Create new scanner.
+    fn new(options: Options) -> Self {
+        Self {
+            options,
+            ascii: Run::default(),
+            utf8: (0, Vec::new()),
+            utf16: Default::default(),
+            prev: None,
+            found: Vec::new(),
+        }
+    }
+
+    /// Process the next portion of data.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - offset of the data, must follow the previous portion
+    /// * `data` - data to process
+    fn update(&mut self, offset: u64, data: &[u8]) {
+        for (pos, &byte) in data.iter().enumerate() {
+            let offset = offset + pos as u64;
+            self.update_utf8(offset, byte);
+            if let Some(prev) = self.prev {
+                self.update_utf16(offset, prev, byte);
+            }
+            self.prev = Some(byte);
+        }
+    }
+
+    /// Get the list of found strings.
+    fn finish(mut self) -> Vec<Text> {
+        let min = self.options.min_length;
+        self.ascii.flush(min, Encoding::Ascii, &mut self.found);
+        for (index, run) in self.utf16.iter_mut().enumerate() {
+            let encoding = Scanner::utf16_encoding(index);
+            run.flush(min, encoding, &mut self.found);
+        }
+        self.found.sort_by_key(|text| text.offset);
+
+        // UTF-16 string is also seen in the other byte order shifted by one
+        // byte: keep the longest of the overlapping strings, the aligned one
+        // if they have the same length
+        let rank = |text: &Text| (text.text.chars().count(), text.offset.is_multiple_of(2));
+        let mut found: Vec<Text> = Vec::with_capacity(self.found.len());
+        let mut last_utf16: Option<usize> = None;
+        for text in self.found {
+            if matches!(text.encoding, Encoding::Utf16Le | Encoding::Utf16Be) {
+                if let Some(index) = last_utf16 {
+                    let last = &found[index];
+                    let end = last.offset + last.text.chars().count() as u64 * 2;
+                    if text.offset < end {
+                        if rank(&text) > rank(last) {
+                            found[index] = text;
+                        }
+                        continue;
+                    }
+                }
+                last_utf16 = Some(found.len());
+            }
+            found.push(text);
+        }
+
+        found.truncate(MAX_STRINGS);
+        found
+    }
+
+    /// Process the next byte as part of ASCII/UTF-8 string.
+    fn update_utf8(&mut self, offset: u64, byte: u8) {
+        let min = self.options.min_length;
+        let (start, seq) = &mut self.utf8;
+        if !seq.is_empty() {
+            if byte & 0xc0 == 0x80 {
+                seq.push(byte);
+                let need = match seq[0] {
+                    0xc2..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    _ => 4,
+                };
+                if seq.len() == need {
+                    let ch = std::str::from_utf8(seq)
+                        .ok()
+                        .and_then(|s| s.chars().next())
+                        .filter(|&ch| is_printable(ch));
+                    if let Some(ch) = ch {
+                        self.ascii.push(*start, ch);
+                    } else {
+                        self.ascii.flush(min, Encoding::Ascii, &mut self.found);
+                    }
+                    seq.clear();
+                }
+                return;
+            }
+            // broken sequence
+            seq.clear();
+            self.ascii.flush(min, Encoding::Ascii, &mut self.found);
+        }
+
+        let ch = char::from(byte);
+        if byte.is_ascii() && is_printable(ch) {
+            self.ascii.push(offset, ch);
+        } else if self.options.utf8 && (0xc2..=0xf4).contains(&byte) {
+            *start = offset;
+            seq.push(byte);
+        } else {
+            self.ascii.flush(min, Encoding::Ascii, &mut self.found);
+        }
+    }
+
+    /// Process the next byte as the second byte of UTF-16 code unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - offset of the byte
+    /// * `prev` - first byte of the code unit
+    /// * `byte` - second byte of the code unit
+    fn update_utf16(&mut self, offset: u64, prev: u8, byte: u8) {
+        let min = self.options.min_length;
+        let odd = (offset - 1) % 2 == 1;
+        for (le, enabled) in [(true, self.options.utf16le), (false, self.options.utf16be)] {
+            if !enabled {
+                continue;
+            }
+            let unit = if le {
+                u16::from_le_bytes([prev, byte])
+            } else {
+                u16::from_be_bytes([prev, byte])
+            };
+            let index = usize::from(!le) * 2 + usize::from(odd);
+            let run = &mut self.utf16[index];
+            // only Latin-1 is accepted, other code units are mostly noise
+            let ch = char::from_u32(u32::from(unit)).filter(|&ch| unit <= 0xff && is_printable(ch));
+            if let Some(ch) = ch {
+                run.push(offset - 1, ch);
+            } else {
+                run.flush(min, Scanner::utf16_encoding(index), &mut self.found);
+            }
+        }
+    }
+
+    /// Get encoding of the UTF-16 run by its index.
+    fn utf16_encoding(index: usize) -> Encoding {
+        if index < 2 {
+            Encoding::Utf16Le
+        } else {
+            Encoding::Utf16Be
+        }
+    }
+}
+
+#[test]
+fn test_strings() {
+    let scan = |data: &[u8], options: Options| {
+        let mut scanner = Scanner::new(options);
+        let (head, tail) = data.split_at(data.len() / 2);
+        scanner.update(0, head);
+        scanner.update(head.len() as u64, tail);
+        scanner
+            .finish()
+            .into_iter()
+            .map(|t| (t.offset, t.encoding, t.text))
+            .collect::<Vec<_>>()
+    };
+
+    let data = b"\0\x01hello\xffab\0\xd0\xbf\xd1\x80\xd0\xb8\xd0\xb2\xd0\xb5\xd1\x82\x02";
+    assert_eq!(
+        scan(data, Options::default()),
+        vec![
+            (2, Encoding::Ascii, "hello".to_string()),
+            (11, Encoding::Utf8, "привет".to_string()),
+        ]
+    );
+    let options = Options {
+        min_length: 2,
+        utf8: false,
+        utf16le: false,
+        utf16be: false,
+    };
+    assert_eq!(
+        scan(data, options),
+        vec![
+            (2, Encoding::Ascii, "hello".to_string()),
+            (8, Encoding::Ascii, "ab".to_string()),
+        ]
+    );
+
+    let data = b"\x01\x02t\0e\0s\0t\0\0\0\xff\0W\0i\0d\0e\0\0";
+    assert_eq!(
+        scan(data, Options::default()),
+        vec![
+            (2, Encoding::Utf16Le, "test".to_string()),
+            (12, Encoding::Utf16Le, "\u{ff}Wide".to_string()),
+        ]
+    );
+    // LE strings are not reported as BE ones shifted by one byte
+    let options = Options {
+        utf16le: true,
+        utf16be: true,
+        ..Options::default()
+    };
+    assert_eq!(
+        scan(data, options),
+        vec![
+            (2, Encoding::Utf16Le, "test".to_string()),
+            (12, Encoding::Utf16Le, "\u{ff}Wide".to_string()),
+        ]
+    );
+    let data = b"\0t\0e\0s\0t\0\0\x01\x02\0\xff\0W\0i\0d\0e\x03";
+    assert_eq!(
+        scan(data, options),
+        vec![
+            (0, Encoding::Utf16Be, "test".to_string()),
+            (12, Encoding::Utf16Be, "\u{ff}Wide".to_string()),
+        ]
+    );
+
+    // the first byte of the range is not a part of the previous code unit
+    let mut scanner = Scanner::new(options);
+    scanner.update(5, b"t\0e\0s\0t\0");
+    let found: Vec<(u64, Encoding)> = scanner
+        .finish()
+        .into_iter()
+        .map(|t| (t.offset, t.encoding))
+        .collect();
+    assert_eq!(found, vec![(5, Encoding::Utf16Le)]);
+}
+
+#[test]
+fn test_strings_malformed() {
+    let scan = |data: &[u8]| {
+        let mut scanner = Scanner::new(Options::default());
+        scanner.update(0, data);
+        scanner.finish()
+    };
+
+    // overlong, surrogate, out of range and truncated UTF-8 sequences
+    let data = b"abcd\xc0\xafefgh\xed\xa0\x80ijkl\xf4\x90\x80\x80mnop\xe2\x82";
+    let found: Vec<(u64, Encoding, String)> = scan(data)
+        .into_iter()
+        .map(|t| (t.offset, t.encoding, t.text))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (0, Encoding::Ascii, "abcd".to_string()),
+            (6, Encoding::Ascii, "efgh".to_string()),
+            (13, Encoding::Ascii, "ijkl".to_string()),
+            (21, Encoding::Ascii, "mnop".to_string()),
+        ]
+    );
+    assert!(scan(b"\x80\x80\x80\x80\xbf\xbf\xbf\xbf").is_empty());
+    assert!(scan(b"").is_empty());
+
+    // number of strings is limited
+    let data = b"text\0".repeat(MAX_STRINGS + 10);
+    assert_eq!(scan(&data).len(), MAX_STRINGS);
+}
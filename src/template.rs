@@ -0,0 +1,1146 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::config::Config;
+use super::file::File;
+use super::hex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Scalar data type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scalar {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    Char,
+}
+
+impl Scalar {
+    /// Names of the types.
+    const NAMES: &'static [(&'static str, Scalar)] = &[
+        ("u8", Scalar::U8),
+        ("u16", Scalar::U16),
+        ("u32", Scalar::U32),
+        ("u64", Scalar::U64),
+        ("i8", Scalar::I8),
+        ("i16", Scalar::I16),
+        ("i32", Scalar::I32),
+        ("i64", Scalar::I64),
+        ("f32", Scalar::F32),
+        ("f64", Scalar::F64),
+        ("char", Scalar::Char),
+    ];
+
+    /// Parse type name with optional byte order suffix (`le` or `be`).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - type name
+    /// * `little_endian` - default byte order
+    ///
+    /// # Return value
+    ///
+    /// Scalar type and byte order (`true` for little endian).
+    fn parse(name: &str, little_endian: bool) -> Option<(Self, bool)> {
+        let find = |name: &str| {
+            Scalar::NAMES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, s)| *s)
+        };
+        if let Some(scalar) = find(name) {
+            return Some((scalar, little_endian));
+        }
+        if let Some(base) = name.strip_suffix("le") {
+            find(base).map(|scalar| (scalar, true))
+        } else if let Some(base) = name.strip_suffix("be") {
+            find(base).map(|scalar| (scalar, false))
+        } else {
+            None
+        }
+    }
+
+    /// Get size of the value in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Scalar::U8 | Scalar::I8 | Scalar::Char => 1,
+            Scalar::U16 | Scalar::I16 => 2,
+            Scalar::U32 | Scalar::I32 | Scalar::F32 => 4,
+            Scalar::U64 | Scalar::I64 | Scalar::F64 => 8,
+        }
+    }
+
+    /// Check if the type is a signed integer.
+    fn is_signed(self) -> bool {
+        matches!(self, Scalar::I8 | Scalar::I16 | Scalar::I32 | Scalar::I64)
+    }
+
+    /// Check if the type is an integer.
+    fn is_integer(self) -> bool {
+        !matches!(self, Scalar::F32 | Scalar::F64 | Scalar::Char)
+    }
+
+    /// Convert raw data to the numeric value, signed integers are sign
+    /// extended, floats are truncated.
+    fn value(self, raw: u64) -> u64 {
+        let bits = self.size() as u32 * 8;
+        match self {
+            _ if self.is_signed() => {
+                let shift = 64 - bits;
+                ((raw << shift) as i64 >> shift) as u64
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            Scalar::F32 => f32::from_bits(raw as u32) as u64,
+            Scalar::F64 => f64::from_bits(raw) as u64,
+            _ => raw,
+        }
+    }
+}
+
+/// Type of the declared field.
+#[derive(Debug, PartialEq)]
+enum Type {
+    /// Scalar value with byte order (`true` for little endian).
+    Scalar(Scalar, bool),
+    /// Structure (name).
+    Struct(String),
+}
+
+/// Number of elements in the array.
+#[derive(Debug, PartialEq)]
+enum Count {
+    /// Fixed number.
+    Fixed(u64),
+    /// Value of the previously read field.
+    Field(String),
+    /// Length prefix of the specified type stored before the data.
+    Prefix(Scalar, bool),
+}
+
+/// Numeric operand: constant or value of the previously read field.
+#[derive(Debug, PartialEq)]
+enum Operand {
+    Number(u64),
+    Field(String),
+}
+
+/// Comparison operator.
+#[derive(Debug, PartialEq)]
+enum Compare {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    /// Bitwise test: result of AND is not zero.
+    BitAnd,
+}
+
+/// Field declaration inside the structure.
+#[derive(Debug, PartialEq)]
+struct Declaration {
+    /// Field name.
+    name: String,
+    /// Type name as it written in the template.
+    type_name: String,
+    /// Data type.
+    dtype: Type,
+    /// Number of elements for arrays.
+    count: Option<Count>,
+    /// Offset of the field relative to the template start.
+    at: Option<Operand>,
+    /// Condition of the field presence: field, operator and operand.
+    condition: Option<(String, Compare, Operand)>,
+    /// Line number in the template file.
+    line: usize,
+}
+
+/// Structure declaration.
+#[derive(Debug, PartialEq)]
+struct Struct {
+    name: String,
+    fields: Vec<Declaration>,
+}
+
+/// Structure template: declarative description of the binary data.
+///
+/// The template is a text file, each line contains a single statement:
+/// * `struct NAME` ... `end`: structure declaration, the first one is the
+///   root structure;
+/// * `endian little|big`: default byte order for the next fields;
+/// * `TYPE NAME [at OFFSET] [if FIELD OP VALUE]`: field declaration.
+///
+/// Type is a scalar (`u8`-`u64`, `i8`-`i64`, `f32`, `f64`, `char` with
+/// optional `le`/`be` suffix) or a structure name, arrays are declared as
+/// `TYPE[SIZE]`, where the size is a number, a name of the previously read
+/// field or a scalar type of the length prefix stored before the data.
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    /// Template name.
+    pub name: String,
+    /// Declared structures.
+    structs: Vec<Struct>,
+}
+
+impl Template {
+    /// Max number of fields produced by the template.
+    const MAX_FIELDS: usize = 100_000;
+    /// Max nesting level of structures.
+    const MAX_DEPTH: usize = 32;
+
+    /// Get path to the directory with user templates.
+    pub fn dir() -> PathBuf {
+        Config::dir().join("templates")
+    }
+
+    /// Get list of the user templates.
+    ///
+    /// # Return value
+    ///
+    /// Sorted list of template files.
+    pub fn list() -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(Template::dir())
+            .map(|dir| {
+                dir.filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+        files
+    }
+
+    /// Load template from the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path to the template file
+    ///
+    /// # Return value
+    ///
+    /// Template instance.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let name = path
+            .file_stem()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Template::parse(&name, &text)
+    }
+
+    /// Parse template.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - template name
+    /// * `text` - template description
+    ///
+    /// # Return value
+    ///
+    /// Template instance.
+    pub fn parse(name: &str, text: &str) -> Result<Self> {
+        let error = |line: usize, msg: String| {
+            Error::new(ErrorKind::InvalidData, format!("{} at line {}", msg, line))
+        };
+
+        let mut structs: Vec<Struct> = Vec::new();
+        let mut current: Option<Struct> = None;
+        let mut little_endian = true;
+
+        for (index, line) in text.lines().enumerate() {
+            let line_num = index + 1;
+            let tokens: Vec<&str> = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect();
+            match tokens.as_slice() {
+                [] => {}
+                ["endian", order] => {
+                    little_endian = match *order {
+                        "little" => true,
+                        "big" => false,
+                        _ => {
+                            return Err(error(line_num, format!("Invalid byte order '{}'", order)))
+                        }
+                    };
+                }
+                ["struct", name] => {
+                    if current.is_some() {
+                        return Err(error(line_num, "Nested structure".to_string()));
+                    }
+                    if !is_ident(name) || Scalar::parse(name, true).is_some() {
+                        return Err(error(
+                            line_num,
+                            format!("Invalid structure name '{}'", name),
+                        ));
+                    }
+                    if structs.iter().any(|s| s.name == *name) {
+                        return Err(error(line_num, format!("Duplicate structure '{}'", name)));
+                    }
+                    current = Some(Struct {
+                        name: (*name).to_string(),
+                        fields: Vec::new(),
+                    });
+                }
+                ["end"] => {
+                    let st = current
+                        .take()
+                        .ok_or_else(|| error(line_num, "Unexpected 'end'".to_string()))?;
+                    structs.push(st);
+                }
+                _ => {
+                    let st = current
+                        .as_mut()
+                        .ok_or_else(|| error(line_num, "Field outside of structure".to_string()))?;
+                    let decl = Template::parse_field(&tokens, little_endian, line_num)
+                        .map_err(|msg| error(line_num, msg))?;
+                    if st.fields.iter().any(|f| f.name == decl.name) {
+                        return Err(error(line_num, format!("Duplicate field '{}'", decl.name)));
+                    }
+                    st.fields.push(decl);
+                }
+            }
+        }
+
+        if let Some(st) = current {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Missing 'end' of structure '{}'", st.name),
+            ));
+        }
+        if structs.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Template has no structures",
+            ));
+        }
+
+        // check references to structures
+        for decl in structs.iter().flat_map(|s| s.fields.iter()) {
+            if let Type::Struct(name) = &decl.dtype {
+                if !structs.iter().any(|s| s.name == *name) {
+                    return Err(error(decl.line, format!("Unknown type '{}'", name)));
+                }
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            structs,
+        })
+    }
+
+    /// Parse field declaration.
+    fn parse_field(
+        tokens: &[&str],
+        little_endian: bool,
+        line: usize,
+    ) -> std::result::Result<Declaration, String> {
+        let (type_name, name) = match tokens {
+            [type_name, name, ..] => (*type_name, *name),
+            _ => return Err(format!("Invalid declaration '{}'", tokens.join(" "))),
+        };
+        if !is_ident(name) {
+            return Err(format!("Invalid field name '{}'", name));
+        }
+
+        // type and array size
+        let (base, count) = match type_name.split_once('[') {
+            Some((base, count)) => {
+                let count = count
+                    .strip_suffix(']')
+                    .ok_or_else(|| format!("Invalid array '{}'", type_name))?;
+                let count = if let Some(num) = parse_number(count) {
+                    Count::Fixed(num)
+                } else if let Some((scalar, le)) =
+                    Scalar::parse(count, little_endian).filter(|(s, _)| s.is_integer())
+                {
+                    Count::Prefix(scalar, le)
+                } else if is_ident(count) {
+                    Count::Field(count.to_string())
+                } else {
+                    return Err(format!("Invalid array size '{}'", count));
+                };
+                (base, Some(count))
+            }
+            None => (type_name, None),
+        };
+        let dtype = if let Some((scalar, le)) = Scalar::parse(base, little_endian) {
+            Type::Scalar(scalar, le)
+        } else if is_ident(base) {
+            Type::Struct(base.to_string())
+        } else {
+            return Err(format!("Invalid type '{}'", base));
+        };
+
+        // optional modifiers
+        let operand = |text: &str| -> std::result::Result<Operand, String> {
+            if let Some(num) = parse_number(text) {
+                Ok(Operand::Number(num))
+            } else if is_ident(text) {
+                Ok(Operand::Field(text.to_string()))
+            } else {
+                Err(format!("Invalid operand '{}'", text))
+            }
+        };
+        let mut at = None;
+        let mut condition = None;
+        let mut rest = &tokens[2..];
+        while !rest.is_empty() {
+            match rest {
+                ["at", offset, tail @ ..] => {
+                    at = Some(operand(offset)?);
+                    rest = tail;
+                }
+                ["if", field, op, value, tail @ ..] => {
+                    if !is_ident(field) {
+                        return Err(format!("Invalid field name '{}'", field));
+                    }
+                    let op = match *op {
+                        "==" => Compare::Equal,
+                        "!=" => Compare::NotEqual,
+                        "<" => Compare::Less,
+                        "<=" => Compare::LessEqual,
+                        ">" => Compare::Greater,
+                        ">=" => Compare::GreaterEqual,
+                        "&" => Compare::BitAnd,
+                        _ => return Err(format!("Invalid operator '{}'", op)),
+                    };
+                    condition = Some(((*field).to_string(), op, operand(value)?));
+                    rest = tail;
+                }
+                _ => return Err(format!("Unexpected '{}'", rest[0])),
+            }
+        }
+
+        Ok(Declaration {
+            name: name.to_string(),
+            type_name: type_name.to_string(),
+            dtype,
+            count,
+            at,
+            condition,
+            line,
+        })
+    }
+
+    /// Apply template to the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - start offset of the root structure
+    /// * `size` - size of the file
+    /// * `read` - data reader: offset and size of the block
+    ///
+    /// # Return value
+    ///
+    /// List of fields in the tree order (parent before its children).
+    pub fn apply(
+        &self,
+        offset: u64,
+        size: u64,
+        read: &mut dyn FnMut(u64, usize) -> Result<Vec<u8>>,
+    ) -> Result<Vec<Field>> {
+        let mut eval = Evaluator {
+            template: self,
+            base: offset,
+            size,
+            read,
+            fields: Vec::new(),
+            scopes: Vec::new(),
+        };
+        let root = &self.structs[0];
+        eval.structure(root, self.name.clone(), root.name.clone(), 0, offset)?;
+        Ok(eval.fields)
+    }
+}
+
+/// Template applied to the document.
+pub struct Overlay {
+    /// Applied template.
+    pub template: Template,
+    /// Start offset of the root structure.
+    pub offset: u64,
+    /// Fields of the evaluated template.
+    pub fields: Vec<Field>,
+    /// Description of the last evaluation error.
+    pub error: Option<String>,
+    /// File size at the last evaluation.
+    size: u64,
+}
+
+impl Overlay {
+    /// Create new overlay.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - template to apply
+    /// * `offset` - start offset of the root structure
+    ///
+    /// # Return value
+    ///
+    /// Overlay instance, fields are evaluated on the first update.
+    pub fn new(template: Template, offset: u64) -> Self {
+        Self {
+            template,
+            offset,
+            fields: Vec::new(),
+            error: None,
+            size: 0,
+        }
+    }
+
+    /// Evaluate template for the current file data.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - file to read data from
+    pub fn update(&mut self, file: &mut File) {
+        self.size = file.size;
+        let mut read = |offset: u64, size: usize| file.read(offset, size);
+        match self.template.apply(self.offset, self.size, &mut read) {
+            Ok(fields) => {
+                self.fields = fields;
+                self.error = None;
+            }
+            Err(err) => {
+                self.fields.clear();
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Check if the file size was changed since the last evaluation.
+    pub fn is_outdated(&self, file: &File) -> bool {
+        self.size != file.size
+    }
+}
+
+/// Representation of the field data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// Structure or array of elements.
+    Group,
+    /// Numeric value with byte order (`true` for little endian).
+    Number(Scalar, bool),
+    /// Array of chars.
+    Text,
+    /// Array of bytes.
+    Bytes,
+}
+
+/// Field of the applied template.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    /// Field name, array elements are named as `[index]`.
+    pub name: String,
+    /// Type name.
+    pub type_name: String,
+    /// Nesting level.
+    pub depth: usize,
+    /// Range of the field data in the file.
+    pub range: Range<u64>,
+    /// Representation of the data.
+    pub kind: Kind,
+    /// Field data (head of the data for long arrays).
+    data: Vec<u8>,
+}
+
+impl Field {
+    /// Max number of bytes read for text and byte arrays.
+    const MAX_DATA: usize = 64;
+
+    /// Get text representation of the field value.
+    ///
+    /// # Return value
+    ///
+    /// Value of the field, empty for groups.
+    pub fn value(&self) -> String {
+        let tail = if (self.data.len() as u64) < self.range.end - self.range.start {
+            "\u{2026}"
+        } else {
+            ""
+        };
+        match self.kind {
+            Kind::Group => String::new(),
+            Kind::Number(Scalar::Char, _) => format!("'{}'", escape(&self.data)),
+            Kind::Number(scalar, little_endian) => {
+                let raw = read_number(&self.data, little_endian);
+                let value = scalar.value(raw);
+                match scalar {
+                    _ if scalar.is_signed() => format!("{}", value as i64),
+                    #[allow(clippy::cast_possible_truncation)]
+                    Scalar::F32 => format!("{}", f32::from_bits(raw as u32)),
+                    Scalar::F64 => format!("{}", f64::from_bits(raw)),
+                    _ => format!("{} (0x{:x})", value, value),
+                }
+            }
+            Kind::Text => format!("\"{}{}\"", escape(&self.data), tail),
+            Kind::Bytes => {
+                let hex: Vec<String> = self.data.iter().map(|b| format!("{:02x}", b)).collect();
+                format!("{}{}", hex.join(" "), tail)
+            }
+        }
+    }
+
+    /// Get line for the fields tree.
+    ///
+    /// # Return value
+    ///
+    /// Offset, type, name and value of the field.
+    pub fn line(&self) -> String {
+        let mut line = format!(
+            "{:08x}  {:indent$}{} {}",
+            self.range.start,
+            "",
+            self.type_name,
+            self.name,
+            indent = self.depth * 2
+        );
+        if self.kind != Kind::Group {
+            line += " = ";
+            line += &self.value();
+        }
+        line
+    }
+
+    /// Check if the field value can be edited.
+    pub fn is_editable(&self) -> bool {
+        match self.kind {
+            Kind::Group => false,
+            Kind::Number(_, _) => true,
+            Kind::Text | Kind::Bytes => self.data.len() as u64 == self.range.end - self.range.start,
+        }
+    }
+
+    /// Get the current value in the edit format.
+    pub fn edit_value(&self) -> String {
+        match self.kind {
+            Kind::Number(Scalar::Char, _) | Kind::Text => escape(&self.data),
+            Kind::Number(scalar, little_endian) if scalar.is_integer() => {
+                let value = scalar.value(read_number(&self.data, little_endian));
+                if scalar.is_signed() {
+                    format!("{}", value as i64)
+                } else {
+                    format!("{}", value)
+                }
+            }
+            _ => self.value(),
+        }
+    }
+
+    /// Convert edited text to the field data.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - new value of the field
+    ///
+    /// # Return value
+    ///
+    /// Field data, `None` if the value is invalid.
+    pub fn encode(&self, text: &str) -> Option<Vec<u8>> {
+        #[allow(clippy::cast_possible_truncation)]
+        let size = (self.range.end - self.range.start) as usize;
+        match self.kind {
+            Kind::Group => None,
+            Kind::Number(Scalar::Char, _) | Kind::Text => {
+                let mut data = unescape(text)?;
+                if data.len() > size || (self.kind != Kind::Text && data.len() != size) {
+                    return None;
+                }
+                data.resize(size, 0);
+                Some(data)
+            }
+            Kind::Bytes => hex::parse_bytes(text).filter(|data| data.len() == size),
+            Kind::Number(scalar, little_endian) => {
+                let text = text.trim();
+                let raw = match scalar {
+                    Scalar::F32 => u64::from(text.parse::<f32>().ok()?.to_bits()),
+                    Scalar::F64 => text.parse::<f64>().ok()?.to_bits(),
+                    _ => {
+                        let bits = size as u32 * 8;
+                        let max = u64::MAX >> (64 - bits);
+                        if let Some(value) = text.strip_prefix('-') {
+                            if !scalar.is_signed() {
+                                return None;
+                            }
+                            let value = parse_number(value)?;
+                            if value > (max >> 1) + 1 {
+                                return None;
+                            }
+                            value.wrapping_neg() & max
+                        } else {
+                            let value = parse_number(text)?;
+                            if value > max {
+                                return None;
+                            }
+                            value
+                        }
+                    }
+                };
+                let bytes = raw.to_le_bytes();
+                let mut data = bytes[..size].to_vec();
+                if !little_endian {
+                    data.reverse();
+                }
+                Some(data)
+            }
+        }
+    }
+}
+
+/// Template evaluator.
+struct Evaluator<'a> {
+    template: &'a Template,
+    /// Start offset of the root structure.
+    base: u64,
+    /// Size of the file.
+    size: u64,
+    /// Data reader.
+    read: &'a mut dyn FnMut(u64, usize) -> Result<Vec<u8>>,
+    /// Produced fields.
+    fields: Vec<Field>,
+    /// Values of the numeric fields for each nested structure.
+    scopes: Vec<BTreeMap<String, u64>>,
+}
+
+impl Evaluator<'_> {
+    /// Evaluate structure.
+    ///
+    /// # Return value
+    ///
+    /// End offset of the structure.
+    fn structure(
+        &mut self,
+        st: &Struct,
+        name: String,
+        type_name: String,
+        depth: usize,
+        offset: u64,
+    ) -> Result<u64> {
+        if depth >= Template::MAX_DEPTH {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Structure nesting is too deep",
+            ));
+        }
+        let index = self.push(Field {
+            name,
+            type_name,
+            depth,
+            range: offset..offset,
+            kind: Kind::Group,
+            data: Vec::new(),
+        })?;
+
+        self.scopes.push(BTreeMap::new());
+        let mut pos = offset;
+        for decl in &st.fields {
+            if let Some((field, op, operand)) = &decl.condition {
+                let left = self.lookup(field)?;
+                let right = self.operand(operand)?;
+                let present = match op {
+                    Compare::Equal => left == right,
+                    Compare::NotEqual => left != right,
+                    Compare::Less => left < right,
+                    Compare::LessEqual => left <= right,
+                    Compare::Greater => left > right,
+                    Compare::GreaterEqual => left >= right,
+                    Compare::BitAnd => left & right != 0,
+                };
+                if !present {
+                    continue;
+                }
+            }
+            let start = match &decl.at {
+                Some(operand) => self.base.saturating_add(self.operand(operand)?),
+                None => pos,
+            };
+            let end = self.declaration(decl, depth + 1, start)?;
+            if decl.at.is_none() {
+                pos = end;
+            }
+        }
+        self.scopes.pop();
+
+        self.fields[index].range.end = pos;
+        Ok(pos)
+    }
+
+    /// Evaluate field declaration.
+    ///
+    /// # Return value
+    ///
+    /// End offset of the field.
+    fn declaration(&mut self, decl: &Declaration, depth: usize, offset: u64) -> Result<u64> {
+        let (count, start) = match &decl.count {
+            None => {
+                let (end, value) = self.element(
+                    decl,
+                    decl.name.clone(),
+                    decl.type_name.clone(),
+                    depth,
+                    offset,
+                )?;
+                if let (Some(value), Some(scope)) = (value, self.scopes.last_mut()) {
+                    scope.insert(decl.name.clone(), value);
+                }
+                return Ok(end);
+            }
+            Some(Count::Fixed(count)) => (*count, offset),
+            Some(Count::Field(name)) => (self.lookup(name)?, offset),
+            Some(Count::Prefix(scalar, little_endian)) => {
+                let data = self.read(offset, scalar.size(), &decl.name)?;
+                let count = scalar.value(read_number(&data, *little_endian));
+                (count, offset + scalar.size() as u64)
+            }
+        };
+
+        match decl.dtype {
+            Type::Scalar(Scalar::U8 | Scalar::Char, _) => {
+                // text and byte arrays are represented as a single field
+                let end = start
+                    .checked_add(count)
+                    .filter(|&end| end <= self.size)
+                    .ok_or_else(|| Evaluator::out_of_range(&decl.name))?;
+                #[allow(clippy::cast_possible_truncation)]
+                let len = count.min(Field::MAX_DATA as u64) as usize;
+                let data = self.read(start, len, &decl.name)?;
+                let kind = if matches!(decl.dtype, Type::Scalar(Scalar::Char, _)) {
+                    Kind::Text
+                } else {
+                    Kind::Bytes
+                };
+                self.push(Field {
+                    name: decl.name.clone(),
+                    type_name: decl.type_name.clone(),
+                    depth,
+                    range: start..end,
+                    kind,
+                    data,
+                })?;
+                Ok(end)
+            }
+            _ => {
+                if count > (Template::MAX_FIELDS - self.fields.len()) as u64 {
+                    return Err(Evaluator::too_many());
+                }
+                let index = self.push(Field {
+                    name: decl.name.clone(),
+                    type_name: decl.type_name.clone(),
+                    depth,
+                    range: offset..offset,
+                    kind: Kind::Group,
+                    data: Vec::new(),
+                })?;
+                let type_name = match &decl.dtype {
+                    Type::Scalar(_, _) => decl.type_name.split('[').next().unwrap_or_default(),
+                    Type::Struct(name) => name,
+                }
+                .to_string();
+                let mut pos = start;
+                for i in 0..count {
+                    let (end, _) =
+                        self.element(decl, format!("[{}]", i), type_name.clone(), depth + 1, pos)?;
+                    pos = end;
+                }
+                self.fields[index].range.end = pos;
+                Ok(pos)
+            }
+        }
+    }
+
+    /// Evaluate single element of the declared type.
+    ///
+    /// # Return value
+    ///
+    /// End offset and numeric value of the scalar.
+    fn element(
+        &mut self,
+        decl: &Declaration,
+        name: String,
+        type_name: String,
+        depth: usize,
+        offset: u64,
+    ) -> Result<(u64, Option<u64>)> {
+        match &decl.dtype {
+            Type::Scalar(scalar, little_endian) => {
+                let data = self.read(offset, scalar.size(), &decl.name)?;
+                let value = scalar.value(read_number(&data, *little_endian));
+                let end = offset + data.len() as u64;
+                self.push(Field {
+                    name,
+                    type_name,
+                    depth,
+                    range: offset..end,
+                    kind: Kind::Number(*scalar, *little_endian),
+                    data,
+                })?;
+                Ok((end, Some(value)))
+            }
+            Type::Struct(struct_name) => {
+                let template = self.template;
+                let st = template
+                    .structs
+                    .iter()
+                    .find(|s| s.name == *struct_name)
+                    .unwrap();
+                let end = self.structure(st, name, type_name, depth, offset)?;
+                Ok((end, None))
+            }
+        }
+    }
+
+    /// Read data from the file.
+    fn read(&mut self, offset: u64, size: usize, name: &str) -> Result<Vec<u8>> {
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        match offset.checked_add(size as u64) {
+            Some(end) if end <= self.size => (self.read)(offset, size),
+            _ => Err(Evaluator::out_of_range(name)),
+        }
+    }
+
+    /// Add field to the list.
+    ///
+    /// # Return value
+    ///
+    /// Index of the added field.
+    fn push(&mut self, field: Field) -> Result<usize> {
+        if self.fields.len() >= Template::MAX_FIELDS {
+            return Err(Evaluator::too_many());
+        }
+        self.fields.push(field);
+        Ok(self.fields.len() - 1)
+    }
+
+    /// Get value of the previously read field.
+    fn lookup(&self, name: &str) -> Result<u64> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Unknown field '{}'", name)))
+    }
+
+    /// Get value of the operand.
+    fn operand(&self, operand: &Operand) -> Result<u64> {
+        match operand {
+            Operand::Number(value) => Ok(*value),
+            Operand::Field(name) => self.lookup(name),
+        }
+    }
+
+    /// Create "out of range" error.
+    fn out_of_range(name: &str) -> Error {
+        Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("Field '{}' is out of file range", name),
+        )
+    }
+
+    /// Create "too many fields" error.
+    fn too_many() -> Error {
+        Error::new(ErrorKind::InvalidData, "Too many fields")
+    }
+}
+
+/// Check if the text is a valid identifier.
+fn is_ident(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse decimal or hex (with `0x` prefix) number.
+fn parse_number(text: &str) -> Option<u64> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        text.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Compose number from the bytes.
+fn read_number(data: &[u8], little_endian: bool) -> u64 {
+    let fold = |value: u64, byte: &u8| (value << 8) | u64::from(*byte);
+    if little_endian {
+        data.iter().rev().fold(0, fold)
+    } else {
+        data.iter().fold(0, fold)
+    }
+}
+
+/// Convert bytes to text, non-printable characters are escaped as `\xNN`.
+fn escape(data: &[u8]) -> String {
+    data.iter()
+        .map(|&b| match b {
+            b'\\' => "\\\\".to_string(),
+            b' '..=b'~' => (b as char).to_string(),
+            _ => format!("\\x{:02x}", b),
+        })
+        .collect()
+}
+
+/// Convert text with escaped characters (`\xNN`, `\\`) to bytes.
+fn unescape(text: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(chr) = chars.next() {
+        if chr == '\\' {
+            match chars.next()? {
+                '\\' => data.push(b'\\'),
+                'x' => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    if hex.len() != 2 {
+                        return None;
+                    }
+                    data.push(u8::from_str_radix(&hex, 16).ok()?);
+                }
+                _ => return None,
+            }
+        } else if chr.is_ascii() {
+            data.push(chr as u8);
+        } else {
+            return None;
+        }
+    }
+    Some(data)
+}
+
+#[test]
+fn test_template() {
+    let text = "
+        # test template
+        struct header
+            char[4]      magic
+            u16          count
+            u16be        flags
+            entry[count] entries
+            u32          extra    if flags & 0x1
+            u32          skipped  if count > 2
+            u8[u8]       name
+            u32          tail     at 0x14
+        end
+        struct entry
+            u8 id
+            i8 value
+        end";
+    let template = Template::parse("test", text).unwrap();
+    let data = b"TEST\x02\x00\x00\x01\x01\xff\x02\x7f\xaa\xbb\xcc\xdd\x02hi\x00\x78\x56\x34\x12";
+    let mut read = |offset: u64, size: usize| {
+        let offset = offset as usize;
+        Ok(data[offset..offset + size].to_vec())
+    };
+    let fields = template.apply(0, data.len() as u64, &mut read).unwrap();
+    assert_eq!(fields.len(), 14);
+    assert_eq!(fields[0].range, 0..19);
+    assert_eq!(fields[1].value(), "\"TEST\"");
+    assert_eq!(fields[2].value(), "2 (0x2)");
+    assert_eq!(fields[3].value(), "1 (0x1)");
+    assert_eq!(fields[4].range, 8..12);
+    assert_eq!(fields[7].line(), "00000009        i8 value = -1");
+    assert_eq!(fields[11].value(), "3721182122 (0xddccbbaa)");
+    assert_eq!(fields[12].range, 17..19);
+    assert_eq!(fields[12].value(), "68 69");
+    assert_eq!(fields[13].range, 0x14..0x18);
+    assert_eq!(fields[13].value(), "305419896 (0x12345678)");
+
+    assert!(template.apply(0, 0x10, &mut read).is_err());
+
+    // editing
+    assert_eq!(fields[1].encode("AB"), Some(b"AB\0\0".to_vec()));
+    assert_eq!(fields[1].encode("ABCDE"), None);
+    assert_eq!(fields[2].encode("0x102"), Some(vec![0x02, 0x01]));
+    assert_eq!(fields[2].encode("65536"), None);
+    assert_eq!(fields[3].encode("258"), Some(vec![0x01, 0x02]));
+    assert_eq!(fields[7].edit_value(), "-1");
+    assert_eq!(fields[7].encode("-128"), Some(vec![0x80]));
+    assert_eq!(fields[7].encode("-129"), None);
+    assert_eq!(fields[12].encode("00 11"), Some(vec![0x00, 0x11]));
+    assert_eq!(fields[12].encode("00"), None);
+    assert!(!fields[0].is_editable());
+
+    // errors
+    let err = Template::parse("", "struct a\n  foo x\nend").unwrap_err();
+    assert_eq!(err.to_string(), "Unknown type 'foo' at line 2");
+    let err = Template::parse("", "struct a\n  u8 x\n").unwrap_err();
+    assert_eq!(err.to_string(), "Missing 'end' of structure 'a'");
+    let err = Template::parse("", "struct a\n  a\u{436}b x\nend").unwrap_err();
+    assert_eq!(err.to_string(), "Invalid type 'a\u{436}b' at line 2");
+    assert!(Template::parse("", "struct a\n  u8[\u{436}] x\nend").is_err());
+    let template = Template::parse("", "struct a\n  u8[y] x\nend").unwrap();
+    assert!(template.apply(0, data.len() as u64, &mut read).is_err());
+}
+
+#[test]
+fn test_template_depth() {
+    let mut read = |_: u64, size: usize| Ok(vec![0; size]);
+    let template = Template::parse("", "struct a\n  u8 x\n  a child\nend").unwrap();
+    let err = template.apply(0, 0x1000, &mut read).unwrap_err();
+    assert_eq!(err.to_string(), "Structure nesting is too deep");
+    let template = Template::parse("", "struct a\n  a child\nend").unwrap();
+    assert!(template.apply(0, 0x1000, &mut read).is_err());
+}
+
+#[test]
+fn test_template_too_many() {
+    let data = [0xff_u8; 8];
+    let mut read = |offset: u64, size: usize| {
+        let offset = offset as usize;
+        Ok(data[offset..offset + size].to_vec())
+    };
+    let template = Template::parse("", "struct a\n  u32 n\n  u16[n] x\nend").unwrap();
+    let err = template.apply(0, data.len() as u64, &mut read).unwrap_err();
+    assert_eq!(err.to_string(), "Too many fields");
+    let template = Template::parse("", "struct a\n  u32 n\n  b[n] x\nend\nstruct b\nend").unwrap();
+    let err = template.apply(0, data.len() as u64, &mut read).unwrap_err();
+    assert_eq!(err.to_string(), "Too many fields");
+}
+
+#[test]
+fn test_template_out_of_range() {
+    let data = [0xff_u8; 8];
+    let mut read = |offset: u64, size: usize| {
+        let offset = offset as usize;
+        Ok(data[offset..offset + size].to_vec())
+    };
+    let size = data.len() as u64;
+
+    // length prefix points past the end of file
+    let template = Template::parse("", "struct a\n  u8[u8] x\nend").unwrap();
+    let err = template.apply(0, size, &mut read).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(err.to_string(), "Field 'x' is out of file range");
+    let template = Template::parse("", "struct a\n  u16[u64] x\nend").unwrap();
+    assert!(template.apply(0, size, &mut read).is_err());
+    let template = Template::parse("", "struct a\n  u8 y at 4\n  u8[u8] x at 7\nend").unwrap();
+    assert!(template.apply(0, size, &mut read).is_err());
+
+    // offsets beyond the file
+    let template = Template::parse("", "struct a\n  u8 x at 8\nend").unwrap();
+    let err = template.apply(0, size, &mut read).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    let template = Template::parse("", "struct a\n  u64 n\n  u8 x at n\nend").unwrap();
+    assert!(template.apply(0, size, &mut read).is_err());
+    let template = Template::parse("", "struct a\n  u8 x at 0xffffffffffffffff\nend").unwrap();
+    assert!(template.apply(4, size, &mut read).is_err());
+    assert!(template.apply(u64::MAX, size, &mut read).is_err());
+}
@@ -0,0 +1,361 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::hex;
+
+/// Transform operation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    /// XOR with a repeating key.
+    Xor(Vec<u8>),
+    /// AND with a repeating key.
+    And(Vec<u8>),
+    /// OR with a repeating key.
+    Or(Vec<u8>),
+    /// Bitwise NOT.
+    Not,
+    /// Add value with wrap around.
+    Add(u64),
+    /// Subtract value with wrap around.
+    Sub(u64),
+    /// Rotate bits to the left.
+    RotateLeft(u32),
+    /// Rotate bits to the right.
+    RotateRight(u32),
+    /// Shift bits to the left.
+    ShiftLeft(u32),
+    /// Shift bits to the right.
+    ShiftRight(u32),
+    /// Swap byte order in the unit.
+    Swap,
+    /// Reverse order of bytes in the entire range.
+    Reverse,
+}
+
+impl Operation {
+    /// Names of the operations, the index is used by `Operation::parse`.
+    pub const NAMES: &'static [&'static str] = &[
+        "XOR",
+        "AND",
+        "OR",
+        "NOT",
+        "Add",
+        "Subtract",
+        "Rotate left",
+        "Rotate right",
+        "Shift left",
+        "Shift right",
+        "Swap byte order",
+        "Reverse range",
+    ];
+
+    /// Get description of the operand.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the operation in `NAMES`
+    ///
+    /// # Return value
+    ///
+    /// Operand description, `None` if operation has no operand.
+    pub fn operand(index: usize) -> Option<&'static str> {
+        match index {
+            0..=2 => Some("Key (hex bytes):"),
+            4 | 5 => Some("Value (hex):"),
+            6..=9 => Some("Number of bits:"),
+            _ => None,
+        }
+    }
+
+    /// Construct operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the operation in `NAMES`
+    /// * `operand` - text representation of the operand
+    ///
+    /// # Return value
+    ///
+    /// Operation, `None` if operand is invalid.
+    pub fn parse(index: usize, operand: &str) -> Option<Self> {
+        let key = || hex::parse_bytes(operand);
+        let value = || u64::from_str_radix(operand.trim(), 16).ok();
+        let bits = || operand.trim().parse::<u32>().ok();
+        match index {
+            0 => key().map(Operation::Xor),
+            1 => key().map(Operation::And),
+            2 => key().map(Operation::Or),
+            3 => Some(Operation::Not),
+            4 => value().map(Operation::Add),
+            5 => value().map(Operation::Sub),
+            6 => bits().map(Operation::RotateLeft),
+            7 => bits().map(Operation::RotateRight),
+            8 => bits().map(Operation::ShiftLeft),
+            9 => bits().map(Operation::ShiftRight),
+            10 => Some(Operation::Swap),
+            11 => Some(Operation::Reverse),
+            _ => None,
+        }
+    }
+}
+
+/// Transform: operation applied to units of data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transform {
+    /// Operation.
+    pub operation: Operation,
+    /// Size of the unit in bytes: 1, 2, 4 or 8.
+    pub width: usize,
+    /// Byte order of the units.
+    pub little_endian: bool,
+}
+
+impl Transform {
+    /// Available unit sizes.
+    pub const WIDTHS: &'static [usize] = &[1, 2, 4, 8];
+
+    /// Check if the transform can be applied to the range.
+    ///
+    /// # Arguments
+    ///
+    /// * `length` - length of the range
+    ///
+    /// # Return value
+    ///
+    /// Error description if the transform is not applicable.
+    pub fn validate(&self, length: u64) -> Result<(), &'static str> {
+        if self.operation == Operation::Reverse {
+            Ok(())
+        } else if self.operation == Operation::Swap && self.width == 1 {
+            Err("Byte swap requires 16, 32 or 64-bit unit")
+        } else if !length.is_multiple_of(self.width as u64) {
+            Err("Range length is not a multiple of the unit")
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get size of the block that can be transformed independently of the
+    /// preceding data.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - max size of the block
+    ///
+    /// # Return value
+    ///
+    /// Block size aligned to the unit and the key, `None` if the entire range
+    /// must be transformed at once.
+    pub fn block_size(&self, max: usize) -> Option<usize> {
+        let align = match &self.operation {
+            Operation::Reverse => return None,
+            Operation::Xor(key) | Operation::And(key) | Operation::Or(key) => {
+                // least common multiple of the unit and the key sizes
+                let (mut a, mut b) = (self.width, key.len());
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                self.width / a * key.len()
+            }
+            _ => self.width,
+        };
+        Some((max / align).max(1) * align)
+    }
+
+    /// Apply transform to the data, repeating keys are applied byte by byte
+    /// from the start of the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - data to transform, its size must be a multiple of the unit
+    pub fn apply(&self, data: &mut [u8]) {
+        debug_assert!(Transform::WIDTHS.contains(&self.width));
+        debug_assert!(self.validate(data.len() as u64).is_ok());
+
+        match &self.operation {
+            Operation::Xor(key) => Transform::apply_key(data, key, |l, r| l ^ r),
+            Operation::And(key) => Transform::apply_key(data, key, |l, r| l & r),
+            Operation::Or(key) => Transform::apply_key(data, key, |l, r| l | r),
+            Operation::Swap => data.chunks_exact_mut(self.width).for_each(<[u8]>::reverse),
+            Operation::Reverse => data.reverse(),
+            _ => {
+                for unit in data.chunks_exact_mut(self.width) {
+                    let value = self.transform(self.read(unit));
+                    self.write(unit, value);
+                }
+            }
+        }
+    }
+
+    /// Apply repeating key to the data.
+    fn apply_key(data: &mut [u8], key: &[u8], op: fn(u8, u8) -> u8) {
+        debug_assert!(!key.is_empty());
+        for (byte, k) in data.iter_mut().zip(key.iter().cycle()) {
+            *byte = op(*byte, *k);
+        }
+    }
+
+    /// Apply numeric operation to the unit value.
+    fn transform(&self, value: u64) -> u64 {
+        let bits = self.width as u32 * 8;
+        let mask = u64::MAX >> (64 - bits);
+        let value = match self.operation {
+            Operation::Not => !value,
+            Operation::Add(arg) => value.wrapping_add(arg),
+            Operation::Sub(arg) => value.wrapping_sub(arg),
+            Operation::RotateLeft(count) => {
+                let count = count % bits;
+                (value << count) | (value >> ((bits - count) % bits))
+            }
+            Operation::RotateRight(count) => {
+                let count = count % bits;
+                (value >> count) | (value << ((bits - count) % bits))
+            }
+            Operation::ShiftLeft(count) => value.checked_shl(count).unwrap_or(0),
+            Operation::ShiftRight(count) => value.checked_shr(count).unwrap_or(0),
+            _ => value,
+        };
+        value & mask
+    }
+
+    /// Read unit value.
+    fn read(&self, unit: &[u8]) -> u64 {
+        let mut value = 0;
+        for i in 0..unit.len() {
+            let byte = if self.little_endian {
+                unit[unit.len() - 1 - i]
+            } else {
+                unit[i]
+            };
+            value = (value << 8) | u64::from(byte);
+        }
+        value
+    }
+
+    /// Write unit value.
+    fn write(&self, unit: &mut [u8], value: u64) {
+        let len = unit.len();
+        for (i, byte) in unit.iter_mut().enumerate() {
+            let shift = if self.little_endian { i } else { len - 1 - i } * 8;
+            *byte = (value >> shift) as u8;
+        }
+    }
+}
+
+#[test]
+fn test_operation() {
+    assert_eq!(
+        Operation::parse(0, "de ad"),
+        Some(Operation::Xor(vec![0xde, 0xad]))
+    );
+    assert_eq!(Operation::parse(0, "abc"), None);
+    assert_eq!(Operation::parse(3, ""), Some(Operation::Not));
+    assert_eq!(Operation::parse(4, "1f"), Some(Operation::Add(0x1f)));
+    assert_eq!(Operation::parse(6, "12"), Some(Operation::RotateLeft(12)));
+    assert_eq!(Operation::parse(9, "x"), None);
+    assert_eq!(Operation::parse(11, ""), Some(Operation::Reverse));
+    assert_eq!(Operation::NAMES.len(), 12);
+}
+
+#[test]
+fn test_transform() {
+    let check = |operation: Operation, width: usize, little_endian: bool, data: &[u8]| {
+        let mut data = data.to_vec();
+        Transform {
+            operation,
+            width,
+            little_endian,
+        }
+        .apply(&mut data);
+        data
+    };
+
+    let data = [0x12, 0x34, 0x56, 0x78];
+    assert_eq!(
+        check(Operation::Xor(vec![0xff, 0x00]), 4, false, &data),
+        [0xed, 0x34, 0xa9, 0x78]
+    );
+    assert_eq!(
+        check(Operation::And(vec![0x0f]), 1, false, &data),
+        [0x02, 0x04, 0x06, 0x08]
+    );
+    assert_eq!(
+        check(Operation::Not, 2, true, &data),
+        [0xed, 0xcb, 0xa9, 0x87]
+    );
+    assert_eq!(
+        check(Operation::Add(0xff), 1, false, &data),
+        [0x11, 0x33, 0x55, 0x77]
+    );
+    assert_eq!(
+        check(Operation::Add(0xff), 2, true, &data),
+        [0x11, 0x35, 0x55, 0x79]
+    );
+    assert_eq!(
+        check(Operation::Sub(1), 4, false, &[0, 0, 0, 0]),
+        [0xff, 0xff, 0xff, 0xff]
+    );
+    assert_eq!(
+        check(Operation::RotateLeft(4), 2, false, &data),
+        [0x23, 0x41, 0x67, 0x85]
+    );
+    assert_eq!(
+        check(Operation::RotateRight(8), 4, true, &data),
+        [0x34, 0x56, 0x78, 0x12]
+    );
+    assert_eq!(
+        check(Operation::ShiftLeft(4), 1, false, &data),
+        [0x20, 0x40, 0x60, 0x80]
+    );
+    assert_eq!(
+        check(Operation::ShiftRight(64), 8, false, &[0xff; 8]),
+        [0; 8]
+    );
+    assert_eq!(
+        check(Operation::Swap, 2, false, &data),
+        [0x34, 0x12, 0x78, 0x56]
+    );
+    assert_eq!(
+        check(Operation::Swap, 4, false, &data),
+        [0x78, 0x56, 0x34, 0x12]
+    );
+    assert_eq!(check(Operation::Reverse, 4, false, &[1, 2, 3]), [3, 2, 1]);
+}
+
+#[test]
+fn test_block_size() {
+    let transform = |operation: Operation, width: usize| Transform {
+        operation,
+        width,
+        little_endian: true,
+    };
+    assert_eq!(transform(Operation::Not, 8).block_size(100), Some(96));
+    assert_eq!(
+        transform(Operation::Xor(vec![1; 3]), 2).block_size(100),
+        Some(96)
+    );
+    assert_eq!(
+        transform(Operation::Xor(vec![1; 6]), 4).block_size(100),
+        Some(96)
+    );
+    assert_eq!(
+        transform(Operation::Or(vec![1; 12]), 8).block_size(10),
+        Some(24)
+    );
+    assert_eq!(transform(Operation::Reverse, 1).block_size(100), None);
+}
+
+#[test]
+fn test_validate() {
+    let transform = |operation: Operation, width: usize| Transform {
+        operation,
+        width,
+        little_endian: true,
+    };
+    assert!(transform(Operation::Swap, 2).validate(4).is_ok());
+    assert!(transform(Operation::Swap, 4).validate(6).is_err());
+    assert!(transform(Operation::Swap, 1).validate(4).is_err());
+    assert!(transform(Operation::Reverse, 8).validate(3).is_ok());
+    assert!(transform(Operation::Not, 2).validate(3).is_err());
+    assert!(transform(Operation::Not, 1).validate(3).is_ok());
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::widget::{InputFormat, InputLine, StandardButton, TextView, WidgetType};
+
+/// "Pending changes" dialog: review and save the patch list.
+pub struct ChangesDialog {
+    path: ItemId,
+    btn_save: ItemId,
+    btn_close: ItemId,
+}
+
+impl ChangesDialog {
+    /// Max number of visible lines of the list.
+    const MAX_LINES: usize = 12;
+    /// Min width of the dialog.
+    const MIN_WIDTH: usize = 30;
+
+    /// Show the "Pending changes" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - patch list lines
+    /// * `default` - default name of the file to save the list
+    ///
+    /// # Return value
+    ///
+    /// Path to the file to save the list.
+    pub fn show(lines: Vec<String>, default: String) -> Option<String> {
+        let title = "Pending changes";
+        let width = lines
+            .iter()
+            .map(String::len)
+            .max()
+            .unwrap_or(0)
+            .max(ChangesDialog::MIN_WIDTH)
+            + 1 /* scroll marks */;
+        let width = width.min(Dialog::max_width());
+        let height = lines.len().min(ChangesDialog::MAX_LINES);
+
+        // create dialog
+        let mut dlg = Dialog::new(width, height + 3, DialogType::Normal, title);
+        dlg.add_line(WidgetType::TextView(TextView {
+            lines,
+            top: 0,
+            height,
+            current: None,
+        }));
+        dlg.add_separator();
+
+        // target file
+        dlg.add_line(WidgetType::StaticText("Save to file:".to_string()));
+        let edit = InputLine::new(default, InputFormat::Any, Vec::new(), width);
+        let path = dlg.add_line(WidgetType::Edit(edit));
+
+        // buttons
+        let btn_save = dlg.add_button(StandardButton::Save, false);
+        let btn_close = dlg.add_button(StandardButton::Close, true);
+
+        // construct dialog handler
+        let mut handler = Self {
+            path,
+            btn_save,
+            btn_close,
+        };
+        handler.on_item_change(&mut dlg, handler.path);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_close {
+                return Some(handler.get_path(&dlg));
+            }
+        }
+        None
+    }
+
+    /// Get file path.
+    fn get_path(&self, dialog: &Dialog) -> String {
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.path) {
+            widget.get_value().to_string()
+        } else {
+            String::new()
+        }
+    }
+}
+
+impl DialogHandler for ChangesDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        if item == self.btn_close {
+            return true;
+        }
+        if item == self.btn_save || item == self.path {
+            return dialog.get_context(self.btn_save).enabled;
+        }
+        false
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        if item == self.path {
+            let is_ok = !self.get_path(dialog).is_empty();
+            dialog.set_enabled(self.btn_save, is_ok);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::super::checksum::Algorithm;
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::range::RangeControl;
+use super::widget::{CheckBox, InputFormat, InputLine, ListBox, StandardButton, WidgetType};
+use std::ops::Range;
+
+/// Target to write the checksum value.
+pub struct WriteTarget {
+    /// Start address.
+    pub offset: u64,
+    /// Byte order of the numeric value.
+    pub little_endian: bool,
+}
+
+/// "Checksum" dialog: calculate checksum or hash of the range.
+pub struct ChecksumDialog {
+    rctl: RangeControl,
+    algorithm: ItemId,
+    write: ItemId,
+    offset: ItemId,
+    order: ItemId,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+impl ChecksumDialog {
+    /// Show the "Checksum" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - default target offset (current position)
+    /// * `max` - max offset (file size)
+    ///
+    /// # Return value
+    ///
+    /// Range to process, algorithm and optional target to write the result.
+    pub fn show(offset: u64, max: u64) -> Option<(Range<u64>, Algorithm, Option<WriteTarget>)> {
+        // create dialog
+        let mut dlg = Dialog::new(
+            RangeControl::DIALOG_WIDTH,
+            7,
+            DialogType::Normal,
+            "Checksum",
+        );
+
+        // place range control on dialog
+        let rctl = RangeControl::create(&mut dlg, 0..max, max);
+        dlg.add_separator();
+
+        // algorithm
+        let label = dlg.add_line(WidgetType::StaticText("Algorithm:".to_string()));
+        let listbox = ListBox {
+            list: Algorithm::ALL
+                .iter()
+                .map(|a| a.name().to_string())
+                .collect(),
+            current: Algorithm::ALL
+                .iter()
+                .position(|&a| a == Algorithm::Crc32)
+                .unwrap_or(0),
+        };
+        let algorithm = dlg.add(
+            Dialog::PADDING_X + 12,
+            dlg.get_context(label).y,
+            17,
+            WidgetType::ListBox(listbox),
+        );
+
+        // target to write the result
+        let checkbox = CheckBox {
+            state: false,
+            title: "Write at offset:".to_string(),
+        };
+        let write = dlg.add_line(WidgetType::CheckBox(checkbox));
+        let widget = InputLine::new(
+            format!("{:x}", offset),
+            InputFormat::HexUnsigned,
+            Vec::new(),
+            RangeControl::DIALOG_WIDTH - 21,
+        );
+        let offset = dlg.add(
+            Dialog::PADDING_X + 21,
+            dlg.get_context(write).y,
+            RangeControl::DIALOG_WIDTH - 21,
+            WidgetType::Edit(widget),
+        );
+        let label = dlg.add_line(WidgetType::StaticText("    Byte order:".to_string()));
+        let listbox = ListBox {
+            list: vec!["Big endian".to_string(), "Little endian".to_string()],
+            current: 0,
+        };
+        let order = dlg.add(
+            Dialog::PADDING_X + 21,
+            dlg.get_context(label).y,
+            RangeControl::DIALOG_WIDTH - 21,
+            WidgetType::ListBox(listbox),
+        );
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            rctl,
+            algorithm,
+            write,
+            offset,
+            order,
+            btn_ok,
+            btn_cancel,
+        };
+        handler.on_item_change(&mut dlg, handler.write);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                let range = handler.rctl.get(&dlg)?;
+                let algorithm = handler.get_algorithm(&dlg);
+                let mut target = None;
+                if handler.is_write(&dlg) {
+                    let offset = handler.get_offset(&dlg)?;
+                    let little_endian =
+                        if let WidgetType::ListBox(widget) = dlg.get_widget(handler.order) {
+                            widget.current == 1
+                        } else {
+                            false
+                        };
+                    target = Some(WriteTarget {
+                        offset,
+                        little_endian,
+                    });
+                }
+                return Some((range, algorithm, target));
+            }
+        }
+        None
+    }
+
+    /// Get currently selected algorithm.
+    fn get_algorithm(&self, dialog: &Dialog) -> Algorithm {
+        if let WidgetType::ListBox(widget) = dialog.get_widget(self.algorithm) {
+            Algorithm::ALL[widget.current]
+        } else {
+            Algorithm::Crc32
+        }
+    }
+
+    /// Check if the result must be written to the file.
+    fn is_write(&self, dialog: &Dialog) -> bool {
+        if let WidgetType::CheckBox(widget) = dialog.get_widget(self.write) {
+            widget.state
+        } else {
+            false
+        }
+    }
+
+    /// Get target offset.
+    fn get_offset(&self, dialog: &Dialog) -> Option<u64> {
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.offset) {
+            u64::from_str_radix(widget.get_value(), 16).ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl DialogHandler for ChecksumDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_item_change(dialog, item);
+        let write = self.is_write(dialog);
+        let numeric = !self.get_algorithm(dialog).is_hash();
+        dialog.set_enabled(self.offset, write);
+        dialog.set_enabled(self.order, write && numeric);
+        let is_ok =
+            self.rctl.get(dialog).is_some() && (!write || self.get_offset(dialog).is_some());
+        dialog.set_enabled(self.btn_ok, is_ok);
+    }
+
+    fn on_focus_lost(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_focus_lost(dialog, item);
+    }
+}
@@ -253,7 +253,11 @@ impl Dialog {
     pub fn add_line(&mut self, widget: WidgetType) -> ItemId {
         let (width, _) = self.get_size();
         let line = self.lcline;
-        self.lcline += 1;
+        self.lcline += if let WidgetType::TextView(view) = &widget {
+            view.height
+        } else {
+            1
+        };
         self.add(Dialog::PADDING_X, line, width, widget)
     }
 
@@ -456,11 +460,7 @@ impl Dialog {
     pub fn max_width() -> usize {
         let (width, _) = Curses::screen_size();
         let padding = Dialog::PADDING_X * 2;
-        if width < padding {
-            0
-        } else {
-            width - padding
-        }
+        width.saturating_sub(padding)
     }
 }
 
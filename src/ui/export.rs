@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::super::hexdump::Dump;
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::range::RangeControl;
+use super::widget::{CheckBox, InputFormat, InputLine, ListBox, StandardButton, WidgetType};
+use std::ops::Range;
+
+/// "Export range" dialog: write range of the file to another file.
+pub struct ExportDialog {
+    rctl: RangeControl,
+    path: ItemId,
+    append: ItemId,
+    format: ItemId,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+impl ExportDialog {
+    /// Show the "Export range" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - default range
+    /// * `max` - max offset (file size)
+    /// * `default` - default file name
+    ///
+    /// # Return value
+    ///
+    /// Range to export, target file name, append flag and text dump format
+    /// (`None` for raw binary).
+    pub fn show(
+        range: Range<u64>,
+        max: u64,
+        default: String,
+    ) -> Option<(Range<u64>, String, bool, Option<Dump>)> {
+        // create dialog
+        let mut dlg = Dialog::new(
+            RangeControl::DIALOG_WIDTH,
+            8,
+            DialogType::Normal,
+            "Export range",
+        );
+
+        // place range control on dialog
+        let rctl = RangeControl::create(&mut dlg, range, max);
+        dlg.add_separator();
+
+        // target file
+        dlg.add_line(WidgetType::StaticText("File name:".to_string()));
+        let edit = InputLine::new(
+            default,
+            InputFormat::Any,
+            Vec::new(),
+            RangeControl::DIALOG_WIDTH,
+        );
+        let path = dlg.add_line(WidgetType::Edit(edit));
+        let checkbox = CheckBox {
+            state: false,
+            title: "Append to existing file".to_string(),
+        };
+        let append = dlg.add_line(WidgetType::CheckBox(checkbox));
+
+        // output format
+        let label = dlg.add_line(WidgetType::StaticText("Format:".to_string()));
+        let mut list = vec!["Raw binary".to_string()];
+        list.extend(Dump::ALL.iter().map(|d| d.name().to_string()));
+        let listbox = ListBox { list, current: 0 };
+        let format = dlg.add(
+            Dialog::PADDING_X + 8,
+            dlg.get_context(label).y,
+            16,
+            WidgetType::ListBox(listbox),
+        );
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            rctl,
+            path,
+            append,
+            format,
+            btn_ok,
+            btn_cancel,
+        };
+        handler.on_item_change(&mut dlg, handler.path);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                let range = handler.rctl.get(&dlg)?;
+                let path = if let WidgetType::Edit(widget) = dlg.get_widget(handler.path) {
+                    widget.get_value().to_string()
+                } else {
+                    String::new()
+                };
+                let append = if let WidgetType::CheckBox(widget) = dlg.get_widget(handler.append) {
+                    widget.state
+                } else {
+                    false
+                };
+                let mut format = None;
+                if let WidgetType::ListBox(widget) = dlg.get_widget(handler.format) {
+                    if widget.current > 0 {
+                        format = Some(Dump::ALL[widget.current - 1]);
+                    }
+                }
+                return Some((range, path, append, format));
+            }
+        }
+        None
+    }
+}
+
+impl DialogHandler for ExportDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_item_change(dialog, item);
+        let has_path = match dialog.get_widget(self.path) {
+            WidgetType::Edit(widget) => !widget.get_value().is_empty(),
+            _ => true,
+        };
+        let is_ok = has_path && self.rctl.get(dialog).is_some();
+        dialog.set_enabled(self.btn_ok, is_ok);
+    }
+
+    fn on_focus_lost(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_focus_lost(dialog, item);
+    }
+}
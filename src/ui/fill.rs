@@ -1,20 +1,42 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
+use super::super::generator::Generator;
+use super::super::hex;
 use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
 use super::range::RangeControl;
-use super::widget::{InputFormat, InputLine, StandardButton, WidgetType};
+use super::widget::{InputFormat, InputLine, ListBox, StandardButton, WidgetType};
 use std::ops::Range;
 
 /// "Fill fange" dialog.
 pub struct FillDialog {
     rctl: RangeControl,
-    pattern: ItemId,
+    source: ItemId,
+    label: ItemId,
+    value: ItemId,
+    width: ItemId,
+    order: ItemId,
+    /// Values of the parameter field for each source.
+    values: Vec<String>,
+    /// Currently selected source.
+    current: usize,
     btn_ok: ItemId,
     btn_cancel: ItemId,
 }
 
 impl FillDialog {
+    /// Names of the fill sources.
+    const SOURCES: &'static [&'static str] = &[
+        "Pattern",
+        "Random",
+        "Counter up",
+        "Counter down",
+        "File",
+        "Clipboard",
+    ];
+    /// Size of the counter units.
+    const WIDTHS: &'static [usize] = &[1, 2, 4, 8];
+
     /// Show the "Fill range" configuration dialog.
     ///
     /// # Arguments
@@ -25,69 +47,150 @@ impl FillDialog {
     ///
     /// # Return value
     ///
-    /// Range and pattern to fill.
-    pub fn show(offset: u64, max: u64, pattern: &[u8]) -> Option<(Range<u64>, Vec<u8>)> {
+    /// Range and source of data to fill.
+    pub fn show(offset: u64, max: u64, pattern: &[u8]) -> Option<(Range<u64>, Generator)> {
         // create dialog
         let mut dlg = Dialog::new(
             RangeControl::DIALOG_WIDTH,
-            6,
+            8,
             DialogType::Normal,
             "Fill range",
         );
 
         // place range control on dialog
         let rctl = RangeControl::create(&mut dlg, offset..offset + 1, max);
-
-        // pattern
         dlg.add_separator();
-        dlg.add_line(WidgetType::StaticText("Fill with pattern:".to_string()));
+
+        // source of data
+        let label = dlg.add_line(WidgetType::StaticText("Fill with:".to_string()));
+        let listbox = ListBox {
+            list: FillDialog::SOURCES
+                .iter()
+                .map(|s| (*s).to_string())
+                .collect(),
+            current: 0,
+        };
+        let source = dlg.add(
+            Dialog::PADDING_X + 12,
+            dlg.get_context(label).y,
+            16,
+            WidgetType::ListBox(listbox),
+        );
+
+        // source parameter
+        let label = dlg.add_line(WidgetType::StaticText(String::new()));
         let text = pattern.iter().map(|b| format!("{:02x}", b)).collect();
         let widget = InputLine::new(
             text,
-            InputFormat::HexStream,
+            InputFormat::Any,
             Vec::new(),
             RangeControl::DIALOG_WIDTH,
         );
-        let pattern = dlg.add_line(WidgetType::Edit(widget));
+        let value = dlg.add_line(WidgetType::Edit(widget));
+
+        // counter unit size and byte order
+        let unit = dlg.add_line(WidgetType::StaticText("Unit:".to_string()));
+        let y = dlg.get_context(unit).y;
+        let listbox = ListBox {
+            list: FillDialog::WIDTHS
+                .iter()
+                .map(|w| format!("{}-bit", w * 8))
+                .collect(),
+            current: 0,
+        };
+        let width = dlg.add(Dialog::PADDING_X + 6, y, 10, WidgetType::ListBox(listbox));
+        dlg.add(
+            Dialog::PADDING_X + 19,
+            y,
+            6,
+            WidgetType::StaticText("Order:".to_string()),
+        );
+        let listbox = ListBox {
+            list: vec!["Little endian".to_string(), "Big endian".to_string()],
+            current: 0,
+        };
+        let order = dlg.add(
+            Dialog::PADDING_X + 26,
+            y,
+            RangeControl::DIALOG_WIDTH - 26,
+            WidgetType::ListBox(listbox),
+        );
 
         // buttons
         let btn_ok = dlg.add_button(StandardButton::OK, true);
         let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
 
         // construct dialog handler
+        let mut values = vec![String::new(); FillDialog::SOURCES.len()];
+        values[1] = "0".to_string();
+        values[2] = "0".to_string();
+        values[3] = "0".to_string();
         let mut handler = Self {
             rctl,
-            pattern,
+            source,
+            label,
+            value,
+            width,
+            order,
+            values,
+            current: 0,
             btn_ok,
             btn_cancel,
         };
+        handler.on_item_change(&mut dlg, handler.source);
 
         // show dialog
         if let Some(id) = dlg.show(&mut handler) {
             if id != handler.btn_cancel {
-                let range = handler.rctl.get(&dlg).unwrap();
-                let pattern = handler.get_pattern(&dlg);
-                return Some((range, pattern));
+                let range = handler.rctl.get(&dlg)?;
+                let generator = handler.get_generator(&dlg)?;
+                return Some((range, generator));
             }
         }
         None
     }
 
-    /// Get current sequence from the pattern field.
-    fn get_pattern(&self, dialog: &Dialog) -> Vec<u8> {
-        if let WidgetType::Edit(widget) = dialog.get_widget(self.pattern) {
-            let mut value = widget.get_value().to_string();
-            if !value.is_empty() {
-                if value.len() % 2 != 0 {
-                    value.push('0');
+    /// Get index of the currently selected item in the list box.
+    fn get_index(dialog: &Dialog, item: ItemId) -> usize {
+        if let WidgetType::ListBox(widget) = dialog.get_widget(item) {
+            widget.current
+        } else {
+            0
+        }
+    }
+
+    /// Get current value of the parameter field.
+    fn get_value(&self, dialog: &Dialog) -> String {
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.value) {
+            widget.get_value().to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Get source of data specified in the dialog fields.
+    fn get_generator(&self, dialog: &Dialog) -> Option<Generator> {
+        let value = self.get_value(dialog);
+        let number = || u64::from_str_radix(value.trim(), 16).ok();
+        match FillDialog::get_index(dialog, self.source) {
+            0 => {
+                let mut text: String = value.split_whitespace().collect();
+                if !text.len().is_multiple_of(2) {
+                    text.push('0');
                 }
-                return (0..value.len())
-                    .step_by(2)
-                    .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
-                    .collect();
+                hex::parse_bytes(&text).map(Generator::Pattern)
             }
+            1 => number().map(Generator::Random),
+            index @ (2 | 3) => Some(Generator::Counter {
+                start: number()?,
+                increment: index == 2,
+                width: FillDialog::WIDTHS[FillDialog::get_index(dialog, self.width)],
+                little_endian: FillDialog::get_index(dialog, self.order) == 0,
+            }),
+            4 if !value.is_empty() => Some(Generator::File(value)),
+            5 => Some(Generator::Clipboard),
+            _ => None,
         }
-        vec![0]
     }
 }
 
@@ -98,22 +201,51 @@ impl DialogHandler for FillDialog {
 
     fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
         self.rctl.on_item_change(dialog, item);
-        dialog.set_enabled(self.btn_ok, self.rctl.get(dialog).is_some());
+
+        // switch the parameter field to the new source
+        let index = FillDialog::get_index(dialog, self.source);
+        if item == self.source {
+            self.values[self.current] = self.get_value(dialog);
+            self.current = index;
+            let value = self.values[index].clone();
+            if let WidgetType::Edit(widget) = dialog.get_widget_mut(self.value) {
+                widget.set_value(value);
+            }
+            let title = match index {
+                0 => "Pattern (hex bytes):",
+                1 => "Seed (hex):",
+                2 | 3 => "Start value (hex):",
+                4 => "File name:",
+                _ => "No parameters",
+            };
+            if let WidgetType::StaticText(text) = dialog.get_widget_mut(self.label) {
+                *text = title.to_string();
+            }
+            dialog.set_enabled(self.value, index != 5);
+            dialog.set_enabled(self.width, index == 2 || index == 3);
+            dialog.set_enabled(self.order, index == 2 || index == 3);
+        }
+
+        let is_ok = self.rctl.get(dialog).is_some() && self.get_generator(dialog).is_some();
+        dialog.set_enabled(self.btn_ok, is_ok);
     }
 
     fn on_focus_lost(&mut self, dialog: &mut Dialog, item: ItemId) {
-        if item == self.pattern {
-            if let WidgetType::Edit(widget) = dialog.get_widget_mut(self.pattern) {
-                let mut value = widget.get_value().to_string();
-                if value.is_empty() {
-                    widget.set_value("00".to_string());
-                } else if value.len() % 2 != 0 {
-                    value.push('0');
-                    widget.set_value(value);
+        if item == self.value {
+            if FillDialog::get_index(dialog, self.source) == 0 {
+                if let WidgetType::Edit(widget) = dialog.get_widget_mut(self.value) {
+                    let mut value = widget.get_value().to_string();
+                    if value.is_empty() {
+                        widget.set_value("00".to_string());
+                    } else if value.len() % 2 != 0 {
+                        value.push('0');
+                        widget.set_value(value);
+                    }
                 }
             }
         } else {
             self.rctl.on_focus_lost(dialog, item);
         }
+        self.on_item_change(dialog, item);
     }
 }
@@ -1,21 +1,27 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
+use super::super::pe::Pe;
 use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
 use super::widget::{InputFormat, InputLine, StandardButton, WidgetType};
 
 /// "Goto" dialog.
-pub struct GotoDialog {
+pub struct GotoDialog<'a> {
     // Current offet (cursor position)
     current: u64,
+    // PE description used for RVA translation.
+    pe: Option<&'a Pe>,
     // Items of the dialog.
     abs_hex: ItemId,
     abs_dec: ItemId,
     rel_hex: ItemId,
     rel_dec: ItemId,
+    rva: Option<ItemId>,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
 }
 
-impl GotoDialog {
+impl<'a> GotoDialog<'a> {
     /// Width of the input fields.
     const INP_WIDTH: usize = 17;
 
@@ -25,13 +31,15 @@ impl GotoDialog {
     ///
     /// * `history` - address history
     /// * `current` - current offset
+    /// * `pe` - PE description to translate relative virtual addresses
     ///
     /// # Return value
     ///
     /// Absolute offset to jump.
-    pub fn show(history: &[u64], current: u64) -> Option<u64> {
+    pub fn show(history: &[u64], current: u64, pe: Option<&'a Pe>) -> Option<u64> {
         // create dialog
-        let mut dlg = Dialog::new(44, 5, DialogType::Normal, "Goto");
+        let height = if pe.is_some() { 8 } else { 5 };
+        let mut dlg = Dialog::new(44, height, DialogType::Normal, "Goto");
 
         dlg.add_line(WidgetType::StaticText("Absolute offset".to_string()));
 
@@ -114,23 +122,50 @@ impl GotoDialog {
             WidgetType::Edit(widget),
         );
 
+        // relative virtual address in hex
+        let rva = if pe.is_some() {
+            dlg.add_separator();
+            dlg.add_line(WidgetType::StaticText(
+                "Relative virtual address (PE)".to_string(),
+            ));
+            dlg.add_line(WidgetType::StaticText("hex:".to_string()));
+            let widget = InputLine::new(
+                String::new(),
+                InputFormat::HexUnsigned,
+                Vec::new(),
+                GotoDialog::INP_WIDTH,
+            );
+            Some(dlg.add(
+                Dialog::PADDING_X + 4,
+                Dialog::PADDING_Y + 7,
+                GotoDialog::INP_WIDTH,
+                WidgetType::Edit(widget),
+            ))
+        } else {
+            None
+        };
+
         // buttons
-        dlg.add_button(StandardButton::OK, true);
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
         let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
 
         // construct dialog handler
         let mut handler = Self {
             current,
+            pe,
             abs_hex,
             abs_dec,
             rel_hex,
             rel_dec,
+            rva,
+            btn_ok,
+            btn_cancel,
         };
         handler.on_item_change(&mut dlg, handler.abs_hex);
 
         // show dialog
         if let Some(id) = dlg.show(&mut handler) {
-            if id != btn_cancel {
+            if id != handler.btn_cancel {
                 if let WidgetType::Edit(widget) = dlg.get_widget(handler.abs_hex) {
                     return Some(u64::from_str_radix(widget.get_value(), 16).unwrap_or(0));
                 }
@@ -154,6 +189,21 @@ impl GotoDialog {
                 offset = u64::from_str_radix(value, 16).unwrap_or(0);
             } else if source == self.abs_dec {
                 offset = value.parse::<u64>().unwrap_or(0);
+            } else if Some(source) == self.rva {
+                let rva = u64::from_str_radix(value, 16).unwrap_or(0);
+                match self.pe.and_then(|pe| pe.rva_to_offset(rva)) {
+                    Some(val) => offset = val,
+                    None => {
+                        // address is not mapped to the file
+                        for item in [self.abs_hex, self.abs_dec, self.rel_hex, self.rel_dec] {
+                            if let WidgetType::Edit(widget) = dialog.get_widget_mut(item) {
+                                widget.set_value(String::new());
+                            }
+                        }
+                        dialog.set_enabled(self.btn_ok, false);
+                        return;
+                    }
+                }
             } else if source == self.rel_hex || source == self.rel_dec {
                 let relative = if source == self.rel_hex {
                     i64::from_str_radix(value, 16).unwrap_or(0)
@@ -173,6 +223,7 @@ impl GotoDialog {
             }
         }
         // update other fields
+        dialog.set_enabled(self.btn_ok, true);
         if source != self.abs_hex {
             if let WidgetType::Edit(widget) = dialog.get_widget_mut(self.abs_hex) {
                 widget.set_value(format!("{:x}", offset));
@@ -197,10 +248,25 @@ impl GotoDialog {
                 widget.set_value(format!("{}", offset));
             }
         }
+        if let Some(rva) = self.rva {
+            if source != rva {
+                let value = self
+                    .pe
+                    .and_then(|pe| pe.offset_to_rva(offset))
+                    .map_or_else(String::new, |rva| format!("{:x}", rva));
+                if let WidgetType::Edit(widget) = dialog.get_widget_mut(rva) {
+                    widget.set_value(value);
+                }
+            }
+        }
     }
 }
 
-impl DialogHandler for GotoDialog {
+impl<'a> DialogHandler for GotoDialog<'a> {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
     fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
         self.update(dialog, item);
     }
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::widget::{InputFormat, InputLine, ListBox, StandardButton, WidgetType};
+
+/// "Import file" dialog: read file contents into the document.
+pub struct ImportDialog {
+    path: ItemId,
+    dump: ItemId,
+    offset: ItemId,
+    mode: ItemId,
+    limit: ItemId,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+/// Import parameters.
+pub struct ImportParams {
+    /// Path to the source file.
+    pub path: String,
+    /// Source file is a text dump (`xxd`, `hexdump -C` or `od`).
+    pub dump: bool,
+    /// Start offset in the document.
+    pub offset: u64,
+    /// Insert data instead of overwriting.
+    pub insert: bool,
+    /// Max number of bytes to import, 0 for the whole file.
+    pub limit: u64,
+}
+
+impl ImportDialog {
+    /// Width of the dialog.
+    const WIDTH: usize = 42;
+
+    /// Show the "Import file" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - default start offset (current position)
+    ///
+    /// # Return value
+    ///
+    /// Import parameters.
+    pub fn show(offset: u64) -> Option<ImportParams> {
+        // create dialog
+        let mut dlg = Dialog::new(ImportDialog::WIDTH, 6, DialogType::Normal, "Import file");
+
+        // source file
+        dlg.add_line(WidgetType::StaticText("File name:".to_string()));
+        let widget = InputLine::new(
+            String::new(),
+            InputFormat::Any,
+            Vec::new(),
+            ImportDialog::WIDTH,
+        );
+        let path = dlg.add_line(WidgetType::Edit(widget));
+        let label = dlg.add_line(WidgetType::StaticText("Format:".to_string()));
+        let listbox = ListBox {
+            list: vec!["Raw binary".to_string(), "Text hex dump".to_string()],
+            current: 0,
+        };
+        let dump = dlg.add(
+            Dialog::PADDING_X + 8,
+            dlg.get_context(label).y,
+            15,
+            WidgetType::ListBox(listbox),
+        );
+        dlg.add_separator();
+
+        // mode and start offset
+        let listbox = ListBox {
+            list: vec!["Overwrite".to_string(), "Insert".to_string()],
+            current: 0,
+        };
+        let mode = dlg.add(
+            Dialog::PADDING_X,
+            Dialog::PADDING_Y + 4,
+            13,
+            WidgetType::ListBox(listbox),
+        );
+        dlg.add(
+            Dialog::PADDING_X + 14,
+            Dialog::PADDING_Y + 4,
+            15,
+            WidgetType::StaticText("at offset".to_string()),
+        );
+        let widget = InputLine::new(
+            format!("{:x}", offset),
+            InputFormat::HexUnsigned,
+            Vec::new(),
+            12,
+        );
+        let offset = dlg.add(
+            Dialog::PADDING_X + 30,
+            Dialog::PADDING_Y + 4,
+            12,
+            WidgetType::Edit(widget),
+        );
+
+        // max number of bytes
+        dlg.add(
+            Dialog::PADDING_X,
+            Dialog::PADDING_Y + 5,
+            29,
+            WidgetType::StaticText("Max bytes (0 for whole file):".to_string()),
+        );
+        let widget = InputLine::new("0".to_string(), InputFormat::DecUnsigned, Vec::new(), 12);
+        let limit = dlg.add(
+            Dialog::PADDING_X + 30,
+            Dialog::PADDING_Y + 5,
+            12,
+            WidgetType::Edit(widget),
+        );
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            path,
+            dump,
+            offset,
+            mode,
+            limit,
+            btn_ok,
+            btn_cancel,
+        };
+        handler.on_item_change(&mut dlg, handler.path);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                return Some(handler.get_params(&dlg));
+            }
+        }
+        None
+    }
+
+    /// Get import parameters from the dialog fields.
+    fn get_params(&self, dialog: &Dialog) -> ImportParams {
+        let mut params = ImportParams {
+            path: String::new(),
+            dump: false,
+            offset: 0,
+            insert: false,
+            limit: 0,
+        };
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.path) {
+            params.path = widget.get_value().to_string();
+        }
+        if let WidgetType::ListBox(widget) = dialog.get_widget(self.dump) {
+            params.dump = widget.current == 1;
+        }
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.offset) {
+            params.offset = u64::from_str_radix(widget.get_value(), 16).unwrap_or(0);
+        }
+        if let WidgetType::ListBox(widget) = dialog.get_widget(self.mode) {
+            params.insert = widget.current == 1;
+        }
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.limit) {
+            params.limit = widget.get_value().parse::<u64>().unwrap_or(0);
+        }
+        params
+    }
+}
+
+impl DialogHandler for ImportDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        if item == self.path {
+            let is_ok = match dialog.get_widget(self.path) {
+                WidgetType::Edit(widget) => !widget.get_value().is_empty(),
+                _ => true,
+            };
+            dialog.set_enabled(self.btn_ok, is_ok);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
+use super::super::hex;
 use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
 use super::widget::{InputFormat, InputLine, StandardButton, WidgetType};
 
@@ -117,10 +118,7 @@ impl InsertDialog {
                 if value.len() % 2 != 0 {
                     value.push('0');
                 }
-                return (0..value.len())
-                    .step_by(2)
-                    .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
-                    .collect();
+                return hex::parse_bytes(&value).unwrap_or_default();
             }
         }
         vec![0]
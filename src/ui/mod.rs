@@ -1,15 +1,25 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
+pub mod changes;
+pub mod checksum;
 pub mod cut;
 pub mod dialog;
+pub mod export;
 pub mod fill;
 pub mod goto;
+pub mod import;
 pub mod insert;
 pub mod messagebox;
+pub mod patch;
 pub mod progress;
 pub mod range;
 pub mod saveas;
 pub mod search;
 pub mod setup;
+pub mod stats;
+pub mod strings;
+pub mod template;
+pub mod transform;
+pub mod tree;
 pub mod widget;
@@ -0,0 +1,195 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::super::patch::PatchFormat;
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::widget::{InputFormat, InputLine, ListBox, StandardButton, WidgetType};
+use std::path::Path;
+
+/// "Create patch" and "Apply patch" dialogs.
+pub struct PatchDialog {
+    path: ItemId,
+    format: Option<ItemId>,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+impl PatchDialog {
+    /// Width of the dialog.
+    const WIDTH: usize = 40;
+
+    /// Show the "Create patch" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `default` - default file name without extension
+    /// * `others` - other documents that can be used as the patch source
+    ///
+    /// # Return value
+    ///
+    /// Index of the source document (`None` for pending changes), patch
+    /// format and file name.
+    pub fn create(
+        default: &str,
+        others: &[(usize, String)],
+    ) -> Option<(Option<usize>, PatchFormat, String)> {
+        // create dialog
+        let mut dlg = Dialog::new(PatchDialog::WIDTH, 6, DialogType::Normal, "Create patch");
+
+        // patch source: pending changes or diff with another document
+        let label = dlg.add_line(WidgetType::StaticText("Source:".to_string()));
+        let mut list = vec!["Pending changes".to_string()];
+        list.extend(others.iter().map(|(_, path)| {
+            let name = Path::new(path)
+                .file_name()
+                .map_or_else(|| path.clone(), |n| n.to_string_lossy().to_string());
+            format!("Diff with {}", name)
+        }));
+        let listbox = ListBox { list, current: 0 };
+        let source = dlg.add(
+            Dialog::PADDING_X + 8,
+            dlg.get_context(label).y,
+            PatchDialog::WIDTH - 8,
+            WidgetType::ListBox(listbox),
+        );
+
+        // patch format
+        let label = dlg.add_line(WidgetType::StaticText("Format:".to_string()));
+        let listbox = ListBox {
+            list: PatchFormat::ALL
+                .iter()
+                .map(|f| f.name().to_string())
+                .collect(),
+            current: 0,
+        };
+        let format = dlg.add(
+            Dialog::PADDING_X + 8,
+            dlg.get_context(label).y,
+            7,
+            WidgetType::ListBox(listbox),
+        );
+        dlg.add_separator();
+
+        // target file
+        dlg.add_line(WidgetType::StaticText("File name:".to_string()));
+        let edit = InputLine::new(
+            format!("{}.{}", default, PatchFormat::ALL[0].id()),
+            InputFormat::Any,
+            Vec::new(),
+            PatchDialog::WIDTH,
+        );
+        let path = dlg.add_line(WidgetType::Edit(edit));
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            path,
+            format: Some(format),
+            btn_ok,
+            btn_cancel,
+        };
+        handler.on_item_change(&mut dlg, handler.path);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                let mut index = None;
+                if let WidgetType::ListBox(widget) = dlg.get_widget(source) {
+                    if widget.current > 0 {
+                        index = Some(others[widget.current - 1].0);
+                    }
+                }
+                let format = handler.get_format(&dlg);
+                return Some((index, format, handler.get_path(&dlg)));
+            }
+        }
+        None
+    }
+
+    /// Show the "Apply patch" dialog.
+    ///
+    /// # Return value
+    ///
+    /// Path to the patch file.
+    pub fn apply() -> Option<String> {
+        // create dialog
+        let mut dlg = Dialog::new(PatchDialog::WIDTH, 2, DialogType::Normal, "Apply patch");
+        dlg.add_line(WidgetType::StaticText(
+            "Patch file (IPS, BPS, UPS, text list):".to_string(),
+        ));
+        let edit = InputLine::new(
+            String::new(),
+            InputFormat::Any,
+            Vec::new(),
+            PatchDialog::WIDTH,
+        );
+        let path = dlg.add_line(WidgetType::Edit(edit));
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            path,
+            format: None,
+            btn_ok,
+            btn_cancel,
+        };
+        handler.on_item_change(&mut dlg, handler.path);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                return Some(handler.get_path(&dlg));
+            }
+        }
+        None
+    }
+
+    /// Get currently selected patch format.
+    fn get_format(&self, dialog: &Dialog) -> PatchFormat {
+        if let Some(format) = self.format {
+            if let WidgetType::ListBox(widget) = dialog.get_widget(format) {
+                return PatchFormat::ALL[widget.current];
+            }
+        }
+        PatchFormat::ALL[0]
+    }
+
+    /// Get file path.
+    fn get_path(&self, dialog: &Dialog) -> String {
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.path) {
+            widget.get_value().to_string()
+        } else {
+            String::new()
+        }
+    }
+}
+
+impl DialogHandler for PatchDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        if Some(item) == self.format {
+            // change extension of the file name
+            let format = self.get_format(dialog);
+            let path = self.get_path(dialog);
+            if let Some((name, ext)) = path.rsplit_once('.') {
+                if PatchFormat::ALL.iter().any(|f| f.id() == ext) {
+                    let path = format!("{}.{}", name, format.id());
+                    if let WidgetType::Edit(widget) = dialog.get_widget_mut(self.path) {
+                        widget.set_value(path);
+                    }
+                }
+            }
+        }
+        let is_ok = !self.get_path(dialog).is_empty();
+        dialog.set_enabled(self.btn_ok, is_ok);
+    }
+}
@@ -1,12 +1,15 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
+use super::super::image::Format;
 use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
-use super::widget::{InputFormat, InputLine, StandardButton, WidgetType};
+use super::widget::{InputFormat, InputLine, ListBox, StandardButton, WidgetType};
 
 /// "Save as" dialog.
 pub struct SaveAsDialog {
     path: ItemId,
+    format: ItemId,
+    fill: ItemId,
     btn_ok: ItemId,
     btn_cancel: ItemId,
 }
@@ -14,23 +17,56 @@ pub struct SaveAsDialog {
 impl SaveAsDialog {
     /// Width of the dialog.
     const WIDTH: usize = 40;
+    /// Available file formats.
+    const FORMATS: &'static [Format] = &[Format::Raw, Format::IntelHex, Format::SRecord];
 
     /// Show the "Save As" dialog.
     ///
     /// # Arguments
     ///
     /// * `default` - default file name
+    /// * `format` - default file format
     ///
     /// # Return value
     ///
-    /// New file name.
-    pub fn show(default: String) -> Option<String> {
+    /// New file name, its format and value of unused bytes (fill).
+    pub fn show(default: String, format: Format) -> Option<(String, Format, u8)> {
         // create dialog
-        let mut dlg = Dialog::new(SaveAsDialog::WIDTH, 2, DialogType::Normal, "Save as");
+        let mut dlg = Dialog::new(SaveAsDialog::WIDTH, 5, DialogType::Normal, "Save as");
         // file path input
         dlg.add_line(WidgetType::StaticText("File name:".to_string()));
         let edit = InputLine::new(default, InputFormat::Any, Vec::new(), SaveAsDialog::WIDTH);
         let path = dlg.add_line(WidgetType::Edit(edit));
+        dlg.add_separator();
+
+        // file format
+        dlg.add_line(WidgetType::StaticText("Format:".to_string()));
+        let listbox = ListBox {
+            list: SaveAsDialog::FORMATS
+                .iter()
+                .map(|f| f.name().to_string())
+                .collect(),
+            current: SaveAsDialog::FORMATS
+                .iter()
+                .position(|&f| f == format)
+                .unwrap_or(0),
+        };
+        let format = dlg.add(
+            Dialog::PADDING_X + 11,
+            Dialog::PADDING_Y + 3,
+            21,
+            WidgetType::ListBox(listbox),
+        );
+
+        // fill byte for gaps
+        dlg.add_line(WidgetType::StaticText("Fill byte:".to_string()));
+        let edit = InputLine::new("ff".to_string(), InputFormat::HexStream, Vec::new(), 2);
+        let fill = dlg.add(
+            Dialog::PADDING_X + 11,
+            Dialog::PADDING_Y + 4,
+            2,
+            WidgetType::Edit(edit),
+        );
         // buttons
         let btn_ok = dlg.add_button(StandardButton::OK, true);
         let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
@@ -38,6 +74,8 @@ impl SaveAsDialog {
         // construct dialog handler
         let mut handler = Self {
             path,
+            format,
+            fill,
             btn_ok,
             btn_cancel,
         };
@@ -46,8 +84,16 @@ impl SaveAsDialog {
         // show dialog
         if let Some(id) = dlg.show(&mut handler) {
             if id != handler.btn_cancel {
+                let mut format = Format::Raw;
+                if let WidgetType::ListBox(widget) = dlg.get_widget(handler.format) {
+                    format = SaveAsDialog::FORMATS[widget.current];
+                }
+                let mut fill = 0xff;
+                if let WidgetType::Edit(widget) = dlg.get_widget(handler.fill) {
+                    fill = u8::from_str_radix(widget.get_value(), 16).unwrap_or(fill);
+                }
                 if let WidgetType::Edit(widget) = dlg.get_widget(handler.path) {
-                    return Some(widget.get_value().to_string());
+                    return Some((widget.get_value().to_string(), format, fill));
                 }
             }
         }
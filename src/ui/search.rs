@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: MIT
 // Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
 
+use super::super::hex;
 use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
 use super::widget::{CheckBox, InputFormat, InputLine, StandardButton, WidgetType};
 
@@ -105,12 +106,7 @@ impl SearchDialog {
                 if value.len() % 2 != 0 {
                     value.push('0');
                 }
-                result = Some(
-                    (0..value.len())
-                        .step_by(2)
-                        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
-                        .collect(),
-                );
+                result = hex::parse_bytes(&value);
             }
         }
         result
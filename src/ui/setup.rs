@@ -3,6 +3,7 @@
 
 use super::super::ascii;
 use super::super::config::Config;
+use super::super::minimap::MapMode;
 use super::dialog::{Dialog, DialogType};
 use super::widget::{CheckBox, ListBox, StandardButton, WidgetType};
 
@@ -21,7 +22,7 @@ impl SetupDialog {
     /// true if settings were changed
     pub fn show(config: &mut Config) -> bool {
         // create dialog
-        let mut dlg = Dialog::new(27, 4, DialogType::Normal, "Setup");
+        let mut dlg = Dialog::new(27, 7, DialogType::Normal, "Setup");
 
         // fixed width setup
         let checkbox = CheckBox {
@@ -29,6 +30,13 @@ impl SetupDialog {
             title: "Fixed width (16 bytes)".to_string(),
         };
         let fixed = dlg.add_line(WidgetType::CheckBox(checkbox));
+
+        // byte class colors
+        let checkbox = CheckBox {
+            state: config.byte_colors,
+            title: "Color bytes by class".to_string(),
+        };
+        let colors = dlg.add_line(WidgetType::CheckBox(checkbox));
         dlg.add_separator();
 
         // ASCII encoding
@@ -50,6 +58,19 @@ impl SetupDialog {
         };
         let ascii = dlg.add_line(WidgetType::ListBox(listbox));
 
+        // minimap mode
+        dlg.add_line(WidgetType::StaticText("Minimap:".to_string()));
+        let mut modes = vec!["None (hide)".to_string()];
+        modes.extend(MapMode::ALL.iter().map(|m| m.name().to_string()));
+        let listbox = ListBox {
+            list: modes,
+            current: config
+                .minimap
+                .and_then(|mode| MapMode::ALL.iter().position(|&m| m == mode))
+                .map_or(0, |index| index + 1 /* "None (hide)" */),
+        };
+        let minimap = dlg.add_line(WidgetType::ListBox(listbox));
+
         // buttons
         dlg.add_button(StandardButton::OK, true);
         let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
@@ -60,6 +81,9 @@ impl SetupDialog {
                 if let WidgetType::CheckBox(widget) = dlg.get_widget(fixed) {
                     config.fixed_width = widget.state;
                 }
+                if let WidgetType::CheckBox(widget) = dlg.get_widget(colors) {
+                    config.byte_colors = widget.state;
+                }
                 if let WidgetType::ListBox(widget) = dlg.get_widget(ascii) {
                     config.ascii_table = if widget.current == 0 {
                         None
@@ -67,6 +91,13 @@ impl SetupDialog {
                         ascii::TABLES.get(widget.current - 1)
                     }
                 }
+                if let WidgetType::ListBox(widget) = dlg.get_widget(minimap) {
+                    config.minimap = if widget.current == 0 {
+                        None
+                    } else {
+                        MapMode::ALL.get(widget.current - 1).copied()
+                    }
+                }
                 return true;
             }
         }
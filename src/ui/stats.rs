@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::super::histogram::Histogram;
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::range::RangeControl;
+use super::widget::{InputFormat, InputLine, StandardButton, TextView, WidgetType};
+use std::ops::Range;
+
+/// "Statistics" dialogs: range selection and byte histogram.
+pub struct StatsDialog {
+    rctl: RangeControl,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+impl StatsDialog {
+    /// Width of the histogram dialog.
+    const WIDTH: usize = 60;
+    /// Number of visible lines of the histogram.
+    const LINES: usize = 12;
+    /// Max width of the histogram bar.
+    const BAR_WIDTH: usize = 20;
+
+    /// Show the range selection dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - max offset (file size)
+    ///
+    /// # Return value
+    ///
+    /// Range to process.
+    pub fn range(max: u64) -> Option<Range<u64>> {
+        // create dialog
+        let mut dlg = Dialog::new(
+            RangeControl::DIALOG_WIDTH,
+            2,
+            DialogType::Normal,
+            "Statistics",
+        );
+
+        // place range control on dialog
+        let rctl = RangeControl::create(&mut dlg, 0..max, max);
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            rctl,
+            btn_ok,
+            btn_cancel,
+        };
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                return handler.rctl.get(&dlg);
+            }
+        }
+        None
+    }
+
+    /// Show the byte histogram.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - processed range
+    /// * `hist` - histogram of the range
+    /// * `default` - default name of the CSV file
+    ///
+    /// # Return value
+    ///
+    /// Name of the file to export the histogram as CSV.
+    pub fn show(range: &Range<u64>, hist: &Histogram, default: String) -> Option<String> {
+        let width = StatsDialog::WIDTH.min(Dialog::max_width());
+        let mut dlg = Dialog::new(
+            width,
+            StatsDialog::LINES + 7,
+            DialogType::Normal,
+            "Statistics",
+        );
+
+        // summary
+        dlg.add_line(WidgetType::StaticText(format!(
+            "Range: 0x{:x}-0x{:x} ({} bytes)",
+            range.start,
+            range.end - 1,
+            hist.total
+        )));
+        dlg.add_line(WidgetType::StaticText(format!(
+            "Distinct values: {}, entropy: {:.4} bits per byte",
+            hist.distinct(),
+            hist.entropy()
+        )));
+        let top: Vec<String> = hist
+            .top(Histogram::TOP)
+            .into_iter()
+            .map(|byte| format!("{:02x} ({:.2}%)", byte, hist.percent(byte)))
+            .collect();
+        dlg.add_line(WidgetType::StaticText(format!(
+            "Most frequent: {}",
+            top.join(", ")
+        )));
+        dlg.add_separator();
+
+        // histogram
+        dlg.add_line(WidgetType::StaticText(
+            "Value Char        Count  Percent".to_string(),
+        ));
+        let max = hist.counts.iter().max().copied().unwrap_or(0).max(1);
+        let lines = (0..=u8::MAX)
+            .map(|byte| {
+                let count = hist.counts[byte as usize];
+                #[allow(clippy::cast_possible_truncation)]
+                let bar = (u128::from(count) * StatsDialog::BAR_WIDTH as u128)
+                    .div_ceil(u128::from(max)) as usize;
+                format!(
+                    "  {:02x}   {}  {:>12}  {:>6.2}%  {}",
+                    byte,
+                    if byte.is_ascii_graphic() {
+                        byte as char
+                    } else {
+                        '.'
+                    },
+                    count,
+                    hist.percent(byte),
+                    "\u{2588}".repeat(bar)
+                )
+            })
+            .collect();
+        dlg.add_line(WidgetType::TextView(TextView {
+            lines,
+            top: 0,
+            height: StatsDialog::LINES,
+            current: None,
+        }));
+        dlg.add_separator();
+
+        // CSV export
+        let label = dlg.add_line(WidgetType::StaticText("CSV file:".to_string()));
+        let widget = InputLine::new(default, InputFormat::Any, Vec::new(), width - 10);
+        let path = dlg.add(
+            Dialog::PADDING_X + 10,
+            dlg.get_context(label).y,
+            width - 10,
+            WidgetType::Edit(widget),
+        );
+
+        // buttons
+        let btn_export = dlg.add_button(StandardButton::Export, false);
+        dlg.add_button(StandardButton::Close, true);
+
+        if dlg.show_unmanaged() == Some(btn_export) {
+            if let WidgetType::Edit(widget) = dlg.get_widget(path) {
+                let name = widget.get_value().trim();
+                if !name.is_empty() {
+                    return Some(name.to_string());
+                }
+            }
+        }
+        None
+    }
+}
+
+impl DialogHandler for StatsDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_item_change(dialog, item);
+        let is_ok = self.rctl.get(dialog).is_some();
+        dialog.set_enabled(self.btn_ok, is_ok);
+    }
+
+    fn on_focus_lost(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_focus_lost(dialog, item);
+    }
+}
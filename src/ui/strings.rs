@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::super::strings::Options;
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::range::RangeControl;
+use super::widget::{CheckBox, InputFormat, InputLine, StandardButton, TextView, WidgetType};
+use std::ops::Range;
+
+/// "Strings" dialog: options to extract printable strings.
+pub struct StringsDialog {
+    rctl: RangeControl,
+    min_length: ItemId,
+    utf8: ItemId,
+    utf16le: ItemId,
+    utf16be: ItemId,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+impl StringsDialog {
+    /// Show the "Strings" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - max offset (file size)
+    /// * `options` - default options
+    ///
+    /// # Return value
+    ///
+    /// Range to scan and string options.
+    pub fn show(max: u64, options: Options) -> Option<(Range<u64>, Options)> {
+        // create dialog
+        let mut dlg = Dialog::new(RangeControl::DIALOG_WIDTH, 7, DialogType::Normal, "Strings");
+
+        // place range control on dialog
+        let rctl = RangeControl::create(&mut dlg, 0..max, max);
+        dlg.add_separator();
+
+        // min length
+        let label = dlg.add_line(WidgetType::StaticText("Min length:".to_string()));
+        let widget = InputLine::new(
+            options.min_length.to_string(),
+            InputFormat::DecUnsigned,
+            Vec::new(),
+            5,
+        );
+        let min_length = dlg.add(
+            Dialog::PADDING_X + 12,
+            dlg.get_context(label).y,
+            5,
+            WidgetType::Edit(widget),
+        );
+
+        // encodings
+        let mut checkbox = |title: &str, state: bool| {
+            dlg.add_line(WidgetType::CheckBox(CheckBox {
+                state,
+                title: title.to_string(),
+            }))
+        };
+        let utf8 = checkbox("UTF-8", options.utf8);
+        let utf16le = checkbox("UTF-16LE", options.utf16le);
+        let utf16be = checkbox("UTF-16BE", options.utf16be);
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            rctl,
+            min_length,
+            utf8,
+            utf16le,
+            utf16be,
+            btn_ok,
+            btn_cancel,
+        };
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                let range = handler.rctl.get(&dlg)?;
+                let options = Options {
+                    min_length: handler.get_min_length(&dlg)?,
+                    utf8: handler.is_checked(&dlg, handler.utf8),
+                    utf16le: handler.is_checked(&dlg, handler.utf16le),
+                    utf16be: handler.is_checked(&dlg, handler.utf16be),
+                };
+                return Some((range, options));
+            }
+        }
+        None
+    }
+
+    /// Get min length of the string.
+    fn get_min_length(&self, dialog: &Dialog) -> Option<usize> {
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.min_length) {
+            widget
+                .get_value()
+                .parse::<usize>()
+                .ok()
+                .filter(|&len| len > 0)
+        } else {
+            None
+        }
+    }
+
+    /// Get state of the check box.
+    fn is_checked(&self, dialog: &Dialog, item: ItemId) -> bool {
+        if let WidgetType::CheckBox(widget) = dialog.get_widget(item) {
+            widget.state
+        } else {
+            false
+        }
+    }
+}
+
+impl DialogHandler for StringsDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_item_change(dialog, item);
+        let is_ok = self.rctl.get(dialog).is_some() && self.get_min_length(dialog).is_some();
+        dialog.set_enabled(self.btn_ok, is_ok);
+    }
+
+    fn on_focus_lost(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_focus_lost(dialog, item);
+    }
+}
+
+/// Filterable list of the found strings.
+pub struct StringListDialog {
+    /// Text representation of all strings.
+    lines: Vec<String>,
+    /// Indices of the strings matching the filter.
+    visible: Vec<usize>,
+    filter: ItemId,
+    view: ItemId,
+    btn_goto: ItemId,
+    btn_close: ItemId,
+}
+
+impl StringListDialog {
+    /// Number of visible lines of the list.
+    const HEIGHT: usize = 16;
+    /// Width of the dialog.
+    const WIDTH: usize = 70;
+
+    /// Show the list of strings.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - text representation of the strings
+    /// * `current` - index of the initially selected string
+    ///
+    /// # Return value
+    ///
+    /// Index of the string to go to.
+    pub fn show(lines: Vec<String>, current: usize) -> Option<usize> {
+        debug_assert!(current < lines.len());
+
+        let title = format!("Strings found: {}", lines.len());
+        let width = StringListDialog::WIDTH.min(Dialog::max_width());
+        let height = lines.len().min(StringListDialog::HEIGHT);
+
+        // create dialog
+        let mut dlg = Dialog::new(width, height + 2, DialogType::Normal, &title);
+        let label = dlg.add_line(WidgetType::StaticText("Filter:".to_string()));
+        let widget = InputLine::new(String::new(), InputFormat::Any, Vec::new(), width - 8);
+        let filter = dlg.add(
+            Dialog::PADDING_X + 8,
+            dlg.get_context(label).y,
+            width - 8,
+            WidgetType::Edit(widget),
+        );
+        dlg.add_separator();
+        let mut widget = TextView {
+            lines: lines.clone(),
+            top: 0,
+            height,
+            current: None,
+        };
+        widget.select(current);
+        let view = dlg.add_line(WidgetType::TextView(widget));
+
+        // buttons
+        let btn_goto = dlg.add_button(StandardButton::Goto, true);
+        let btn_close = dlg.add_button(StandardButton::Close, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            visible: (0..lines.len()).collect(),
+            lines,
+            filter,
+            view,
+            btn_goto,
+            btn_close,
+        };
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_close {
+                return handler.get_current(&dlg);
+            }
+        }
+        None
+    }
+
+    /// Get index of the currently selected string.
+    fn get_current(&self, dialog: &Dialog) -> Option<usize> {
+        if let WidgetType::TextView(widget) = dialog.get_widget(self.view) {
+            widget
+                .current
+                .and_then(|index| self.visible.get(index))
+                .copied()
+        } else {
+            None
+        }
+    }
+}
+
+impl DialogHandler for StringListDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_close
+            || ((item == self.view || item == self.btn_goto) && !self.visible.is_empty())
+            || (item == self.filter && dialog.get_context(self.btn_goto).enabled)
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        if item != self.filter {
+            return;
+        }
+        let filter = if let WidgetType::Edit(widget) = dialog.get_widget(self.filter) {
+            widget.get_value().to_lowercase()
+        } else {
+            String::new()
+        };
+        self.visible = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.to_lowercase().contains(&filter))
+            .map(|(index, _)| index)
+            .collect();
+        let lines = self
+            .visible
+            .iter()
+            .map(|&i| self.lines[i].clone())
+            .collect();
+        if let WidgetType::TextView(widget) = dialog.get_widget_mut(self.view) {
+            widget.lines = lines;
+            widget.top = 0;
+            widget.select(0);
+        }
+        dialog.set_enabled(self.btn_goto, !self.visible.is_empty());
+    }
+}
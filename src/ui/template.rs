@@ -0,0 +1,209 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::super::template::Field;
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::widget::{InputFormat, InputLine, ListBox, StandardButton, WidgetType};
+
+/// "Apply template" dialog.
+pub struct TemplateDialog {
+    template: ItemId,
+    offset: ItemId,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+impl TemplateDialog {
+    /// Width of the dialog.
+    const WIDTH: usize = 43;
+
+    /// Show the "Apply template" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - names of the available templates
+    /// * `current` - index of the currently applied template
+    /// * `offset` - default start offset
+    ///
+    /// # Return value
+    ///
+    /// Index of the template (`None` to remove the current one) and start
+    /// offset.
+    pub fn show(
+        names: &[String],
+        current: Option<usize>,
+        offset: u64,
+    ) -> Option<(Option<usize>, u64)> {
+        // create dialog
+        let mut dlg = Dialog::new(
+            TemplateDialog::WIDTH,
+            2,
+            DialogType::Normal,
+            "Apply template",
+        );
+
+        // template name
+        let label = dlg.add_line(WidgetType::StaticText("Template:".to_string()));
+        let mut list = vec!["None".to_string()];
+        list.extend(names.iter().cloned());
+        let listbox = ListBox {
+            list,
+            current: current.map_or(0, |index| index + 1),
+        };
+        let template = dlg.add(
+            Dialog::PADDING_X + 10,
+            dlg.get_context(label).y,
+            TemplateDialog::WIDTH - 10,
+            WidgetType::ListBox(listbox),
+        );
+
+        // start offset
+        let label = dlg.add_line(WidgetType::StaticText("Offset:".to_string()));
+        let widget = InputLine::new(
+            format!("{:x}", offset),
+            InputFormat::HexUnsigned,
+            Vec::new(),
+            TemplateDialog::WIDTH - 10,
+        );
+        let offset = dlg.add(
+            Dialog::PADDING_X + 10,
+            dlg.get_context(label).y,
+            TemplateDialog::WIDTH - 10,
+            WidgetType::Edit(widget),
+        );
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            template,
+            offset,
+            btn_ok,
+            btn_cancel,
+        };
+        handler.on_item_change(&mut dlg, handler.template);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                let index = handler.get_template(&dlg);
+                let offset = handler.get_offset(&dlg).unwrap_or(0);
+                return Some((index, offset));
+            }
+        }
+        None
+    }
+
+    /// Get index of the currently selected template.
+    fn get_template(&self, dialog: &Dialog) -> Option<usize> {
+        if let WidgetType::ListBox(widget) = dialog.get_widget(self.template) {
+            widget.current.checked_sub(1)
+        } else {
+            None
+        }
+    }
+
+    /// Get start offset.
+    fn get_offset(&self, dialog: &Dialog) -> Option<u64> {
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.offset) {
+            u64::from_str_radix(widget.get_value(), 16).ok()
+        } else {
+            None
+        }
+    }
+}
+
+impl DialogHandler for TemplateDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, _item: ItemId) {
+        let selected = self.get_template(dialog).is_some();
+        dialog.set_enabled(self.offset, selected);
+        let is_ok = !selected || self.get_offset(dialog).is_some();
+        dialog.set_enabled(self.btn_ok, is_ok);
+    }
+}
+
+/// "Edit field" dialog.
+pub struct FieldDialog<'a> {
+    field: &'a Field,
+    value: ItemId,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+impl<'a> FieldDialog<'a> {
+    /// Width of the dialog.
+    const WIDTH: usize = 43;
+
+    /// Show the "Edit field" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - field to edit
+    ///
+    /// # Return value
+    ///
+    /// New data of the field.
+    pub fn show(field: &'a Field) -> Option<Vec<u8>> {
+        // create dialog
+        let mut dlg = Dialog::new(FieldDialog::WIDTH, 2, DialogType::Normal, "Edit field");
+
+        dlg.add_line(WidgetType::StaticText(format!(
+            "{} {}:",
+            field.type_name, field.name
+        )));
+        let widget = InputLine::new(
+            field.edit_value(),
+            InputFormat::Any,
+            Vec::new(),
+            FieldDialog::WIDTH,
+        );
+        let value = dlg.add_line(WidgetType::Edit(widget));
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            field,
+            value,
+            btn_ok,
+            btn_cancel,
+        };
+        handler.on_item_change(&mut dlg, handler.value);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                return handler.get_data(&dlg);
+            }
+        }
+        None
+    }
+
+    /// Get new field data from the value.
+    fn get_data(&self, dialog: &Dialog) -> Option<Vec<u8>> {
+        if let WidgetType::Edit(widget) = dialog.get_widget(self.value) {
+            self.field.encode(widget.get_value())
+        } else {
+            None
+        }
+    }
+}
+
+impl DialogHandler for FieldDialog<'_> {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, _item: ItemId) {
+        let is_ok = self.get_data(dialog).is_some();
+        dialog.set_enabled(self.btn_ok, is_ok);
+    }
+}
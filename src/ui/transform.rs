@@ -0,0 +1,190 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::super::transform::{Operation, Transform};
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::range::RangeControl;
+use super::widget::{InputFormat, InputLine, ListBox, StandardButton, WidgetType};
+use std::ops::Range;
+
+/// "Transform range" dialog.
+pub struct TransformDialog {
+    rctl: RangeControl,
+    operation: ItemId,
+    label: ItemId,
+    operand: ItemId,
+    width: ItemId,
+    order: ItemId,
+    message: ItemId,
+    btn_ok: ItemId,
+    btn_cancel: ItemId,
+}
+
+impl TransformDialog {
+    /// Show the "Transform range" dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - default start offset (current position)
+    /// * `max` - max offset (file size)
+    ///
+    /// # Return value
+    ///
+    /// Range and transform to apply.
+    pub fn show(offset: u64, max: u64) -> Option<(Range<u64>, Transform)> {
+        // create dialog
+        let mut dlg = Dialog::new(
+            RangeControl::DIALOG_WIDTH,
+            9,
+            DialogType::Normal,
+            "Transform range",
+        );
+
+        // place range control on dialog
+        let rctl = RangeControl::create(&mut dlg, offset..offset + 1, max);
+        dlg.add_separator();
+
+        // operation
+        let label = dlg.add_line(WidgetType::StaticText("Operation:".to_string()));
+        let listbox = ListBox {
+            list: Operation::NAMES.iter().map(|n| (*n).to_string()).collect(),
+            current: 0,
+        };
+        let operation = dlg.add(
+            Dialog::PADDING_X + 12,
+            dlg.get_context(label).y,
+            17,
+            WidgetType::ListBox(listbox),
+        );
+
+        // operand
+        let label = dlg.add_line(WidgetType::StaticText(String::new()));
+        let widget = InputLine::new(
+            String::new(),
+            InputFormat::Any,
+            Vec::new(),
+            RangeControl::DIALOG_WIDTH,
+        );
+        let operand = dlg.add_line(WidgetType::Edit(widget));
+
+        // unit size and byte order
+        let unit = dlg.add_line(WidgetType::StaticText("Unit:".to_string()));
+        let y = dlg.get_context(unit).y;
+        let listbox = ListBox {
+            list: Transform::WIDTHS
+                .iter()
+                .map(|w| format!("{}-bit", w * 8))
+                .collect(),
+            current: 0,
+        };
+        let width = dlg.add(Dialog::PADDING_X + 6, y, 10, WidgetType::ListBox(listbox));
+        dlg.add(
+            Dialog::PADDING_X + 19,
+            y,
+            6,
+            WidgetType::StaticText("Order:".to_string()),
+        );
+        let listbox = ListBox {
+            list: vec!["Little endian".to_string(), "Big endian".to_string()],
+            current: 0,
+        };
+        let order = dlg.add(
+            Dialog::PADDING_X + 26,
+            y,
+            RangeControl::DIALOG_WIDTH - 26,
+            WidgetType::ListBox(listbox),
+        );
+
+        // error message
+        let message = dlg.add_line(WidgetType::StaticText(String::new()));
+
+        // buttons
+        let btn_ok = dlg.add_button(StandardButton::OK, true);
+        let btn_cancel = dlg.add_button(StandardButton::Cancel, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            rctl,
+            operation,
+            label,
+            operand,
+            width,
+            order,
+            message,
+            btn_ok,
+            btn_cancel,
+        };
+        handler.on_item_change(&mut dlg, handler.operation);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            if id != handler.btn_cancel {
+                let range = handler.rctl.get(&dlg)?;
+                let transform = handler.get_transform(&dlg)?;
+                return Some((range, transform));
+            }
+        }
+        None
+    }
+
+    /// Get index of the currently selected item in the list box.
+    fn get_index(dialog: &Dialog, item: ItemId) -> usize {
+        if let WidgetType::ListBox(widget) = dialog.get_widget(item) {
+            widget.current
+        } else {
+            0
+        }
+    }
+
+    /// Get transform specified in the dialog fields.
+    fn get_transform(&self, dialog: &Dialog) -> Option<Transform> {
+        let operand = if let WidgetType::Edit(widget) = dialog.get_widget(self.operand) {
+            widget.get_value()
+        } else {
+            ""
+        };
+        let index = TransformDialog::get_index(dialog, self.operation);
+        Some(Transform {
+            operation: Operation::parse(index, operand)?,
+            width: Transform::WIDTHS[TransformDialog::get_index(dialog, self.width)],
+            little_endian: TransformDialog::get_index(dialog, self.order) == 0,
+        })
+    }
+}
+
+impl DialogHandler for TransformDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        item == self.btn_cancel || dialog.get_context(self.btn_ok).enabled
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_item_change(dialog, item);
+
+        // operand description
+        let index = TransformDialog::get_index(dialog, self.operation);
+        let operand = Operation::operand(index);
+        if let WidgetType::StaticText(text) = dialog.get_widget_mut(self.label) {
+            *text = operand.unwrap_or("No operand").to_string();
+        }
+        dialog.set_enabled(self.operand, operand.is_some());
+
+        // check parameters
+        let transform = self.get_transform(dialog);
+        let mut message = "";
+        if let (Some(range), Some(transform)) = (self.rctl.get(dialog), &transform) {
+            if let Err(err) = transform.validate(range.end - range.start) {
+                message = err;
+            }
+        }
+        if let WidgetType::StaticText(text) = dialog.get_widget_mut(self.message) {
+            *text = message.to_string();
+        }
+        let is_ok = self.rctl.get(dialog).is_some() && transform.is_some() && message.is_empty();
+        dialog.set_enabled(self.btn_ok, is_ok);
+    }
+
+    fn on_focus_lost(&mut self, dialog: &mut Dialog, item: ItemId) {
+        self.rctl.on_focus_lost(dialog, item);
+        self.on_item_change(dialog, item);
+    }
+}
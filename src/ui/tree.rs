@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MIT
+// Copyright (C) 2021 Artem Senichev <artemsen@gmail.com>
+
+use super::dialog::{Dialog, DialogHandler, DialogType, ItemId};
+use super::widget::{StandardButton, TextView, WidgetType};
+
+/// Action selected in the tree dialog.
+pub enum TreeAction {
+    /// Move cursor to the item.
+    Goto(usize),
+    /// Apply the extra action to the item: button of the action and index of
+    /// the item.
+    Action(StandardButton, usize),
+}
+
+/// Extra action button of the tree dialog.
+pub struct TreeButton {
+    /// Button of the action.
+    pub button: StandardButton,
+    /// Availability of the action for each item, empty if the action is
+    /// available for all items.
+    pub enabled: Vec<bool>,
+}
+
+/// Navigable tree dialog: list of items with go to and extra actions.
+pub struct TreeDialog {
+    view: ItemId,
+    btn_goto: ItemId,
+    /// Extra action buttons.
+    actions: Vec<(ItemId, TreeButton)>,
+    btn_close: ItemId,
+}
+
+impl TreeDialog {
+    /// Max number of visible lines of the list.
+    const MAX_LINES: usize = 16;
+    /// Min width of the dialog.
+    const MIN_WIDTH: usize = 40;
+
+    /// Show the tree dialog.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - dialog title
+    /// * `header` - header line of the list
+    /// * `lines` - text representation of the items
+    /// * `current` - index of the initially selected item
+    /// * `actions` - extra action buttons
+    ///
+    /// # Return value
+    ///
+    /// Action for the selected item.
+    pub fn show(
+        title: &str,
+        header: Option<String>,
+        lines: Vec<String>,
+        current: usize,
+        actions: Vec<TreeButton>,
+    ) -> Option<TreeAction> {
+        debug_assert!(current < lines.len());
+        debug_assert!(actions
+            .iter()
+            .all(|action| action.enabled.is_empty() || action.enabled.len() == lines.len()));
+
+        let buttons: usize = [StandardButton::Goto, StandardButton::Close]
+            .iter()
+            .chain(actions.iter().map(|action| &action.button))
+            .map(|button| button.text(false).len() + 1 /* space */)
+            .sum();
+        let width = lines
+            .iter()
+            .chain(header.iter())
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(TreeDialog::MIN_WIDTH)
+            .max(buttons)
+            + 1 /* scroll marks */;
+        let width = width.min(Dialog::max_width());
+        let height = lines.len().min(TreeDialog::MAX_LINES);
+
+        // create dialog
+        let mut dlg = Dialog::new(
+            width,
+            height + usize::from(header.is_some()),
+            DialogType::Normal,
+            title,
+        );
+        if let Some(header) = header {
+            dlg.add_line(WidgetType::StaticText(header));
+        }
+        let mut widget = TextView {
+            lines,
+            top: 0,
+            height,
+            current: None,
+        };
+        widget.select(current);
+        let view = dlg.add_line(WidgetType::TextView(widget));
+
+        // buttons
+        let btn_goto = dlg.add_button(StandardButton::Goto, true);
+        let actions = actions
+            .into_iter()
+            .map(|action| (dlg.add_button(action.button, false), action))
+            .collect();
+        let btn_close = dlg.add_button(StandardButton::Close, false);
+
+        // construct dialog handler
+        let mut handler = Self {
+            view,
+            btn_goto,
+            actions,
+            btn_close,
+        };
+        handler.on_item_change(&mut dlg, handler.view);
+
+        // show dialog
+        if let Some(id) = dlg.show(&mut handler) {
+            let index = handler.get_current(&dlg);
+            if let Some((_, action)) = handler.actions.iter().find(|(btn, _)| *btn == id) {
+                return Some(TreeAction::Action(action.button, index));
+            } else if id != handler.btn_close {
+                return Some(TreeAction::Goto(index));
+            }
+        }
+        None
+    }
+
+    /// Get index of the currently selected item.
+    fn get_current(&self, dialog: &Dialog) -> usize {
+        if let WidgetType::TextView(widget) = dialog.get_widget(self.view) {
+            widget.current.unwrap_or(0)
+        } else {
+            0
+        }
+    }
+}
+
+impl DialogHandler for TreeDialog {
+    fn on_close(&mut self, dialog: &mut Dialog, item: ItemId) -> bool {
+        if self.actions.iter().any(|(btn, _)| *btn == item) {
+            return dialog.get_context(item).enabled;
+        }
+        item == self.view || item == self.btn_goto || item == self.btn_close
+    }
+
+    fn on_item_change(&mut self, dialog: &mut Dialog, item: ItemId) {
+        if item == self.view {
+            let index = self.get_current(dialog);
+            for (btn, action) in &self.actions {
+                if !action.enabled.is_empty() {
+                    dialog.set_enabled(*btn, action.enabled[index]);
+                }
+            }
+        }
+    }
+}
@@ -14,6 +14,7 @@ pub enum WidgetType {
     ProgressBar(u8),
     Button(Button),
     Edit(InputLine),
+    TextView(TextView),
 }
 
 impl WidgetType {
@@ -56,6 +57,9 @@ impl WidgetType {
             WidgetType::Edit(widget) => {
                 return widget.draw(wnd, ctx);
             }
+            WidgetType::TextView(widget) => {
+                widget.draw(wnd, ctx);
+            }
         };
         None
     }
@@ -74,6 +78,7 @@ impl WidgetType {
             WidgetType::Edit(widget) => widget.key_press(key),
             WidgetType::CheckBox(widget) => widget.key_press(key),
             WidgetType::ListBox(widget) => widget.key_press(key),
+            WidgetType::TextView(widget) => widget.key_press(key),
             _ => false,
         }
     }
@@ -90,6 +95,7 @@ impl WidgetType {
                 | WidgetType::Button(_)
                 | WidgetType::ListBox(_)
                 | WidgetType::Edit(_)
+                | WidgetType::TextView(_)
         )
     }
 
@@ -237,6 +243,117 @@ impl ListBox {
     }
 }
 
+/// Multiline scrollable text viewer.
+#[derive(PartialEq)]
+pub struct TextView {
+    /// Text lines.
+    pub lines: Vec<String>,
+    /// Index of the first visible line.
+    pub top: usize,
+    /// Number of visible lines.
+    pub height: usize,
+    /// Index of the currently selected line, `None` for read-only view.
+    pub current: Option<usize>,
+}
+impl TextView {
+    /// Draw widget.
+    ///
+    /// # Arguments
+    ///
+    /// * `wnd` - window (canvas)
+    /// * `ctx` - widget context
+    pub fn draw(&self, wnd: &Window, ctx: &WidgetContext) {
+        let width = ctx.width - 1; // last column is used by scroll marks
+        for (y, line) in self
+            .lines
+            .iter()
+            .skip(self.top)
+            .take(self.height)
+            .enumerate()
+        {
+            let text: String = line.chars().take(width).collect();
+            wnd.print(ctx.x, ctx.y + y, &text);
+            if self.current == Some(self.top + y) {
+                let color = if ctx.focused {
+                    Color::Focused
+                } else {
+                    Color::Select
+                };
+                wnd.set_color(ctx.x, ctx.y + y, width, color);
+            }
+        }
+        if self.top > 0 {
+            wnd.print(ctx.x + width, ctx.y, "\u{25b2}");
+        }
+        if self.top + self.height < self.lines.len() {
+            wnd.print(ctx.x + width, ctx.y + self.height - 1, "\u{25bc}");
+        }
+        if ctx.focused && self.current.is_none() {
+            for y in 0..self.height {
+                wnd.set_color(ctx.x + width, ctx.y + y, 1, Color::Focused);
+            }
+        }
+    }
+
+    /// Keyboard input handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - pressed key
+    ///
+    /// # Return value
+    ///
+    /// `true` if key was handled.
+    pub fn key_press(&mut self, key: &KeyPress) -> bool {
+        if let Some(current) = self.current {
+            let max = self.lines.len().saturating_sub(1);
+            let current = match key.key {
+                Key::Up => current.saturating_sub(1),
+                Key::Down => current + 1,
+                Key::PageUp => current.saturating_sub(self.height),
+                Key::PageDown => current + self.height,
+                Key::Home => 0,
+                Key::End => max,
+                _ => {
+                    return false;
+                }
+            }
+            .min(max);
+            self.select(current);
+            return true;
+        }
+
+        let max = self.lines.len().saturating_sub(self.height);
+        self.top = match key.key {
+            Key::Up => self.top.saturating_sub(1),
+            Key::Down => self.top + 1,
+            Key::PageUp => self.top.saturating_sub(self.height),
+            Key::PageDown => self.top + self.height,
+            Key::Home => 0,
+            Key::End => max,
+            _ => {
+                return false;
+            }
+        }
+        .min(max);
+        true
+    }
+
+    /// Select line and scroll the view to make it visible.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the line to select
+    pub fn select(&mut self, index: usize) {
+        self.current = Some(index);
+        if index < self.top {
+            self.top = index;
+        } else if index >= self.top + self.height {
+            self.top = index + 1 - self.height;
+        }
+    }
+}
+
 /// Button.
 #[derive(PartialEq)]
 pub struct Button {
@@ -273,6 +390,16 @@ pub enum StandardButton {
     Retry,
     Yes,
     No,
+    Reload,
+    Keep,
+    Diff,
+    Save,
+    Close,
+    Goto,
+    Edit,
+    Extract,
+    Open,
+    Export,
 }
 impl StandardButton {
     /// Get text representation of the button.
@@ -291,6 +418,16 @@ impl StandardButton {
             StandardButton::Retry => "Retry",
             StandardButton::Yes => "Yes",
             StandardButton::No => "No",
+            StandardButton::Reload => "Reload",
+            StandardButton::Keep => "Keep mine",
+            StandardButton::Diff => "Diff",
+            StandardButton::Save => "Save",
+            StandardButton::Close => "Close",
+            StandardButton::Goto => "Go to",
+            StandardButton::Edit => "Edit",
+            StandardButton::Extract => "Extract",
+            StandardButton::Open => "Open",
+            StandardButton::Export => "Export",
         };
         format!(
             "{} {} {}",
@@ -414,14 +551,14 @@ impl InputLine {
         match key.key {
             Key::Up => {
                 if key.modifier & KeyPress::CTRL != 0 {
-                    self.from_history(false);
+                    self.load_history(false);
                     return true;
                 }
                 return false;
             }
             Key::Down => {
                 if key.modifier & KeyPress::CTRL != 0 {
-                    self.from_history(true);
+                    self.load_history(true);
                     return true;
                 }
                 return false;
@@ -456,7 +593,7 @@ impl InputLine {
 
     /// Focus set handler.
     pub fn on_focus_set(&mut self) {
-        self.move_cursor(isize::max_value());
+        self.move_cursor(isize::MAX);
         self.selection = true;
     }
 
@@ -508,7 +645,7 @@ impl InputLine {
                 // delete the entire selection
                 self.selection = false;
                 self.value.clear();
-                self.move_cursor(isize::min_value());
+                self.move_cursor(isize::MIN);
             }
 
             let byte_pos = self.char2byte(self.cursor);
@@ -525,7 +662,7 @@ impl InputLine {
             // delete the entire selection
             self.selection = false;
             self.value.clear();
-            self.move_cursor(isize::min_value());
+            self.move_cursor(isize::MIN);
         }
 
         let length = self.length();
@@ -537,7 +674,7 @@ impl InputLine {
             self.value.drain(byte_start..byte_end);
         } else if count < 0 && self.cursor > 0 {
             // delete to the left (backspace)
-            let remove = std::cmp::min(self.cursor, count.abs() as usize);
+            let remove = std::cmp::min(self.cursor, count.unsigned_abs());
             let byte_start = self.char2byte(self.cursor - remove);
             let byte_end = self.char2byte(self.cursor);
             self.value.drain(byte_start..byte_end);
@@ -546,7 +683,7 @@ impl InputLine {
     }
 
     /// Move through history.
-    fn from_history(&mut self, forward: bool) {
+    fn load_history(&mut self, forward: bool) {
         if forward && self.current + 1 < self.history.len() {
             self.current += 1;
         } else if !forward && self.current > 0 {
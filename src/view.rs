@@ -5,7 +5,10 @@ use super::ascii::Table;
 use super::config::Config;
 use super::curses::{Color, Window};
 use super::editor::Document;
+use super::image::Format;
+use super::minimap::{ByteClass, MapMode};
 use std::collections::BTreeSet;
+use std::ops::Range;
 use unicode_segmentation::UnicodeSegmentation;
 
 /// Document view.
@@ -14,6 +17,12 @@ pub struct View {
     pub fixed_width: bool,
     /// ASCII characters table (None hides the field).
     pub ascii_table: Option<&'static Table>,
+    /// Minimap mode (None hides the minimap).
+    pub minimap: Option<MapMode>,
+    /// Color bytes in the hex field by their class.
+    pub byte_colors: bool,
+    /// Selected cell of the minimap (keyboard navigation).
+    pub map_selected: Option<usize>,
 
     /// Max offset (file size).
     pub max_offset: u64,
@@ -40,6 +49,24 @@ pub struct View {
     pub changes: BTreeSet<u64>,
     /// Addresses of diff values on the current page.
     pub differs: BTreeSet<u64>,
+    /// Last appended range (follow mode).
+    pub appended: Range<u64>,
+    /// Addresses without data on the current page (memory image gaps).
+    pub gaps: BTreeSet<u64>,
+    /// Template fields on the current page: range and alternate color flag.
+    pub fields: Vec<(Range<u64>, bool)>,
+    /// Start addresses of the file regions (sections) on the current page.
+    pub boundaries: BTreeSet<u64>,
+}
+
+/// Layout of the view fields.
+pub struct Layout {
+    /// Size of the offset field.
+    pub offset_width: usize,
+    /// Number of bytes per line.
+    pub columns: usize,
+    /// Size of the hex field.
+    pub hex_width: usize,
 }
 
 impl View {
@@ -48,13 +75,18 @@ impl View {
     /// Length of a byte in hex representation.
     const HEX_LEN: usize = 2;
     /// Margin size between fields offset/hex/ascii.
-    const FIELD_MARGIN: usize = 3;
+    pub const FIELD_MARGIN: usize = 3;
     /// Margin between bytes in a word.
     const BYTE_MARGIN: usize = 1;
     /// Margin between word.
     const WORD_MARGIN: usize = 2;
     /// Number of bytes in a single word.
-    const BYTES_IN_WORD: usize = 4;
+    pub const BYTES_IN_WORD: usize = 4;
+
+    /// Width of the minimap strip.
+    const MAP_WIDTH: usize = 2;
+    /// Characters used to display entropy levels.
+    const MAP_SHADES: [char; 5] = [' ', '\u{2591}', '\u{2592}', '\u{2593}', '\u{2588}'];
 
     /// Min width of the screen.
     pub const MIN_WIDTH: usize = 30;
@@ -75,6 +107,9 @@ impl View {
         Self {
             fixed_width: config.fixed_width,
             ascii_table: config.ascii_table,
+            minimap: config.minimap,
+            byte_colors: config.byte_colors,
+            map_selected: None,
             max_offset: file_size,
             lines: 1,
             columns: 1,
@@ -86,24 +121,55 @@ impl View {
             data: Vec::new(),
             changes: BTreeSet::new(),
             differs: BTreeSet::new(),
+            appended: 0..0,
+            gaps: BTreeSet::new(),
+            fields: Vec::new(),
+            boundaries: BTreeSet::new(),
         }
     }
 
     /// Reinitialization.
     pub fn reinit(&mut self) {
-        let (width, height) = self.workspace.get_size();
+        let (mut width, height) = self.workspace.get_size();
+        if self.minimap.is_some() {
+            width -= View::MAP_WIDTH + 1 /* margin */;
+        }
+        let layout = View::layout(
+            width,
+            self.fixed_width,
+            self.ascii_table.is_some(),
+            self.max_offset,
+        );
+        self.lines = height;
+        self.columns = layout.columns;
+        self.offset_width = layout.offset_width;
+        self.hex_width = layout.hex_width;
+    }
 
+    /// Calculate the view scheme: sizes of the fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - width of the view
+    /// * `fixed_width` - line width mode (fixed/dynamic)
+    /// * `ascii` - ASCII field is visible
+    /// * `max_offset` - max offset (file size)
+    ///
+    /// # Return value
+    ///
+    /// Layout of the view fields.
+    pub fn layout(width: usize, fixed_width: bool, ascii: bool, max_offset: u64) -> Layout {
         // define size of the offset field
-        self.offset_width = 4; // minimum 4 digits (u16)
+        let mut offset_width = 4; // minimum 4 digits (u16)
         for i in (2..8).rev() {
-            if u64::max_value() << (i * 8) & self.max_offset != 0 {
-                self.offset_width = (i + 1) * 2;
+            if u64::MAX << (i * 8) & max_offset != 0 {
+                offset_width = (i + 1) * 2;
                 break;
             }
         }
 
         // calculate number of words per line
-        let words = if self.fixed_width {
+        let words = if fixed_width {
             View::FIXED_WIDTH
         } else {
             // calculate word width (number of chars per word)
@@ -111,16 +177,12 @@ impl View {
                 + (View::BYTES_IN_WORD - 1) * View::BYTE_MARGIN
                 + View::WORD_MARGIN;
 
-            let ascii_width = if self.ascii_table.is_some() {
-                View::BYTES_IN_WORD
-            } else {
-                0
-            };
+            let ascii_width = if ascii { View::BYTES_IN_WORD } else { 0 };
             let word_width = hex_width + ascii_width;
 
             // available space
-            let mut free_space = width - self.offset_width - View::FIELD_MARGIN;
-            if self.ascii_table.is_some() {
+            let mut free_space = width - offset_width - View::FIELD_MARGIN;
+            if ascii {
                 free_space -= View::FIELD_MARGIN - View::WORD_MARGIN;
             } else {
                 free_space += View::WORD_MARGIN;
@@ -131,27 +193,32 @@ impl View {
         };
         debug_assert_ne!(words, 0); // window too small?
 
-        self.lines = height;
-        self.columns = words * View::BYTES_IN_WORD;
+        let columns = words * View::BYTES_IN_WORD;
 
         // calculate hex field size
         let word_width =
             View::BYTES_IN_WORD * View::HEX_LEN + (View::BYTES_IN_WORD - 1) * View::BYTE_MARGIN;
-        self.hex_width = words * word_width + (words - 1) * View::WORD_MARGIN;
+        let hex_width = words * word_width + (words - 1) * View::WORD_MARGIN;
 
         // increase the offset length if possible
         let data_width = View::FIELD_MARGIN
-            + self.hex_width
-            + self.offset_width
-            + if self.ascii_table.is_some() {
-                View::FIELD_MARGIN + self.columns
+            + hex_width
+            + offset_width
+            + if ascii {
+                View::FIELD_MARGIN + columns
             } else {
                 0
             };
-        if data_width < width && self.offset_width < 8 {
+        if data_width < width && offset_width < 8 {
             let free_space = width - data_width;
-            let max_offset_len = 8 - self.offset_width;
-            self.offset_width += free_space.min(max_offset_len);
+            let max_offset_len = 8 - offset_width;
+            offset_width += free_space.min(max_offset_len);
+        }
+
+        Layout {
+            offset_width,
+            columns,
+            hex_width,
         }
     }
 
@@ -192,6 +259,9 @@ impl View {
             self.draw_ascii(doc);
         }
         self.highlight(doc);
+        if let Some(mode) = self.minimap {
+            self.draw_minimap(doc, mode);
+        }
         self.workspace.refresh();
     }
 
@@ -212,8 +282,31 @@ impl View {
             } else {
                 1
             }) as u8;
+        if let Some(map) = &doc.minimap {
+            if let Some(cell) = self.map_selected {
+                let range = map.range(cell);
+                stat = format!(" \u{2502} map 0x{:x}-0x{:x}", range.start, range.end - 1);
+            } else if !map.is_complete() {
+                stat = format!(" \u{2502} map {}%", map.progress());
+            }
+        }
+        if let Some(file_type) = doc.file_type {
+            stat += &format!(" \u{2502} {}", file_type);
+        }
+        if let Some((range, name)) = doc.region(doc.cursor.offset) {
+            stat += &format!(" \u{2502} {}", name);
+            if doc.cursor.offset != range.start {
+                stat += &format!("+0x{:x}", doc.cursor.offset - range.start);
+            }
+        }
+        if doc.file.format != Format::Raw {
+            stat += &format!(" \u{2502} {}", doc.file.format.id());
+        }
+        if doc.follow {
+            stat += " \u{2502} follow";
+        }
         if let Some(table) = self.ascii_table {
-            stat = format!(" \u{2502} {}", table.id);
+            stat += &format!(" \u{2502} {}", table.id);
         };
         stat += &format!(
             " \u{2502} 0x{offset:04x} = 0x{value:02x} {value:<3} 0{value:<3o} {value:08b} \u{2502} {percent:>3}%",
@@ -302,7 +395,10 @@ impl View {
                         text.push(' '); // word delimiter
                     }
                 }
-                if let Some(&byte) = self.data.get((offset + x as u64 - self.offset) as usize) {
+                let byte_offset = offset + x as u64;
+                if self.gaps.contains(&byte_offset) {
+                    text.push_str("--"); // no data
+                } else if let Some(&byte) = self.data.get((byte_offset - self.offset) as usize) {
                     text.push_str(&format!("{:02x}", byte));
                 } else {
                     text.push_str("  "); // fill with spaces for highlighting
@@ -323,6 +419,42 @@ impl View {
                 self.workspace
                     .set_color(col_x, y, View::HEX_LEN, Color::HexNormHi);
             }
+
+            // color bytes by class, current line and column are not colored
+            if self.byte_colors && cursor_y != y {
+                for x in (0..self.columns).filter(|&x| x != cursor_x) {
+                    let byte_offset = offset + x as u64;
+                    if self.gaps.contains(&byte_offset) {
+                        continue;
+                    }
+                    if let Some(&byte) = self.data.get((byte_offset - self.offset) as usize) {
+                        let col_x =
+                            left_pos + x * (View::BYTES_IN_WORD - 1) + x / View::BYTES_IN_WORD;
+                        self.workspace
+                            .set_color(col_x, y, View::HEX_LEN, View::byte_color(byte));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Get color of the byte in the hex field by its class.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - byte value
+    ///
+    /// # Return value
+    ///
+    /// Color identifier.
+    fn byte_color(byte: u8) -> Color {
+        match byte {
+            0x00 => Color::ByteZero,
+            b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c => Color::ByteSpace,
+            0x21..=0x7e => Color::BytePrint,
+            0x01..=0x1f | 0x7f => Color::ByteControl,
+            0xff => Color::ByteFF,
+            _ => Color::ByteHigh,
         }
     }
 
@@ -348,7 +480,9 @@ impl View {
             let text = (0..self.columns)
                 .map(|i| {
                     let index = (offset + i as u64 - self.offset) as usize;
-                    if let Some(&byte) = self.data.get(index) {
+                    if self.gaps.contains(&(offset + i as u64)) {
+                        ' '
+                    } else if let Some(&byte) = self.data.get(index) {
                         ascii_table.charset[byte as usize]
                     } else {
                         ' '
@@ -370,7 +504,54 @@ impl View {
         }
     }
 
-    /// Highlight changes and diffs.
+    /// Print the minimap strip.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc` - document to render
+    /// * `mode` - minimap mode
+    fn draw_minimap(&self, doc: &Document, mode: MapMode) {
+        let (width, height) = self.workspace.get_size();
+        let left_pos = width - View::MAP_WIDTH;
+        let map = doc.minimap.as_ref();
+        let cursor = map.map(|map| map.cell(doc.cursor.offset));
+
+        for y in 0..height {
+            let slice = map.and_then(|map| map.slices.get(y));
+            let (ch, color) = match (slice, mode) {
+                (Some(slice), MapMode::Entropy) => {
+                    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                    let level = ((slice.entropy / 8.0 * View::MAP_SHADES.len() as f64) as usize)
+                        .min(View::MAP_SHADES.len() - 1);
+                    (View::MAP_SHADES[level], Color::Minimap)
+                }
+                (Some(slice), MapMode::Class) => (
+                    ' ',
+                    match slice.class {
+                        ByteClass::Zero => Color::MinimapZero,
+                        ByteClass::Text => Color::MinimapText,
+                        ByteClass::High => Color::MinimapHigh,
+                        ByteClass::Random => Color::MinimapRandom,
+                        ByteClass::Binary => Color::Minimap,
+                    },
+                ),
+                (None, _) if map.is_some_and(|map| y < map.cells) => ('\u{b7}', Color::Minimap),
+                (None, _) => (' ', Color::Minimap),
+            };
+            let mut text: String = std::iter::repeat_n(ch, View::MAP_WIDTH).collect();
+            if cursor == Some(y) {
+                text.replace_range(..ch.len_utf8(), "\u{25c0}");
+            }
+            self.workspace.color_on(color);
+            self.workspace.print(left_pos, y, &text);
+            if self.map_selected == Some(y) {
+                self.workspace
+                    .set_color(left_pos, y, View::MAP_WIDTH, Color::Focused);
+            }
+        }
+    }
+
+    /// Highlight template fields, region boundaries, changes and diffs.
     ///
     /// # Arguments
     ///
@@ -379,6 +560,71 @@ impl View {
         // calculate cursor position (indexes within the page data)
         let cursor_x = (doc.cursor.offset % self.columns as u64) as usize;
         let cursor_y = (doc.cursor.offset - self.offset) as usize / self.columns;
+        let page_end = self.offset + self.data.len() as u64;
+
+        // highlight template fields, current line and column are not colored
+        for (range, alt) in &self.fields {
+            let color = if *alt { Color::FieldAlt } else { Color::Field };
+            for offset in range.start.max(self.offset)..range.end.min(page_end) {
+                let cx = offset as usize % self.columns;
+                let cy = (offset - self.offset) as usize / self.columns;
+                if cx == cursor_x || cy == cursor_y {
+                    continue;
+                }
+                if let Some((x, y)) = self.get_position(offset, true) {
+                    self.workspace.set_color(x, y, View::HEX_LEN, color);
+                }
+                if self.ascii_table.is_some() {
+                    if let Some((x, y)) = self.get_position(offset, false) {
+                        self.workspace.set_color(x, y, 1, color);
+                    }
+                }
+            }
+        }
+
+        // highlight start of the file regions
+        for &offset in &self.boundaries {
+            let cx = offset as usize % self.columns;
+            let cy = (offset - self.offset) as usize / self.columns;
+            if cx == cursor_x || cy == cursor_y {
+                continue;
+            }
+            if let Some((x, y)) = self.get_position(offset, true) {
+                self.workspace
+                    .set_color(x, y, View::HEX_LEN, Color::Boundary);
+            }
+            if self.ascii_table.is_some() {
+                if let Some((x, y)) = self.get_position(offset, false) {
+                    self.workspace.set_color(x, y, 1, Color::Boundary);
+                }
+            }
+        }
+
+        // highlight appended data
+        let start = self.appended.start.max(self.offset);
+        let end = self.appended.end.min(page_end);
+        for offset in start..end {
+            let cx = offset as usize % self.columns;
+            let cy = (offset - self.offset) as usize / self.columns;
+            if let Some((x, y)) = self.get_position(offset, true) {
+                let color = if cx == cursor_x || cy == cursor_y {
+                    Color::HexNewHi
+                } else {
+                    Color::HexNew
+                };
+                self.workspace.set_color(x, y, View::HEX_LEN, color);
+            }
+            if self.ascii_table.is_some() {
+                if let Some((x, y)) = self.get_position(offset, false) {
+                    let color = if cx == cursor_x || cy == cursor_y {
+                        Color::AsciiNewHi
+                    } else {
+                        Color::AsciiNew
+                    };
+                    self.workspace.set_color(x, y, 1, color);
+                }
+            }
+        }
 
         // highlight diff
         for &offset in &self.differs {